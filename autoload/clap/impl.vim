@@ -98,7 +98,11 @@ function! s:on_typed_async_impl() abort
 
   if clap#filter#async#external#using_maple()
     if g:clap.provider.id ==# 'blines'
-      call clap#filter#async#dyn#start_directly(clap#maple#blines_command())
+      if clap#job#daemon#is_running() && !has_key(g:clap.context, 'blame')
+        call clap#provider#blines#send_query()
+      else
+        call clap#provider#blines#start_maple()
+      endif
       return
     endif
 
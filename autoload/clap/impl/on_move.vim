@@ -31,6 +31,10 @@ function! s:filer_handle(decoded) abort
   endif
 endfunction
 
+" Providers whose filtering is done by the daemon, the results are handled by
+" clap#provider#{provider_id}#daemon_handle().
+let s:daemon_filter_providers = ['blines', 'lines', 'help_tags', 'tags', 'lsp_symbols']
+
 function! clap#impl#on_move#daemon_handle(msg) abort
   let decoded = json_decode(a:msg)
 
@@ -39,6 +43,13 @@ function! clap#impl#on_move#daemon_handle(msg) abort
     return
   endif
 
+  " The filtered results of the providers filtered by the daemon.
+  if index(s:daemon_filter_providers, get(decoded, 'provider_id', '')) > -1
+        \ && !has_key(decoded, 'type')
+    call clap#provider#{decoded.provider_id}#daemon_handle(decoded)
+    return
+  endif
+
   if has_key(decoded, 'error')
     call clap#helper#echo_error('[daemon_handle] '.decoded.error)
     return
@@ -55,7 +66,9 @@ function! clap#impl#on_move#daemon_handle(msg) abort
     catch
       return
     endtry
-    if has_key(decoded, 'fname')
+    if has_key(decoded, 'syntax')
+      call g:clap.preview.set_syntax(decoded.syntax)
+    elseif has_key(decoded, 'fname')
       call g:clap.preview.set_syntax(clap#ext#into_filetype(decoded.fname))
     endif
     call clap#preview#highlight_header()
@@ -69,16 +82,27 @@ endfunction
 function! s:send_preview_request() abort
   let s:req_id += 1
   let curline = g:clap.display.getcurline()
-  let msg = json_encode({
-      \ 'id': s:req_id,
-      \ 'method': 'client.on_move',
-      \ 'params': {
+  let params = {
       \   'cwd': g:clap.provider.id ==# 'filer' ? clap#provider#filer#current_dir() : clap#rooter#working_dir(),
       \   'curline': curline,
       \   'enable_icon': s:enable_icon,
       \   'provider_id': g:clap.provider.id,
       \   'preview_size': clap#preview#size_of(g:clap.provider.id),
-      \ },
+      \   'source_fpath': expand('#'.g:clap.start.bufnr.':p'),
+      \   'source_bufnr': g:clap.start.bufnr,
+      \ }
+  if g:clap.provider.id ==# 'help_tags'
+    let params.runtimepath = &runtimepath
+  elseif g:clap.provider.id ==# 'blines'
+    let source_index = clap#provider#blines#source_index()
+    if source_index >= 0
+      let params.source_index = source_index
+    endif
+  endif
+  let msg = json_encode({
+      \ 'id': s:req_id,
+      \ 'method': 'client.on_move',
+      \ 'params': params,
       \ })
   call clap#job#daemon#send_message(msg)
 endfunction
@@ -90,6 +114,18 @@ function! clap#impl#on_move#send_params(params) abort
   call clap#job#daemon#send_message(json_encode(params))
 endfunction
 
+" Sends the message of which no response is expected, the request id is left
+" alone to not drop the response of the pending request.
+function! clap#impl#on_move#send_notification(method, params) abort
+  if clap#job#daemon#is_running()
+    call clap#job#daemon#send_message(json_encode({
+          \ 'id': 0,
+          \ 'method': a:method,
+          \ 'params': a:params,
+          \ }))
+  endif
+endfunction
+
 function! s:sync_run_with_delay() abort
   if s:on_move_timer != -1
     call timer_stop(s:on_move_timer)
@@ -97,14 +133,30 @@ function! s:sync_run_with_delay() abort
   let s:on_move_timer = timer_start(s:on_move_delay, { -> g:clap.provider._().on_move() })
 endfunction
 
+" Providers whose preview is implemented in the Rust binary.
+let s:rust_preview_providers = [
+      \ 'filer', 'files', 'grep', 'grep2',
+      \ 'tags', 'proj_tags', 'tagfiles', 'lsp_symbols', 'blines', 'buffers',
+      \ 'help_tags',
+      \ 'quickfix', 'loclist', 'git_diff_files', 'commits', 'bcommits',
+      \ ]
+
 if clap#maple#is_available()
+  function! s:has_rust_preview() abort
+    return index(s:rust_preview_providers, g:clap.provider.id) > -1
+  endfunction
+
   function! s:dispatch_on_move_impl() abort
-    if index(['filer', 'files', 'grep', 'grep2'], g:clap.provider.id) > -1
+    if s:has_rust_preview()
       return s:send_preview_request()
     endif
     call s:sync_run_with_delay()
   endfunction
 else
+  function! s:has_rust_preview() abort
+    return v:false
+  endfunction
+
   function! s:dispatch_on_move_impl() abort
     call s:sync_run_with_delay()
   endfunction
@@ -114,7 +166,7 @@ function! clap#impl#on_move#invoke() abort
   if get(g:, '__clap_has_no_matches', v:false)
     return
   endif
-  if has_key(g:clap.provider._(), 'on_move')
+  if has_key(g:clap.provider._(), 'on_move') || s:has_rust_preview()
     call s:dispatch_on_move_impl()
   endif
 endfunction
@@ -34,6 +34,12 @@ function! clap#maple#is_available() abort
   return s:maple_bin isnot v:null
 endfunction
 
+" Returns the maple binary along with the global options shared by all the subcommands.
+function! s:maple_cmd() abort
+  let algo = get(g:, 'clap_maple_algo', v:null)
+  return algo is v:null ? [s:maple_bin] : [s:maple_bin, '--algo', algo]
+endfunction
+
 function! s:on_complete() abort
   " At initial vim8.2, bufwinid(popup_bufnr) seemingly does not work as expected. Ref #223.
   " if bufwinid(g:clap.display.bufnr) == -1
@@ -84,6 +90,11 @@ function! s:on_complete() abort
 
   call clap#state#refresh_matches_count(decoded.total)
 
+  " The annotations are appended so that the indices of matched elements are still valid.
+  if has_key(decoded, 'annotations')
+    call map(decoded.lines, 'v:val."  [".decoded.annotations[v:key]."]"')
+  endif
+
   if s:has_converter
     call g:clap.display.set_lines(map(decoded.lines, 's:Converter(v:val)'))
   else
@@ -193,7 +204,7 @@ function! clap#maple#forerunner_exec_command(cmd) abort
 
   let subcommand = ['exec', a:cmd, '--cmd-dir', clap#rooter#working_dir(), '--output-threshold', clap#filter#capacity()]
 
-  return [s:maple_bin] + global_opt + subcommand
+  return s:maple_cmd() + global_opt + subcommand
 endfunction
 
 " Returns the filtered results after the input stream is complete.
@@ -204,12 +215,12 @@ function! clap#maple#sync_filter_command(query) abort
     call add(global_opt, '--icon-painter=File')
   endif
 
-  return [s:maple_bin] + global_opt + ['filter', a:query, '--sync']
+  return s:maple_cmd() + global_opt + ['filter', a:query, '--sync']
 endfunction
 
 function! clap#maple#tags_forerunner_command() abort
   let global_opt = has_key(g:clap.context, 'no-cache') ? ['--no-cache'] : []
-  return [s:maple_bin] + global_opt + ['tags', '', clap#rooter#working_dir(), '--forerunner']
+  return s:maple_cmd() + global_opt + ['tags', '', clap#rooter#working_dir(), '--forerunner']
 endfunction
 
 function! clap#maple#ripgrep_forerunner_command() abort
@@ -220,12 +231,16 @@ function! clap#maple#ripgrep_forerunner_command() abort
     call add(global_opt, '--no-cache')
   endif
 
-  return [s:maple_bin] + global_opt + ['ripgrep-forerunner', '--cmd-dir', clap#rooter#working_dir(), '--output-threshold', clap#filter#capacity()]
+  return s:maple_cmd() + global_opt + ['ripgrep-forerunner', '--cmd-dir', clap#rooter#working_dir(), '--output-threshold', clap#filter#capacity()]
 endfunction
 
 function! clap#maple#blines_command() abort
   let blines_subcmd = ['--number', g:clap.display.preload_capacity, '--winwidth', winwidth(g:clap.display.winid), 'blines', g:clap.input.get(), expand('#'.g:clap.start.bufnr.':p')]
-  return [s:maple_bin] + blines_subcmd
+  " `:Clap blines +blame`
+  if has_key(g:clap.context, 'blame')
+    call add(blines_subcmd, '--blame')
+  endif
+  return s:maple_cmd() + blines_subcmd
 endfunction
 
 function! clap#maple#run_exec(cmd) abort
@@ -234,7 +249,7 @@ function! clap#maple#run_exec(cmd) abort
     call add(global_opt, '--icon-painter=File')
   endif
   let subcommand = ['exec', a:cmd, '--cmd-dir', clap#rooter#working_dir()]
-  call clap#maple#job_start([s:maple_bin] + global_opt + subcommand)
+  call clap#maple#job_start(s:maple_cmd() + global_opt + subcommand)
 endfunction
 
 function! clap#maple#run_sync_grep(cmd, query, enable_icon, glob) abort
@@ -250,15 +265,15 @@ function! clap#maple#run_sync_grep(cmd, query, enable_icon, glob) abort
     let subcommand += ['--glob', a:glob]
   endif
 
-  call clap#maple#job_start([s:maple_bin] + global_opt + subcommand)
+  call clap#maple#job_start(s:maple_cmd() + global_opt + subcommand)
 endfunction
 
 function! clap#maple#build_cmd(...) abort
-  return [s:maple_bin] + a:000
+  return s:maple_cmd() + a:000
 endfunction
 
 function! clap#maple#build_cmd_list(cmd_list) abort
-  return insert(a:cmd_list, s:maple_bin)
+  return s:maple_cmd() + a:cmd_list
 endfunction
 
 let &cpoptions = s:save_cpo
@@ -7,13 +7,49 @@ set cpoptions&vim
 let s:bcommits = {}
 
 function! s:bcommits.source() abort
-  call g:clap.abort('Not implemented yet')
+  let s:git_root = clap#path#get_git_root()
+  if empty(s:git_root)
+    call g:clap.abort('Not in git repository')
+    return
+  endif
+
+  let s:current = expand('#'.g:clap.start.bufnr.':p')
+  if empty(s:current)
+    call g:clap.abort('The current buffer is not a file')
+    return
+  endif
+
+  " The renames of the file are followed.
+  if clap#maple#is_available()
+    return shellescape(clap#maple#binary()).' commits --cmd-dir '.shellescape(s:git_root).' --file '.shellescape(s:current)
+  endif
+  return 'git log --color=never --date=short ''--format=%cd %h %s (%an)'' --follow -- '.shellescape(s:current)
 endfunction
 
+" Opens the diff of the selected commit for the current file in a scratch buffer, the rename of
+" the file is included.
 function! s:bcommits.sink(line) abort
-  call g:clap.abort('Not implemented yet')
+  let rev = get(split(a:line), 1, '')
+  if rev !~# '^\x\+$'
+    return
+  endif
+
+  let cmd = 'git -C '.shellescape(s:git_root).' log -1 -p -M --follow --color=never '.rev.' -- '.shellescape(s:current)
+  let diff = systemlist(cmd)
+  if v:shell_error
+    call clap#helper#echo_error('Failed to show the commit '.rev.': '.join(diff, ' '))
+    return
+  endif
+
+  belowright new
+  setlocal buftype=nofile bufhidden=wipe noswapfile nobuflisted
+  call setline(1, diff)
+  setlocal nomodifiable filetype=git
+  execute 'silent! file' fnameescape('[bcommits] '.rev.' '.fnamemodify(s:current, ':t'))
 endfunction
 
+let s:bcommits.syntax = 'clap_commits'
+
 let g:clap#provider#bcommits# = s:bcommits
 
 let &cpoptions = s:save_cpo
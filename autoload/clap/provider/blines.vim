@@ -6,9 +6,27 @@ set cpoptions&vim
 
 let s:blines = {}
 
+" The query and 0-based line numbers of the lines filtered by maple.
+let s:filtered = {'query': '', 'source_indices': []}
+
+" Returns the 0-based line number of the current line filtered by maple, -1 if
+" the lines are filtered by Vim, in which case the line number is displayed
+" as part of the line.
+function! clap#provider#blines#source_index() abort
+  let query = g:clap.input.get()
+  if empty(query) || s:filtered.query !=# query
+    return -1
+  endif
+  return get(s:filtered.source_indices, g:clap.display.getcurlnum() - 1, -1)
+endfunction
+
 function! s:blines.sink(selected) abort
-  let lnum = matchstr(a:selected, '^\s*\(\d\+\) ')
-  let lnum = str2nr(trim(lnum))
+  let source_index = clap#provider#blines#source_index()
+  if source_index >= 0
+    let lnum = source_index + 1
+  else
+    let lnum = str2nr(trim(matchstr(a:selected, '^\s*\(\d\+\) ')))
+  endif
   call g:clap.start.goto_win()
   " Push the current position to the jumplist
   normal! m'
@@ -25,6 +43,128 @@ function! s:blines.source() abort
   return clap#provider#blines#format(g:clap.start.get_lines())
 endfunction
 
+" Displays the lines filtered by maple, which are prefixed with the line
+" numbers from `source_indices`, the line numbers are not matched.
+function! s:process_filter_result(result) abort
+  let result = a:result
+  let s:filtered = {'query': g:clap.input.get(), 'source_indices': result.source_indices}
+  let prefixes = map(copy(result.source_indices), 'printf(''%4d '', v:val + 1)')
+  call map(result.lines, 'prefixes[v:key].v:val')
+  for idx in range(len(result.indices))
+    let offset = len(prefixes[idx])
+    call map(result.indices[idx], 'v:val + offset')
+  endfor
+  let truncated_map = {}
+  for [lnum, line] in items(get(result, 'truncated_map', {}))
+    let truncated_map[lnum] = prefixes[lnum - 1].line
+  endfor
+  let result.truncated_map = truncated_map
+  " The annotations are appended so that the indices of matched elements are still valid.
+  if has_key(result, 'annotations')
+    call map(result.lines, 'v:val."  [".result.annotations[v:key]."]"')
+  endif
+  call clap#state#process_filter_result(result)
+endfunction
+
+function! s:handle_maple_message(msg) abort
+  if !g:clap.display.win_is_valid() || g:clap.input.get() !=# s:last_query
+    return
+  endif
+  let decoded = json_decode(a:msg)
+  if has_key(decoded, 'source_indices')
+    call s:process_filter_result(decoded)
+  elseif has_key(decoded, 'total')
+    call clap#indicator#set_matches_number(decoded.total)
+  endif
+endfunction
+
+" Filters the file of buffer via maple if the daemon is not running or in the
+" blame mode.
+function! clap#provider#blines#start_maple() abort
+  let s:last_query = g:clap.input.get()
+  call clap#job#stdio#start_service(function('s:handle_maple_message'), clap#maple#blines_command())
+endfunction
+
+" The changedtick and lines of the buffers kept in the daemon.
+let s:sent_buffers = get(s:, 'sent_buffers', {})
+
+" Returns the change replacing the lines of `old` with `new` that differ, the
+" common leading and trailing lines are excluded.
+function! s:line_change(old, new) abort
+  let old_len = len(a:old)
+  let new_len = len(a:new)
+  let start = 0
+  while start < old_len && start < new_len && a:old[start] ==# a:new[start]
+    let start += 1
+  endwhile
+  let end = 0
+  while end < old_len - start && end < new_len - start
+        \ && a:old[old_len - end - 1] ==# a:new[new_len - end - 1]
+    let end += 1
+  endwhile
+  return {
+        \ 'lnum': start + 1,
+        \ 'end': old_len - end + 1,
+        \ 'lines': new_len - end > start ? a:new[start : new_len - end - 1] : [],
+        \ }
+endfunction
+
+" Filters the lines kept in the daemon, the whole buffer is sent at the first
+" time, then only the changed lines are sent, so that the unsaved changes and
+" [No Name] buffer work.
+function! clap#provider#blines#send_query() abort
+  let bufnr = g:clap.start.bufnr
+  let changedtick = getbufvar(bufnr, 'changedtick')
+  let params = {
+        \ 'bufnr': bufnr,
+        \ 'changedtick': changedtick,
+        \ 'query': g:clap.input.get(),
+        \ 'number': g:clap.display.preload_capacity,
+        \ 'winwidth': winwidth(g:clap.display.winid),
+        \ 'version': 2,
+        \ }
+  let sent = get(s:sent_buffers, bufnr, {})
+  if empty(sent) || sent.changedtick != changedtick
+    let lines = getbufline(bufnr, 1, '$')
+    if empty(sent)
+      let params.lines = lines
+    else
+      let params.prev_changedtick = sent.changedtick
+      let params.changes = [s:line_change(sent.lines, lines)]
+    endif
+    let s:sent_buffers[bufnr] = {'changedtick': changedtick, 'lines': lines}
+  endif
+  call clap#impl#on_move#send_params({'method': 'blines', 'params': params})
+endfunction
+
+" Evicts the deleted buffer kept in the daemon.
+function! clap#provider#blines#on_buf_delete(bufnr) abort
+  if !has_key(s:sent_buffers, a:bufnr)
+    return
+  endif
+  call remove(s:sent_buffers, a:bufnr)
+  call clap#impl#on_move#send_notification('delete_buffer', {'bufnr': a:bufnr})
+endfunction
+
+function! clap#provider#blines#daemon_handle(decoded) abort
+  if has_key(a:decoded, 'error')
+    " The lines kept in the daemon are outdated, send the whole buffer again.
+    if get(a:decoded.error, 'outdated', v:false)
+      silent! call remove(s:sent_buffers, a:decoded.error.bufnr)
+      call clap#provider#blines#send_query()
+    else
+      call clap#helper#echo_error('[blines] '.a:decoded.error.message)
+    endif
+    return
+  endif
+
+  if empty(g:clap.input.get())
+    return
+  endif
+
+  call s:process_filter_result(clap#state#from_items(a:decoded.result))
+endfunction
+
 function! s:blines.on_move() abort
   let items = split(g:clap.display.getcurline())
   if empty(items)
@@ -38,6 +178,7 @@ function! s:blines.on_move() abort
 endfunction
 
 function! s:blines.on_enter() abort
+  let s:filtered = {'query': '', 'source_indices': []}
   let s:origin_syntax = getbufvar(g:clap.start.bufnr, '&syntax')
   call g:clap.display.setbufvar('&syntax', 'clap_blines')
 endfunction
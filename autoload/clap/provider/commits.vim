@@ -13,6 +13,11 @@ function! s:commits.source() abort
     return
   endif
 
+  " maple walks the history via libgit2, which is much faster for the large history.
+  if clap#maple#is_available()
+    return shellescape(clap#maple#binary()).' commits --cmd-dir '.shellescape(s:git_root)
+  endif
+
   let source = 'git log ''--color=never'' ''--date=short'' ''--format=%cd %h%d %s (%an)'' --graph'
   let current = bufname(g:clap.start.bufnr)
   let managed = 0
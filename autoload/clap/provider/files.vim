@@ -33,6 +33,8 @@ function! s:files.source() abort
 
   if has_key(g:clap.context, 'name-only')
     let g:__clap_builtin_content_filtering_enum = 'FileNameOnly'
+  else
+    let g:__clap_builtin_content_filtering_enum = 'FilePath'
   endif
 
   if has_key(g:clap.context, 'finder')
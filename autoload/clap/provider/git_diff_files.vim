@@ -7,27 +7,41 @@ set cpoptions&vim
 let s:git_diff_files = {}
 
 function! s:git_diff_files.source() abort
-  if !executable('git')
+  if clap#maple#is_available()
+    let cmd = shellescape(clap#maple#binary()).' git-files --changed'
+  elseif executable('git')
+    let cmd = 'git status -s -uno'
+  else
     call clap#helper#echo_error('git executable not found')
     return []
   endif
 
-  let changed = systemlist('git status -s -uno')
+  " Each line has the status marker of `git status --short`, e.g., `M  src/lib.rs`.
+  let changed = systemlist(cmd)
   if v:shell_error
-    call clap#helper#echo_error('Error occurs on calling `git status -s -uno`, maybe you are not in a git repo.')
+    call clap#helper#echo_error('Error occurs on calling `'.cmd.'`, maybe you are not in a git repo.')
     return []
   else
-    return map(changed, 'split(v:val)[-1]')
+    return changed
   endif
 endfunction
 
+" Strip the status marker, the renamed file `R  old -> new` is opened by the new path.
+function! s:extract_path(line) abort
+  return split(a:line[3:], ' -> ')[-1]
+endfunction
+
+" The preview is done by maple if it's available, this is the fallback without maple.
 function! s:git_diff_files_on_move() abort
-  let filediff = systemlist('git diff '.g:clap.display.getcurline())
+  let filediff = systemlist('git diff -- '.shellescape(s:extract_path(g:clap.display.getcurline())))
   call g:clap.preview.show(filediff)
   call g:clap.preview.set_syntax('diff')
 endfunction
 
-let s:git_diff_files.sink = 'e'
+function! s:git_diff_files.sink(selected) abort
+  execute 'edit' fnameescape(s:extract_path(a:selected))
+endfunction
+
 let s:git_diff_files.enable_rooter = v:true
 let s:git_diff_files.on_move = function('s:git_diff_files_on_move')
 
@@ -6,7 +6,10 @@ set cpoptions&vim
 
 let s:git_files = {}
 
-if executable('git')
+" maple reads the git index directly, which is much faster than spawning git for the large repo.
+if clap#maple#is_available()
+  let s:git_files.source = shellescape(clap#maple#binary()).' git-files'
+elseif executable('git')
   let s:git_files.source = 'git ls-files '.(has('win32') ? '' : ' | uniq')
 else
   let s:git_files.source = ['git executable not found']
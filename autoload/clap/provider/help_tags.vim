@@ -82,6 +82,47 @@ let s:help_tags = {}
 let s:help_tags.sink = function('s:help_tags_sink')
 let s:help_tags.source = function('s:help_tags_source')
 
+" The helptags are cached and filtered by the daemon if it's running.
+if clap#maple#is_available()
+
+  function! s:send_query() abort
+    call clap#impl#on_move#send_params({
+          \ 'method': 'help_tags',
+          \ 'params': {
+          \   'query': g:clap.input.get(),
+          \   'doc_tags': join(s:get_doc_tags(), ','),
+          \   'runtimepath': &runtimepath,
+          \   'number': g:clap.display.preload_capacity,
+          \   'winwidth': winwidth(g:clap.display.winid),
+          \ }})
+  endfunction
+
+  function! clap#provider#help_tags#daemon_handle(decoded) abort
+    if has_key(a:decoded, 'error')
+      call clap#helper#echo_error('[help_tags] '.a:decoded.error.message)
+      return
+    endif
+    call clap#state#process_filter_result(a:decoded.result)
+  endfunction
+
+  function! s:help_tags.init() abort
+    if clap#job#daemon#is_running()
+      call s:send_query()
+    else
+      call g:clap.provider.init_default_impl()
+    endif
+  endfunction
+
+  function! s:help_tags.on_typed() abort
+    if clap#job#daemon#is_running()
+      call s:send_query()
+    else
+      call clap#impl#on_typed()
+    endif
+  endfunction
+
+endif
+
 let g:clap#provider#help_tags# = s:help_tags
 
 let &cpoptions = s:save_cpo
@@ -8,10 +8,21 @@ set cpoptions&vim
 
 let s:lines = {}
 
+" changedtick of the buffers kept in the daemon.
+let s:sent_buffers = get(s:, 'sent_buffers', {})
+
+" [bufnr, lnum] of the displayed lines filtered by the daemon.
+let s:positions = []
+
 function! s:lines.sink(selected) abort
-  let splitted = split(a:selected)
-  let bufnr = splitted[0][1:-2]
-  let lnum = str2nr(splitted[2])
+  let position = get(s:positions, g:clap.display.getcurlnum() - 1, [])
+  if !empty(position)
+    let [bufnr, lnum] = position
+  else
+    let splitted = split(a:selected)
+    let bufnr = splitted[0][1:-2]
+    let lnum = str2nr(splitted[2])
+  endif
   execute 'b' bufnr
   silent call cursor(lnum, 1)
   normal! ^zvzz
@@ -73,6 +84,83 @@ function! s:lines.source() abort
   return extend(cur, rest)
 endfunction
 
+" The buffers are only sent when they are changed since the last time, the
+" lines of the unmodified buffers are read by maple from the file.
+function! s:buffer_sources() abort
+  let cur = bufnr('')
+  let sources = []
+  for b in [cur] + filter(s:buflisted(), 'v:val != cur')
+    let bufname = empty(bufname(b)) ? '[No Name '.b.']' : fnamemodify(bufname(b), ':~:.')
+    let changedtick = getbufvar(b, 'changedtick', 0)
+    let source = {'bufnr': b, 'bufname': bufname, 'changedtick': changedtick}
+    if get(s:sent_buffers, b, -1) != changedtick
+      let path = fnamemodify(bufname(b), ':p')
+      if !empty(bufname(b)) && !getbufvar(b, '&modified') && filereadable(path)
+        let source.path = path
+      else
+        let source.lines = getbufline(b, 1, '$')
+      endif
+      let s:sent_buffers[b] = changedtick
+    endif
+    call add(sources, source)
+  endfor
+  return sources
+endfunction
+
+function! s:send_query() abort
+  call clap#impl#on_move#send_params({
+        \ 'method': 'lines',
+        \ 'params': {
+        \   'query': g:clap.input.get(),
+        \   'buffers': s:buffer_sources(),
+        \   'number': g:clap.display.preload_capacity,
+        \   'winwidth': winwidth(g:clap.display.winid),
+        \ }})
+endfunction
+
+" Evicts the deleted buffer kept in the daemon.
+function! clap#provider#lines#on_buf_delete(bufnr) abort
+  if has_key(s:sent_buffers, a:bufnr)
+    call remove(s:sent_buffers, a:bufnr)
+    call clap#impl#on_move#send_notification('delete_buffer', {'bufnr': a:bufnr})
+  endif
+endfunction
+
+function! clap#provider#lines#daemon_handle(decoded) abort
+  if has_key(a:decoded, 'error')
+    " The lines kept in the daemon are outdated, send the buffer again.
+    if get(a:decoded.error, 'outdated', v:false)
+      silent! call remove(s:sent_buffers, a:decoded.error.bufnr)
+      call s:send_query()
+    else
+      call clap#helper#echo_error('[lines] '.a:decoded.error.message)
+    endif
+    return
+  endif
+  let s:positions = get(a:decoded.result, 'positions', [])
+  call clap#state#process_filter_result(a:decoded.result)
+endfunction
+
+" Filter all the buffers in parallel in maple if the daemon is running.
+function! s:lines.init() abort
+  let s:positions = []
+  if clap#job#daemon#is_running()
+    " The files could be changed outside since the last time.
+    let s:sent_buffers = {}
+    call s:send_query()
+  else
+    call g:clap.provider.init_default_impl()
+  endif
+endfunction
+
+function! s:lines.on_typed() abort
+  if clap#job#daemon#is_running()
+    call s:send_query()
+  else
+    call clap#impl#on_typed()
+  endif
+endfunction
+
 let s:lines.syntax = 'clap_lines'
 let g:clap#provider#lines# = s:lines
 
@@ -0,0 +1,72 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: Symbols of the workspace provided by the language servers configured in the [lsp] section of maple config.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:lsp_symbols = {}
+
+" The daemon keeps the language server running for each project root.
+function! s:send_query() abort
+  call clap#impl#on_move#send_params({
+        \ 'method': 'lsp_symbols',
+        \ 'params': {
+        \   'cwd': clap#rooter#working_dir(),
+        \   'query': g:clap.input.get(),
+        \   'number': g:clap.display.preload_capacity,
+        \   'winwidth': winwidth(g:clap.display.winid),
+        \ }})
+endfunction
+
+function! clap#provider#lsp_symbols#daemon_handle(decoded) abort
+  if has_key(a:decoded, 'error')
+    call g:clap.display.set_lines([a:decoded.error.message])
+    return
+  endif
+  call clap#state#process_filter_result(a:decoded.result)
+endfunction
+
+" The language server is started for each query without the daemon.
+function! s:start_maple() abort
+  let cmd = clap#maple#build_cmd_list([
+        \ '--number', g:clap.display.preload_capacity,
+        \ '--winwidth', winwidth(g:clap.display.winid),
+        \ 'lsp-symbols', '--dir', clap#rooter#working_dir(), g:clap.input.get()])
+  call clap#filter#async#dyn#start_directly(cmd)
+endfunction
+
+function! s:query() abort
+  if clap#job#daemon#is_running()
+    call s:send_query()
+  else
+    call s:start_maple()
+  endif
+endfunction
+
+function! s:lsp_symbols.init() abort
+  let g:__clap_builtin_content_filtering_enum = 'TagNameOnly'
+  call s:query()
+endfunction
+
+function! s:lsp_symbols.on_typed() abort
+  call s:query()
+endfunction
+
+function! s:lsp_symbols.sink(selected) abort
+  let [lnum, path] = clap#provider#proj_tags#extract_position(a:selected)
+  call clap#sink#open_file(path, lnum, 1)
+endfunction
+
+function! s:lsp_symbols.on_exit() abort
+  if exists('g:__clap_builtin_content_filtering_enum')
+    unlet g:__clap_builtin_content_filtering_enum
+  endif
+endfunction
+
+let s:lsp_symbols.support_open_action = v:true
+let s:lsp_symbols.syntax = 'clap_proj_tags'
+
+let g:clap#provider#lsp_symbols# = s:lsp_symbols
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
@@ -28,12 +28,19 @@ function! s:proj_tags.init() abort
   endif
 endfunction
 
-function! s:extract(tag_row) abort
-  let lnum = matchstr(a:tag_row, '^.*:\zs\(\d\+\)')
-  let path = matchstr(a:tag_row, '\[.*@\zs\(\f*\)\ze\]')
+" The fields of tag line are delimited by tab:
+" `{name}:{lnum}\t[{language}:{kind}@{path}]\t{scope_kind} {scope} {signature}\t{text}`.
+function! clap#provider#proj_tags#extract_position(tag_row) abort
+  let fields = split(a:tag_row, "\t", 1)
+  let lnum = str2nr(matchstr(fields[0], ':\zs\d\+\ze\s*$'))
+  let path = len(fields) > 1 ? matchstr(fields[1], '^\[.\{-}@\zs.*\ze\]\s*$') : ''
   return [lnum, path]
 endfunction
 
+function! s:extract(tag_row) abort
+  return clap#provider#proj_tags#extract_position(a:tag_row)
+endfunction
+
 function! s:proj_tags.sink(selected) abort
   let [lnum, path] = s:extract(a:selected)
   call clap#sink#open_file(path, lnum, 1)
@@ -0,0 +1,62 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: Tags of the existing tags files in 'tags', e.g., maintained by gutentags.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:tagfiles = {}
+
+" Returns the tags files of the buffer where clap is invoked from.
+function! s:tag_files() abort
+  let files = []
+  if exists('*win_execute')
+    call win_execute(g:clap.start.winid, 'let files = tagfiles()')
+  else
+    let files = tagfiles()
+  endif
+  return map(files, 'fnamemodify(v:val, '':p'')')
+endfunction
+
+function! s:start_maple() abort
+  let cmd = clap#maple#build_cmd_list([
+        \ '--number', g:clap.display.preload_capacity,
+        \ '--winwidth', winwidth(g:clap.display.winid),
+        \ 'tagfiles', g:clap.input.get()] + s:files)
+  call clap#filter#async#dyn#start_directly(cmd)
+endfunction
+
+function! s:tagfiles.init() abort
+  let g:__clap_builtin_content_filtering_enum = 'TagNameOnly'
+  let s:files = s:tag_files()
+  if empty(s:files)
+    call g:clap.display.set_lines(['No tags file found in ''tags'''])
+    return
+  endif
+  call s:start_maple()
+endfunction
+
+function! s:tagfiles.on_typed() abort
+  if !empty(s:files)
+    call s:start_maple()
+  endif
+endfunction
+
+" The line number of the tag located by pattern is resolved by maple.
+function! s:tagfiles.sink(selected) abort
+  let [lnum, path] = clap#provider#proj_tags#extract_position(a:selected)
+  call clap#sink#open_file(path, lnum, 1)
+endfunction
+
+function! s:tagfiles.on_exit() abort
+  if exists('g:__clap_builtin_content_filtering_enum')
+    unlet g:__clap_builtin_content_filtering_enum
+  endif
+endfunction
+
+let s:tagfiles.support_open_action = v:true
+let s:tagfiles.syntax = 'clap_proj_tags'
+
+let g:clap#provider#tagfiles# = s:tagfiles
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
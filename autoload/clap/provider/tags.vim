@@ -6,6 +6,50 @@ set cpoptions&vim
 
 let s:tags = {}
 
+" Use maple to extract the symbols if vista.vim is not installed, the symbols
+" are extracted and filtered by the daemon.
+function! s:send_query() abort
+  call clap#impl#on_move#send_params({
+        \ 'method': 'buffer_tags',
+        \ 'params': {
+        \   'fpath': expand('#'.g:clap.start.bufnr.':p'),
+        \   'query': g:clap.input.get(),
+        \   'number': g:clap.display.preload_capacity,
+        \   'winwidth': winwidth(g:clap.display.winid),
+        \ }})
+endfunction
+
+function! clap#provider#tags#daemon_handle(decoded) abort
+  if has_key(a:decoded, 'error')
+    call g:clap.display.set_lines([a:decoded.error.message])
+    return
+  endif
+  let result = a:decoded.result
+  " The symbols are displayed as a tree if the query is empty.
+  if has_key(result, 'indices')
+    call clap#state#process_filter_result(result)
+  elseif empty(result.lines)
+    call g:clap.display.set_lines(['No symbols found via maple'])
+  else
+    call g:clap.display.set_lines(result.lines)
+    call clap#state#refresh_matches_count(result.total)
+    call g:clap#display_win.shrink_if_undersize()
+  endif
+endfunction
+
+function! s:maple_sink(selected) abort
+  let lnum = str2nr(matchstr(split(a:selected, "\t")[0], ':\zs\d\+\ze\s*$'))
+  call g:clap.start.goto_win()
+  " Push the current position to the jumplist
+  normal! m'
+  silent call cursor(lnum, 1)
+  normal! ^zvzz
+endfunction
+
+function! s:using_maple() abort
+  return !exists('g:loaded_vista') && clap#maple#is_available() && clap#job#daemon#is_running()
+endfunction
+
 function! s:tags.source(...) abort
   let [bufnr, winnr, fname, fpath] = [
         \ g:clap.start.bufnr,
@@ -42,6 +86,23 @@ function! s:tags.source(...) abort
   return vista#finder#PrepareSource(data)
 endfunction
 
+function! s:tags.init() abort
+  if s:using_maple()
+    call s:send_query()
+  else
+    call g:clap.provider.init_default_impl()
+  endif
+endfunction
+
+function! s:tags.on_typed() abort
+  if s:using_maple()
+    call s:send_query()
+  else
+    call clap#impl#on_typed()
+  endif
+endfunction
+
+" The preview is done by maple if it's available, this is the fallback without maple.
 function! s:tags.on_move() abort
   try
     let [lnum, tag] = vista#finder#fzf#extract(g:clap.display.getcurline())
@@ -63,7 +124,13 @@ function! s:tags.on_exit() abort
   endif
 endfunction
 
-let s:tags.sink = function('vista#finder#fzf#sink')
+function! s:tags.sink(selected) abort
+  if s:using_maple()
+    call s:maple_sink(a:selected)
+  else
+    call vista#finder#fzf#sink(a:selected)
+  endif
+endfunction
 
 let g:clap#provider#tags# = s:tags
 
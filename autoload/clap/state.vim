@@ -35,6 +35,47 @@ function! clap#state#handle_message(msg) abort
   endif
 endfunction
 
+" Displays the filtered result of the daemon, i.e., total, lines, indices
+" and truncated_map.
+function! clap#state#process_filter_result(result) abort
+  let result = a:result
+  if result.total == 0
+    call g:clap.display.set_lines([g:clap_no_matches_msg])
+    call clap#indicator#set_matches_number(0)
+    call clap#sign#disable_cursorline()
+    call g:clap#display_win.shrink_if_undersize()
+    call g:clap.preview.hide()
+    return
+  endif
+
+  call clap#state#refresh_matches_count(result.total)
+  call g:clap.display.set_lines(result.lines)
+  call clap#highlight#add_fuzzy_async(result.indices)
+  let g:__clap_lines_truncated_map = result.truncated_map
+  call clap#sign#reset_to_first_line()
+  call g:clap#display_win.shrink_if_undersize()
+endfunction
+
+" Converts the filtered result in the output schema version 2, i.e., `items`,
+" into the parallel `lines`, `indices` and `truncated_map` along with
+" `source_indices`.
+function! clap#state#from_items(result) abort
+  let items = a:result.items
+  let truncated_map = {}
+  for idx in range(len(items))
+    if items[idx].truncated_from isnot v:null
+      let truncated_map[idx + 1] = items[idx].text
+    endif
+  endfor
+  return {
+        \ 'total': a:result.total,
+        \ 'lines': map(copy(items), 'v:val.display'),
+        \ 'indices': map(copy(items), 'v:val.indices'),
+        \ 'source_indices': map(copy(items), 'v:val.source_index'),
+        \ 'truncated_map': truncated_map,
+        \ }
+endfunction
+
 " Returns the cached source tmp file.
 "
 " Write the providers whose `source` is list-style into a tempfile.
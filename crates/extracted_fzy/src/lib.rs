@@ -9,6 +9,9 @@ use crate::scoring_utils::*;
 
 pub type MatchWithPositions = (Score, Vec<usize>);
 
+/// Haystacks longer than this are scored by the linear matcher instead of the score matrices.
+const MAX_MATRIX_HAYSTACK_LENGTH: usize = 1024;
+
 pub fn match_and_score_with_positions(needle: &str, haystack: &str) -> Option<MatchWithPositions> {
     match matches(needle, haystack) {
         Some(needle_length) => {
@@ -32,7 +35,7 @@ pub fn match_and_score_with_positions(needle: &str, haystack: &str) -> Option<Ma
 /// ```
 #[inline]
 fn matches(needle: &str, haystack: &str) -> Option<usize> {
-    if needle.is_empty() || needle == haystack {
+    if needle.is_empty() {
         return Some(0);
     }
 
@@ -62,9 +65,9 @@ fn score_with_positions(needle: &str, needle_length: usize, haystack: &str) -> (
         return (SCORE_MAX, (0..needle_length).collect());
     }
 
-    // unreasonably large haystack
-    if haystack_length > 1024 {
-        return (SCORE_MIN, vec![]);
+    // unreasonably large haystack for the matrices
+    if haystack_length > MAX_MATRIX_HAYSTACK_LENGTH {
+        return score_linear(needle, haystack);
     }
 
     #[allow(non_snake_case)]
@@ -103,6 +106,76 @@ fn score_with_positions(needle: &str, needle_length: usize, haystack: &str) -> (
     (M.get(needle_length - 1, haystack_length - 1), positions)
 }
 
+/// Scores the large haystack in linear time, the needle is matched at the earliest possible end
+/// and then backward from there for the shortest window.
+///
+/// The window is scored the same way with the matrices except that the bonus of consecutive
+/// match is not compared with the bonus of the char, the leading and trailing gaps are counted
+/// up to `MAX_MATRIX_HAYSTACK_LENGTH` so that the long lines are not ranked last for the position
+/// of match.
+fn score_linear(needle: &str, haystack: &str) -> (Score, Vec<usize>) {
+    let needle = needle.chars().collect::<Vec<_>>();
+    let haystack = haystack.chars().collect::<Vec<_>>();
+
+    let mut i = 0;
+    let mut end = 0;
+    for (j, &h) in haystack.iter().enumerate() {
+        if eq(needle[i], h) {
+            i += 1;
+            if i == needle.len() {
+                end = j;
+                break;
+            }
+        }
+    }
+
+    let mut positions = vec![0_usize; needle.len()];
+    for j in (0..=end).rev() {
+        if i > 0 && eq(needle[i - 1], haystack[j]) {
+            i -= 1;
+            positions[i] = j;
+        }
+    }
+
+    (score_window(&haystack, &positions), positions)
+}
+
+/// Scores the given char `positions` of `haystack` the same way with `score_linear()`, the
+/// positions are expected to be sorted, e.g., the indices matched by other means.
+///
+/// Returns `SCORE_MAX` if the positions cover the whole haystack, as the perfect match does.
+pub fn score_positions(haystack: &str, positions: &[usize]) -> Score {
+    let haystack = haystack.chars().collect::<Vec<_>>();
+    if positions.is_empty() {
+        return SCORE_MIN;
+    }
+    if positions.len() == haystack.len() {
+        return SCORE_MAX;
+    }
+    score_window(&haystack, positions)
+}
+
+fn score_window(haystack: &[char], positions: &[usize]) -> Score {
+    let gap = |len: usize| score_from_usize(len.min(MAX_MATRIX_HAYSTACK_LENGTH));
+    let mut score = score_mul(gap(positions[0]), SCORE_GAP_LEADING);
+    for (i, &j) in positions.iter().enumerate() {
+        if i > 0 && positions[i - 1] + 1 == j {
+            score = score_add(score, SCORE_MATCH_CONSECUTIVE);
+        } else {
+            if i > 0 {
+                score = score_add(
+                    score,
+                    score_mul(gap(j - positions[i - 1] - 1), SCORE_GAP_INNER),
+                );
+            }
+            let prev = if j > 0 { haystack[j - 1] } else { '/' };
+            score = score_add(score, bonus_for_char(prev, haystack[j]));
+        }
+    }
+    let trailing = haystack.len() - 1 - positions[positions.len() - 1];
+    score_add(score, score_mul(gap(trailing), SCORE_GAP_TRAILING))
+}
+
 fn calculate_score(
     needle: &str,
     needle_length: usize,
@@ -230,3 +303,24 @@ impl Matrix {
         }
     }
 }
+
+#[test]
+fn test_perfect_match() {
+    // The perfect match used to be skipped as an empty needle, which is the worst score without
+    // any positions.
+    let (score, positions) = match_and_score_with_positions("filer", "filer").unwrap();
+    assert_eq!(score, SCORE_MAX);
+    assert_eq!(positions, vec![0, 1, 2, 3, 4]);
+
+    let (partial, _) = match_and_score_with_positions("filer", "filer.rs").unwrap();
+    assert!(score > partial);
+}
+
+#[test]
+fn test_score_positions() {
+    for (needle, haystack) in [("flr", "src/filer.rs"), ("cmdrpc", "src/cmd/rpc/mod.rs")].iter() {
+        let (score, positions) = match_and_score_with_positions(needle, haystack).unwrap();
+        assert_eq!(score_positions(haystack, &positions), score);
+    }
+    assert_eq!(score_positions("filer", &[0, 1, 2, 3, 4]), SCORE_MAX);
+}
@@ -0,0 +1,228 @@
+//! Acronym matching on top of fzy, the query is matched as the prefixes of at least two words in
+//! order, e.g., `mcl` matches `maple_cli` and `MapleCommandLine`.
+//!
+//! A word starts after a non-alphanumeric char, at an uppercase char following a lowercase one,
+//! or at the last uppercase char of a run followed by a lowercase one, e.g., `HTTPServer` is
+//! `HTTP` and `Server`. The acronym is scored by the positions like fzy, so the gaps between the
+//! words are penalised, plus `WORD_BONUS` for each word after the first one.
+
+use crate::{fuzzy_indices_fzy, ScorerOutput};
+use extracted_fzy::score_positions;
+
+/// Bonus of each word matched after the first one, the start of a word is worth a consecutive
+/// match of fzy at most, so the prefix of a word is still ahead of the acronym of the same chars.
+const WORD_BONUS: i64 = 20;
+
+/// Lines longer than this are matched by fzy only, the matrices of acronym are
+/// `O(query * line)`.
+const MAX_LINE_LENGTH: usize = 1024;
+
+/// Compares two characters case-insensitively.
+#[inline]
+fn eq(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Returns whether each char of `chars` starts a word.
+fn word_starts(chars: &[char]) -> Vec<bool> {
+    chars
+        .iter()
+        .enumerate()
+        .map(|(j, &c)| {
+            if !c.is_alphanumeric() {
+                return false;
+            }
+            let prev = match j {
+                0 => return true,
+                _ => chars[j - 1],
+            };
+            let next_is_lowercase = match chars.get(j + 1) {
+                Some(next) => next.is_lowercase(),
+                None => false,
+            };
+            !prev.is_alphanumeric()
+                || (prev.is_lowercase() && c.is_uppercase())
+                || (prev.is_uppercase() && c.is_uppercase() && next_is_lowercase)
+        })
+        .collect()
+}
+
+/// Best partial match of the query chars up to `i` with the char `i` matched at `j`.
+#[derive(Clone, Copy)]
+struct Cell {
+    /// Position of the first query char, the later the shorter the match is.
+    first: usize,
+    /// Position of the query char `i - 1` and whether it's in multiple words.
+    prev: (usize, bool),
+}
+
+/// Returns the indices of the shortest match of `needle` as the prefixes of at least two words
+/// in `chars`, along with the number of words.
+///
+/// Each query char either continues the match of the previous char or starts a word, which is
+/// tracked for all the positions so that the prefix of a word is not matched greedily, e.g.,
+/// `abc` matches `a` and `bc` of `abd_bc`.
+fn match_words(needle: &[char], chars: &[char]) -> Option<(Vec<usize>, usize)> {
+    let (m, n) = (needle.len(), chars.len());
+    let word_starts = word_starts(chars);
+    // cells[multiple][i * n + j], `multiple` is whether the chars up to `i` are in multiple words.
+    let mut cells = vec![vec![None::<Cell>; m * n]; 2];
+
+    for j in 0..n {
+        if word_starts[j] && eq(needle[0], chars[j]) {
+            cells[0][j] = Some(Cell {
+                first: j,
+                prev: (j, false),
+            });
+        }
+    }
+
+    for i in 1..m {
+        // The latest start of the matches of char `i - 1` before `j - 1`.
+        let mut best_before: Option<(usize, usize, bool)> = None;
+        for j in 1..n {
+            if j >= 2 {
+                for &multiple in &[false, true] {
+                    if let Some(cell) = cells[multiple as usize][(i - 1) * n + j - 2] {
+                        let later = match best_before {
+                            Some((first, _, _)) => cell.first > first,
+                            None => true,
+                        };
+                        if later {
+                            best_before = Some((cell.first, j - 2, multiple));
+                        }
+                    }
+                }
+            }
+            if !eq(needle[i], chars[j]) {
+                continue;
+            }
+            for &multiple in &[false, true] {
+                let consecutive = cells[multiple as usize][(i - 1) * n + j - 1].map(|cell| Cell {
+                    first: cell.first,
+                    prev: (j - 1, multiple),
+                });
+                let new_word = if multiple && word_starts[j] {
+                    best_before.map(|(first, k, prev_multiple)| Cell {
+                        first,
+                        prev: (k, prev_multiple),
+                    })
+                } else {
+                    None
+                };
+                cells[multiple as usize][i * n + j] = match (consecutive, new_word) {
+                    (Some(a), Some(b)) if b.first > a.first => Some(b),
+                    (Some(a), _) => Some(a),
+                    (None, b) => b,
+                };
+            }
+        }
+    }
+
+    let (mut j, _) = (0..n)
+        .filter_map(|j| cells[1][(m - 1) * n + j].map(|cell| (j, j - cell.first)))
+        .min_by_key(|(_, span)| *span)?;
+
+    let mut indices = vec![0; m];
+    let mut words = 1;
+    let mut multiple = true;
+    for i in (0..m).rev() {
+        indices[i] = j;
+        let cell = cells[multiple as usize][i * n + j].expect("matched cell exists; qed");
+        if i > 0 && cell.prev.0 + 1 < j {
+            words += 1;
+        }
+        j = cell.prev.0;
+        multiple = cell.prev.1;
+    }
+
+    Some((indices, words))
+}
+
+/// Make the arguments order same to Skim's `fuzzy_indices()`.
+///
+/// The acronym match is taken if it's scored higher than fzy.
+pub fn acronym_indices(line: &str, query: &str) -> ScorerOutput {
+    let matched = fuzzy_indices_fzy(line, query)?;
+
+    let needle = query.chars().collect::<Vec<_>>();
+    let chars = line.chars().collect::<Vec<_>>();
+    if needle.len() < 2 || chars.len() > MAX_LINE_LENGTH {
+        return Some(matched);
+    }
+
+    match match_words(&needle, &chars) {
+        Some((indices, words)) => {
+            let score = score_positions(line, &indices) as i64 + (words - 1) as i64 * WORD_BONUS;
+            if score > matched.0 {
+                Some((score, indices))
+            } else {
+                Some(matched)
+            }
+        }
+        None => Some(matched),
+    }
+}
+
+#[test]
+fn test_match_words() {
+    let words = |line: &str, query: &str| {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        match_words(&chars(query), &chars(line))
+    };
+    assert_eq!(words("maple_cli", "mcl"), Some((vec![0, 6, 7], 2)));
+    assert_eq!(words("MapleCommandLine", "mcl"), Some((vec![0, 5, 12], 3)));
+    assert_eq!(
+        words("crates/maple_cli/src/lib.rs", "mcl"),
+        Some((vec![7, 13, 14], 2))
+    );
+    assert_eq!(words("camel", "mcl"), None);
+
+    // The prefix of a single word is no acronym.
+    assert_eq!(words("abc.rs", "ab"), None);
+
+    // The last uppercase of a run starts a word.
+    assert_eq!(words("HTTPServer", "hs"), Some((vec![0, 4], 2)));
+    assert_eq!(words("HTTPServer", "htse"), Some((vec![0, 1, 4, 5], 2)));
+    assert_eq!(words("HTTPS", "hs"), None);
+
+    // Not matched greedily.
+    assert_eq!(words("abd_bc", "abc"), Some((vec![0, 4, 5], 2)));
+
+    // The shortest match is taken.
+    assert_eq!(words("foo_bar/foo_baz", "fb"), Some((vec![0, 4], 2)));
+    assert_eq!(words("foo_xxx_bar_baz", "fbb"), Some((vec![0, 8, 12], 3)));
+}
+
+#[test]
+fn test_acronym_indices() {
+    let fzy = |line: &str, query: &str| fuzzy_indices_fzy(line, query).unwrap();
+
+    // The single word is matched by fzy.
+    assert_eq!(acronym_indices("abc.rs", "ab"), Some(fzy("abc.rs", "ab")));
+    assert_eq!(acronym_indices("maple", "m"), Some(fzy("maple", "m")));
+
+    // The gaps between the words are penalised.
+    let (close, _) = acronym_indices("a_b.rs", "ab").unwrap();
+    let (far, _) = acronym_indices("a_long_word_then_b.rs", "ab").unwrap();
+    assert!(close > far);
+
+    // The acronym is preferred to the scattered match.
+    let (acronym, indices) = acronym_indices("MapleCommandLine", "mcl").unwrap();
+    assert_eq!(indices, vec![0, 5, 12]);
+    let (scattered, _) = acronym_indices("my_calculus.rs", "mcl").unwrap();
+    assert!(acronym > scattered);
+    assert!(acronym > fzy("MapleCommandLine", "mcl").0);
+}
+
+#[test]
+fn test_prefix_against_acronym() {
+    let score = |line: &str, query: &str| acronym_indices(line, query).unwrap().0;
+
+    // The prefix of a single word is still ahead of the acronym.
+    assert!(score("mclaren.txt", "mcl") > score("MapleCommandLine", "mcl"));
+    assert!(score("src/filer.rs", "fil") > score("src/find_in_lines.rs", "fil"));
+
+    // The acronym is ahead of the match in the middle of a word.
+    assert!(score("MapleCommandLine", "mcl") > score("tramcline.rs", "mcl"));
+}
@@ -1,5 +1,6 @@
-use crate::{fuzzy_indices_skim, substr_indices, ScorerOutput};
-use extracted_fzy::match_and_score_with_positions;
+use crate::tag_qualifier::TagQualifiers;
+use crate::{acronym_indices, fuzzy_indices_skim, substr_indices, typo_indices, ScorerOutput};
+use extracted_fzy::{match_and_score_with_positions, score_positions};
 use pattern::{file_name_only, strip_grep_filepath, tag_name_only};
 
 /// Make the arguments order same to Skim's `fuzzy_indices()`.
@@ -32,6 +33,22 @@ pub(super) fn apply_substr_on_grep_line(line: &str, query: &str) -> ScorerOutput
     })
 }
 
+#[inline]
+pub(super) fn apply_typo_on_grep_line(line: &str, query: &str) -> ScorerOutput {
+    strip_grep_filepath(line).and_then(|(truncated_line, offset)| {
+        typo_indices(truncated_line, query)
+            .map(|(score, indices)| (score, indices.into_iter().map(|x| x + offset).collect()))
+    })
+}
+
+#[inline]
+pub(super) fn apply_acronym_on_grep_line(line: &str, query: &str) -> ScorerOutput {
+    strip_grep_filepath(line).and_then(|(truncated_line, offset)| {
+        acronym_indices(truncated_line, query)
+            .map(|(score, indices)| (score, indices.into_iter().map(|x| x + offset).collect()))
+    })
+}
+
 #[inline]
 pub(super) fn apply_skim_on_file_line(line: &str, query: &str) -> ScorerOutput {
     file_name_only(line).and_then(|(truncated_line, offset)| {
@@ -56,19 +73,236 @@ pub(super) fn apply_substr_on_file_line(line: &str, query: &str) -> ScorerOutput
     })
 }
 
+#[inline]
+pub(super) fn apply_typo_on_file_line(line: &str, query: &str) -> ScorerOutput {
+    file_name_only(line).and_then(|(truncated_line, offset)| {
+        typo_indices(truncated_line, query)
+            .map(|(score, indices)| (score, indices.into_iter().map(|x| x + offset).collect()))
+    })
+}
+
+#[inline]
+pub(super) fn apply_acronym_on_file_line(line: &str, query: &str) -> ScorerOutput {
+    file_name_only(line).and_then(|(truncated_line, offset)| {
+        acronym_indices(truncated_line, query)
+            .map(|(score, indices)| (score, indices.into_iter().map(|x| x + offset).collect()))
+    })
+}
+
+/// Bonus of matching the last segment of query in the file name, which is worth a consecutive
+/// match of fzy.
+const FILE_NAME_BONUS: i64 = 200;
+
+/// Returns the non-empty components of path `line` along with the char offset of each one.
+fn path_components(line: &str) -> Vec<(usize, &str)> {
+    let mut components = Vec::new();
+    // (byte offset, char offset) of the current component.
+    let mut start = (0, 0);
+    for (char_idx, (byte_idx, c)) in line.char_indices().enumerate() {
+        if c == '/' || c == '\\' {
+            components.push((start.1, &line[start.0..byte_idx]));
+            start = (byte_idx + c.len_utf8(), char_idx + 1);
+        }
+    }
+    components.push((start.1, &line[start.0..]));
+    components.retain(|(_, component)| !component.is_empty());
+    components
+}
+
+/// Scores the char indices of a line positionally, used to put the scores of the segments matched
+/// in the separate components on the same scale with the score of the full line.
+type PositionalScorer = fn(&str, &[usize]) -> i64;
+
+#[inline]
+fn fzy_positional_score(line: &str, indices: &[usize]) -> i64 {
+    score_positions(line, indices) as i64
+}
+
+/// Matches the segments of query against the path components from right to left, each segment
+/// has to be matched within a single component and the components are in the order of segments.
+///
+/// The last segment is matched against the file name first, ignoring the extension if possible,
+/// which gains `FILE_NAME_BONUS`.
+///
+/// With `positional`, the score is the positional score of all the indices on the full line,
+/// plus the adjustments of `scorer` that are not positional, e.g., the penalty of typos and the
+/// bonus of acronyms,
+/// otherwise it's the sum of the scores of segments.
+fn match_path_components(
+    line: &str,
+    segments: &[&str],
+    scorer: &impl Fn(&str, &str) -> ScorerOutput,
+    positional: Option<PositionalScorer>,
+) -> ScorerOutput {
+    let components = path_components(line);
+    let (file_offset, file_name) = *components.last()?;
+    let shift = |indices: Vec<usize>, offset: usize| -> Vec<usize> {
+        indices.into_iter().map(|x| x + offset).collect()
+    };
+    // The part of segment score that is not positional, the score is kept as is if there is no
+    // positional scorer.
+    let adjust = |s: i64, matched: &str, indices: &[usize]| -> i64 {
+        positional.map_or(s, |positional| s - positional(matched, indices))
+    };
+
+    let mut score = 0i64;
+    let mut matched_indices = Vec::with_capacity(segments.len());
+    // Only `components[..end]` can be matched by the next segment.
+    let mut end = components.len();
+
+    for (i, segment) in segments.iter().rev().enumerate() {
+        if i == 0 {
+            let stem = file_name
+                .rfind('.')
+                .filter(|&dot| dot > 0)
+                .map(|dot| &file_name[..dot]);
+            let file_name_match = stem
+                .and_then(|stem| scorer(stem, segment).map(|matched| (stem, matched)))
+                .or_else(|| scorer(file_name, segment).map(|matched| (file_name, matched)));
+            end -= 1;
+            if let Some((matched, (s, indices))) = file_name_match {
+                score += adjust(s, matched, &indices) + FILE_NAME_BONUS;
+                matched_indices.push(shift(indices, file_offset));
+                continue;
+            }
+        }
+
+        let (idx, s, indices) =
+            components[..end]
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(idx, (offset, component))| {
+                    scorer(component, segment).map(|(s, indices)| {
+                        (idx, adjust(s, component, &indices), shift(indices, *offset))
+                    })
+                })?;
+        score += s;
+        matched_indices.push(indices);
+        end = idx;
+    }
+
+    matched_indices.reverse();
+    // The separator between the segments matched in the adjacent components is matched as well.
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut indices = Vec::with_capacity(chars.len());
+    for segment_indices in matched_indices {
+        if let (Some(&last), Some(&next)) = (indices.last(), segment_indices.first()) {
+            if last + 2 == next && (chars[last + 1] == '/' || chars[last + 1] == '\\') {
+                indices.push(last + 1);
+            }
+        }
+        indices.extend(segment_indices);
+    }
+    if let Some(positional) = positional {
+        score += positional(line, &indices);
+    }
+    Some((score, indices))
+}
+
+/// Applies `scorer` on the path components, the query is split into segments on `/`, e.g.,
+/// `maple/rpc/filer` matches `crates/maple_cli/src/cmd/rpc/filer.rs`.
+///
+/// Falls back to applying `scorer` on the full line if the segments can't be matched in the
+/// components, so the indices are always in the full line. The scorers based on fzy pass
+/// `positional` to score the components on the full line as the fallback does, so the better one
+/// of both is taken, the scores of Skim and SubString are roughly additive over the segments and
+/// are summed.
+#[inline]
+fn apply_on_path_line(
+    line: &str,
+    query: &str,
+    scorer: impl Fn(&str, &str) -> ScorerOutput,
+    positional: Option<PositionalScorer>,
+) -> ScorerOutput {
+    let segments = query
+        .split(&['/', '\\'][..])
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    if segments.is_empty() {
+        return scorer(line, query);
+    }
+    let matched = match_path_components(line, &segments, &scorer, positional);
+    if positional.is_none() {
+        return matched.or_else(|| scorer(line, query));
+    }
+    // On the same scale, the full line could be better if the segments are adjacent.
+    match (matched, scorer(line, query)) {
+        (Some(matched), Some(fallback)) if fallback.0 > matched.0 => Some(fallback),
+        (Some(matched), _) => Some(matched),
+        (None, fallback) => fallback,
+    }
+}
+
+#[inline]
+pub(super) fn apply_skim_on_path_line(line: &str, query: &str) -> ScorerOutput {
+    apply_on_path_line(line, query, fuzzy_indices_skim, None)
+}
+
+#[inline]
+pub(super) fn apply_fzy_on_path_line(line: &str, query: &str) -> ScorerOutput {
+    apply_on_path_line(line, query, fuzzy_indices_fzy, Some(fzy_positional_score))
+}
+
+#[inline]
+pub(super) fn apply_substr_on_path_line(line: &str, query: &str) -> ScorerOutput {
+    apply_on_path_line(line, query, substr_indices, None)
+}
+
+#[inline]
+pub(super) fn apply_typo_on_path_line(line: &str, query: &str) -> ScorerOutput {
+    apply_on_path_line(line, query, typo_indices, Some(fzy_positional_score))
+}
+
+#[inline]
+pub(super) fn apply_acronym_on_path_line(line: &str, query: &str) -> ScorerOutput {
+    apply_on_path_line(line, query, acronym_indices, Some(fzy_positional_score))
+}
+
+/// Applies `scorer` on the tag name only, the leading qualifiers of `query` are used to filter
+/// the tags by kind and language, nothing is matched if there is an unknown qualifier.
+#[inline]
+fn apply_on_tag_line(
+    line: &str,
+    query: &str,
+    scorer: impl Fn(&str, &str) -> ScorerOutput,
+) -> ScorerOutput {
+    let (qualifiers, query) = TagQualifiers::parse(query).ok()?;
+    if !qualifiers.matches(line) {
+        return None;
+    }
+    tag_name_only(line).and_then(|tag_name| {
+        if query.is_empty() {
+            Some((0, Vec::new()))
+        } else {
+            scorer(tag_name, query)
+        }
+    })
+}
+
 #[inline]
 pub(super) fn apply_skim_on_tag_line(line: &str, query: &str) -> ScorerOutput {
-    tag_name_only(line).and_then(|tag_name| fuzzy_indices_skim(tag_name, query))
+    apply_on_tag_line(line, query, fuzzy_indices_skim)
 }
 
 #[inline]
 pub(super) fn apply_fzy_on_tag_line(line: &str, query: &str) -> ScorerOutput {
-    tag_name_only(line).and_then(|tag_name| fuzzy_indices_fzy(tag_name, query))
+    apply_on_tag_line(line, query, fuzzy_indices_fzy)
 }
 
 #[inline]
 pub(super) fn apply_substr_on_tag_line(line: &str, query: &str) -> ScorerOutput {
-    tag_name_only(line).and_then(|tag_name| substr_indices(tag_name, query))
+    apply_on_tag_line(line, query, substr_indices)
+}
+
+#[inline]
+pub(super) fn apply_typo_on_tag_line(line: &str, query: &str) -> ScorerOutput {
+    apply_on_tag_line(line, query, typo_indices)
+}
+
+#[inline]
+pub(super) fn apply_acronym_on_tag_line(line: &str, query: &str) -> ScorerOutput {
+    apply_on_tag_line(line, query, acronym_indices)
 }
 
 #[cfg(test)]
@@ -92,4 +326,76 @@ mod tests {
         let (_, indices) = apply_fzy_on_file_line(line, query).unwrap();
         assert_eq!(origin_indices, indices);
     }
+
+    #[test]
+    fn test_tag_name_only_with_qualifiers() {
+        let line = "new:7                         \t[method@src/lib.rs]           \tpub fn new() -> Self {";
+        let (_, origin_indices) = apply_fzy_on_tag_line(line, "nw").unwrap();
+        let (_, indices) = apply_fzy_on_tag_line(line, "fn:nw").unwrap();
+        assert_eq!(origin_indices, indices);
+        assert!(apply_fzy_on_tag_line(line, "struct:nw").is_none());
+        assert!(apply_fzy_on_tag_line(line, "fn:").is_some());
+        assert!(apply_fzy_on_tag_line(line, "func:nw").is_none());
+    }
+
+    #[test]
+    fn test_long_line() {
+        let line = format!(
+            "{}function(foo){{bar}}{}",
+            "x".repeat(2000),
+            "y".repeat(2000)
+        );
+        let (score, indices) = fuzzy_indices_fzy(&line, "foobar").unwrap();
+        assert!(score > i64::from(i32::MIN));
+        let matched = indices
+            .iter()
+            .map(|&i| line.chars().nth(i).unwrap())
+            .collect::<String>();
+        assert_eq!(matched, "foobar");
+        assert_eq!(indices[0], 2009);
+
+        let (_, indices) = substr_indices(&line, "foo bar").unwrap();
+        assert_eq!(indices, vec![2009, 2010, 2011, 2014, 2015, 2016]);
+    }
+
+    #[test]
+    fn test_file_path() {
+        let lines = [
+            "crates/maple_cli/src/cmd/rpc/mod.rs",
+            "autoload/clap/provider/filer.vim",
+            "crates/maple_cli/src/cmd/rpc/filer.rs",
+            "crates/maple_cli/src/cmd/filter/mod.rs",
+        ];
+        let best = |query: &str| {
+            lines
+                .iter()
+                .filter_map(|line| apply_fzy_on_path_line(line, query).map(|(s, _)| (s, *line)))
+                .max_by_key(|(s, _)| *s)
+                .map(|(_, line)| line)
+        };
+        assert_eq!(best("maple/rpc/filer"), Some(lines[2]));
+        assert_eq!(best("clap/filer"), Some(lines[1]));
+
+        let (_, indices) = apply_fzy_on_path_line(lines[2], "maple/rpc/filer").unwrap();
+        assert_eq!(
+            indices,
+            vec![7, 8, 9, 10, 11, 25, 26, 27, 28, 29, 30, 31, 32, 33]
+        );
+
+        // The components are scored on the full line, so the file name match only gains a
+        // bounded bonus over the fallback.
+        let score = |query: &str| apply_fzy_on_path_line(lines[2], query).unwrap().0;
+        let fallback_score = |query: &str| fuzzy_indices_fzy(lines[2], query).unwrap().0;
+        assert_eq!(
+            score("rpc/filer"),
+            fallback_score("rpc/filer") + FILE_NAME_BONUS
+        );
+        assert!(score("maple/filer") > fallback_score("maple/filer"));
+        assert!(score("maple/filer") - fallback_score("maple/filer") < 2 * FILE_NAME_BONUS);
+
+        // Falls back to the full line if the segment spans multiple components.
+        let (_, origin_indices) = fuzzy_indices_fzy(lines[0], "cmdrpc").unwrap();
+        let (_, indices) = apply_fzy_on_path_line(lines[0], "cmdrpc").unwrap();
+        assert_eq!(origin_indices, indices);
+    }
 }
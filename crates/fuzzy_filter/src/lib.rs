@@ -1,18 +1,26 @@
+mod acronym;
 mod content_filtering;
+mod rank;
 mod source;
 mod substr;
+mod tag_qualifier;
+mod typo;
 
 use anyhow::Result;
 use content_filtering::*;
 use rayon::prelude::*;
 use structopt::clap::arg_enum;
 
+pub use acronym::acronym_indices;
 pub use content_filtering::fuzzy_indices_fzy;
 pub use fuzzy_matcher::skim::fuzzy_indices as fuzzy_indices_skim;
+pub use rank::{Rank, TieBreak};
 pub use source::Source;
 #[cfg(feature = "enable_dyn")]
 pub use subprocess;
 pub use substr::substr_indices;
+pub use tag_qualifier::TagQualifiers;
+pub use typo::typo_indices;
 
 // Implement arg_enum so that we could control it from the command line.
 arg_enum! {
@@ -22,6 +30,7 @@ arg_enum! {
       Full,
       TagNameOnly,
       FileNameOnly,
+      FilePath,
       GrepExcludeFilePath,
   }
 }
@@ -32,6 +41,7 @@ impl From<&str> for ContentFiltering {
             "Full" => Self::Full,
             "TagNameOnly" => Self::TagNameOnly,
             "FileNameOnly" => Self::FileNameOnly,
+            "FilePath" => Self::FilePath,
             "GrepExcludeFilePath" => Self::GrepExcludeFilePath,
             _ => Self::Full,
         }
@@ -52,11 +62,14 @@ arg_enum! {
       Skim,
       Fzy,
       SubString,
+      Typo,
+      Acronym,
   }
 }
 
-/// Tuple of (matched line text, filtering score, indices of matched elements)
-pub type FuzzyMatchedLineInfo = (String, i64, Vec<usize>);
+/// Tuple of (matched line text, filtering score, indices of matched elements, index of the line
+/// in the source)
+pub type FuzzyMatchedLineInfo = (String, i64, Vec<usize>, usize);
 
 // Returns the score and indices of matched chars
 // when the line is matched given the query,
@@ -68,10 +81,23 @@ pub fn fuzzy_filter_and_rank<I: Iterator<Item = String>>(
     query: &str,
     source: Source<I>,
     algo: Algo,
+    rank: &Rank,
 ) -> Result<Vec<FuzzyMatchedLineInfo>> {
-    let mut ranked = source.fuzzy_filter(algo, query)?;
+    fuzzy_filter_and_rank_by(query, source, algo, ContentFiltering::Full, rank)
+}
+
+/// Same with [`fuzzy_filter_and_rank`], but the filter is only applied on the part of line
+/// specified by `content_filtering`.
+pub fn fuzzy_filter_and_rank_by<I: Iterator<Item = String>>(
+    query: &str,
+    source: Source<I>,
+    algo: Algo,
+    content_filtering: ContentFiltering,
+    rank: &Rank,
+) -> Result<Vec<FuzzyMatchedLineInfo>> {
+    let mut ranked = source.fuzzy_filter_by(algo, content_filtering, query)?;
 
-    ranked.par_sort_unstable_by(|(_, v1, _), (_, v2, _)| v2.partial_cmp(&v1).unwrap());
+    rank.sort(&mut ranked);
 
     Ok(ranked)
 }
@@ -87,19 +113,68 @@ pub fn get_appropriate_scorer(
             ContentFiltering::Full => fuzzy_indices_skim,
             ContentFiltering::TagNameOnly => apply_skim_on_tag_line,
             ContentFiltering::FileNameOnly => apply_skim_on_file_line,
+            ContentFiltering::FilePath => apply_skim_on_path_line,
             ContentFiltering::GrepExcludeFilePath => apply_skim_on_grep_line,
         },
         Algo::Fzy => match content_filtering {
             ContentFiltering::Full => fuzzy_indices_fzy,
             ContentFiltering::TagNameOnly => apply_fzy_on_tag_line,
             ContentFiltering::FileNameOnly => apply_fzy_on_file_line,
+            ContentFiltering::FilePath => apply_fzy_on_path_line,
             ContentFiltering::GrepExcludeFilePath => apply_fzy_on_grep_line,
         },
         Algo::SubString => match content_filtering {
             ContentFiltering::Full => substr_indices,
             ContentFiltering::TagNameOnly => apply_substr_on_tag_line,
             ContentFiltering::FileNameOnly => apply_substr_on_file_line,
+            ContentFiltering::FilePath => apply_substr_on_path_line,
             ContentFiltering::GrepExcludeFilePath => apply_substr_on_grep_line,
         },
+        Algo::Typo => match content_filtering {
+            ContentFiltering::Full => typo_indices,
+            ContentFiltering::TagNameOnly => apply_typo_on_tag_line,
+            ContentFiltering::FileNameOnly => apply_typo_on_file_line,
+            ContentFiltering::FilePath => apply_typo_on_path_line,
+            ContentFiltering::GrepExcludeFilePath => apply_typo_on_grep_line,
+        },
+        Algo::Acronym => match content_filtering {
+            ContentFiltering::Full => acronym_indices,
+            ContentFiltering::TagNameOnly => apply_acronym_on_tag_line,
+            ContentFiltering::FileNameOnly => apply_acronym_on_file_line,
+            ContentFiltering::FilePath => apply_acronym_on_path_line,
+            ContentFiltering::GrepExcludeFilePath => apply_acronym_on_grep_line,
+        },
     }
 }
+
+#[test]
+fn test_fuzzy_filter_and_rank_by() {
+    let source = || {
+        Source::from(vec![
+            "src/main.rs".to_string(),
+            "crates/maple_cli/src/lib.rs".to_string(),
+            "README.md".to_string(),
+        ])
+    };
+    let ranked = fuzzy_filter_and_rank_by(
+        "mcl",
+        source(),
+        Algo::Fzy,
+        ContentFiltering::FileNameOnly,
+        &Rank::default(),
+    )
+    .unwrap();
+    assert!(ranked.is_empty());
+
+    let ranked = fuzzy_filter_and_rank_by(
+        "mcl",
+        source(),
+        Algo::Fzy,
+        ContentFiltering::FilePath,
+        &Rank::default(),
+    )
+    .unwrap();
+    assert_eq!(ranked.len(), 1);
+    assert_eq!(ranked[0].0, "crates/maple_cli/src/lib.rs");
+    assert_eq!(ranked[0].3, 1);
+}
@@ -0,0 +1,141 @@
+//! Ranking of the filtered items, the items are ordered by the score first, then the ties are
+//! broken by the criteria in order, e.g., `length,begin`, which is similar to `--tiebreak` of fzf.
+//!
+//! The items of the same score and criteria are in the order of source, so that the results are
+//! stable between the queries.
+
+use crate::FuzzyMatchedLineInfo;
+use rayon::slice::ParallelSliceMut;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use structopt::clap::arg_enum;
+
+arg_enum! {
+  /// Criterion of breaking the ties of score:
+  ///
+  /// - `Length`: prefers the shorter line.
+  /// - `Index`: prefers the line that appears earlier in the source.
+  /// - `Depth`: prefers the path with less components.
+  /// - `Begin`: prefers the line of which the first match is closer to the beginning.
+  #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+  pub enum TieBreak {
+      Length,
+      Index,
+      Depth,
+      Begin,
+  }
+}
+
+impl TieBreak {
+    fn compare(self, a: &FuzzyMatchedLineInfo, b: &FuzzyMatchedLineInfo) -> Ordering {
+        match self {
+            Self::Length => a.0.len().cmp(&b.0.len()),
+            Self::Index => a.3.cmp(&b.3),
+            Self::Depth => path_depth(&a.0).cmp(&path_depth(&b.0)),
+            Self::Begin => first_match(&a.2).cmp(&first_match(&b.2)),
+        }
+    }
+}
+
+#[inline]
+fn path_depth(line: &str) -> usize {
+    line.matches(&['/', '\\'][..]).count()
+}
+
+#[inline]
+fn first_match(indices: &[usize]) -> usize {
+    indices.first().copied().unwrap_or(0)
+}
+
+/// Tie-breaking criteria applied in order after the score.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Rank {
+    pub criteria: Vec<TieBreak>,
+}
+
+impl FromStr for Rank {
+    type Err = String;
+
+    /// Parses the comma-separated criteria, e.g., `length,index`, which is case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let criteria = s
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                x.parse::<TieBreak>().map_err(|_| {
+                    format!(
+                        "Invalid rank criterion: {}, valid values: {}",
+                        x,
+                        TieBreak::variants().join(", ")
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { criteria })
+    }
+}
+
+impl Rank {
+    /// Returns `Less` if `a` is ranked before `b` by the score and criteria, the remaining ties
+    /// are up to the caller, e.g., a stable sort.
+    pub fn compare(&self, a: &FuzzyMatchedLineInfo, b: &FuzzyMatchedLineInfo) -> Ordering {
+        self.criteria.iter().fold(b.1.cmp(&a.1), |ord, criterion| {
+            ord.then_with(|| criterion.compare(a, b))
+        })
+    }
+
+    /// Sorts the items from the best, the remaining ties are in the order of source index.
+    pub fn sort(&self, items: &mut [FuzzyMatchedLineInfo]) {
+        items.par_sort_unstable_by(|a, b| {
+            self.compare(a, b)
+                .then_with(|| TieBreak::Index.compare(a, b))
+        });
+    }
+}
+
+#[test]
+fn test_rank() {
+    let item =
+        |text: &str, score, indices: Vec<usize>, idx| (text.to_string(), score, indices, idx);
+    let mut items = vec![
+        item("src/foo/bar.rs", 10, vec![8], 0),
+        item("a/b/c/foo.rs", 10, vec![6], 1),
+        item("foo.rs", 10, vec![0], 2),
+        item("src/baz.rs", 20, vec![4], 3),
+        item("foo/b.rs", 10, vec![0], 4),
+    ];
+    let texts = |items: &[FuzzyMatchedLineInfo]| {
+        items
+            .iter()
+            .map(|x| x.0.clone())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    Rank::default().sort(&mut items);
+    assert_eq!(
+        texts(&items),
+        "src/baz.rs src/foo/bar.rs a/b/c/foo.rs foo.rs foo/b.rs"
+    );
+
+    "length".parse::<Rank>().unwrap().sort(&mut items);
+    assert_eq!(
+        texts(&items),
+        "src/baz.rs foo.rs foo/b.rs a/b/c/foo.rs src/foo/bar.rs"
+    );
+
+    "Begin, depth".parse::<Rank>().unwrap().sort(&mut items);
+    assert_eq!(
+        texts(&items),
+        "src/baz.rs foo.rs foo/b.rs a/b/c/foo.rs src/foo/bar.rs"
+    );
+
+    "depth,index".parse::<Rank>().unwrap().sort(&mut items);
+    assert_eq!(
+        texts(&items),
+        "src/baz.rs foo.rs foo/b.rs src/foo/bar.rs a/b/c/foo.rs"
+    );
+
+    assert!("length,score".parse::<Rank>().is_err());
+}
@@ -1,5 +1,4 @@
 use super::*;
-use crate::{fuzzy_indices_fzy, fuzzy_indices_skim};
 use anyhow::Result;
 use std::io::BufRead;
 use std::path::PathBuf;
@@ -41,40 +40,54 @@ impl<I: Iterator<Item = String>> Source<I> {
     ///
     /// This is kind of synchronous filtering, can be used for multi-staged processing.
     pub fn fuzzy_filter(self, algo: Algo, query: &str) -> Result<Vec<FuzzyMatchedLineInfo>> {
-        let scorer = |line: &str| match algo {
-            Algo::Skim => fuzzy_indices_skim(line, &query),
-            Algo::Fzy => fuzzy_indices_fzy(line, &query),
-            Algo::SubString => substr_indices(line, &query),
-        };
+        self.fuzzy_filter_by(algo, ContentFiltering::Full, query)
+    }
+
+    /// Same with [`Source::fuzzy_filter`], but the filter algo is only applied on the part of
+    /// line specified by `content_filtering`.
+    pub fn fuzzy_filter_by(
+        self,
+        algo: Algo,
+        content_filtering: ContentFiltering,
+        query: &str,
+    ) -> Result<Vec<FuzzyMatchedLineInfo>> {
+        let scorer = get_appropriate_scorer(&algo, &content_filtering);
+        let scorer = |line: &str| scorer(line, query);
 
         let filtered = match self {
             Self::Stdin => std::io::stdin()
                 .lock()
                 .lines()
-                .filter_map(|lines_iter| {
+                .enumerate()
+                .filter_map(|(idx, lines_iter)| {
                     lines_iter.ok().and_then(|line| {
-                        scorer(&line).map(|(score, indices)| (line, score, indices))
+                        scorer(&line).map(|(score, indices)| (line, score, indices, idx))
                     })
                 })
                 .collect::<Vec<_>>(),
             #[cfg(feature = "enable_dyn")]
             Self::Exec(exec_cmd) => std::io::BufReader::new(exec_cmd.stream_stdout()?)
                 .lines()
-                .filter_map(|lines_iter| {
+                .enumerate()
+                .filter_map(|(idx, lines_iter)| {
                     lines_iter.ok().and_then(|line| {
-                        scorer(&line).map(|(score, indices)| (line, score, indices))
+                        scorer(&line).map(|(score, indices)| (line, score, indices, idx))
                     })
                 })
                 .collect::<Vec<_>>(),
             Self::File(fpath) => std::fs::read_to_string(fpath)?
-                .par_lines()
-                .filter_map(|line| {
-                    scorer(&line).map(|(score, indices)| (line.into(), score, indices))
+                .lines()
+                .collect::<Vec<_>>()
+                .par_iter()
+                .enumerate()
+                .filter_map(|(idx, line)| {
+                    scorer(&line).map(|(score, indices)| (line.to_string(), score, indices, idx))
                 })
                 .collect::<Vec<_>>(),
             Self::List(list) => list
-                .filter_map(|line| {
-                    scorer(&line).map(|(score, indices)| (line.into(), score, indices))
+                .enumerate()
+                .filter_map(|(idx, line)| {
+                    scorer(&line).map(|(score, indices)| (line, score, indices, idx))
                 })
                 .collect::<Vec<_>>(),
         };
@@ -0,0 +1,224 @@
+//! Kind and language qualifiers of the query for filtering the tag lines.
+//!
+//! The query can be prefixed by the qualifiers to search only the tags of some kinds or some
+//! languages, e.g., `fn:new`, `struct:`, `rust:type:Tag`. The qualifiers of the same category
+//! are OR-ed, the different categories are AND-ed.
+//!
+//! A qualifier is a word followed by a single `:`, so `std::fs` and `type::Foo` are the plain
+//! queries, the unknown qualifiers are rejected instead of being matched as part of the name.
+
+/// Qualifier name and the kinds it stands for.
+const KIND_QUALIFIERS: &[(&str, &[&str])] = &[
+    (
+        "fn",
+        &[
+            "function",
+            "func",
+            "method",
+            "member",
+            "prototype",
+            "procedure",
+            "subroutine",
+        ],
+    ),
+    ("method", &["method", "member"]),
+    ("struct", &["struct"]),
+    ("enum", &["enum"]),
+    ("union", &["union"]),
+    ("class", &["class"]),
+    ("trait", &["interface", "trait"]),
+    ("interface", &["interface", "trait"]),
+    (
+        "type",
+        &[
+            "struct",
+            "enum",
+            "union",
+            "class",
+            "interface",
+            "trait",
+            "typedef",
+            "type",
+            "alias",
+        ],
+    ),
+    ("impl", &["implementation"]),
+    ("mod", &["module", "namespace", "package"]),
+    ("const", &["constant"]),
+    ("macro", &["macro"]),
+    ("var", &["variable", "var"]),
+];
+
+/// Qualifier name and the names of the language reported by ctags.
+const LANGUAGE_QUALIFIERS: &[(&str, &[&str])] = &[
+    ("rust", &["Rust"]),
+    ("python", &["Python"]),
+    ("go", &["Go"]),
+    ("js", &["JavaScript"]),
+    ("javascript", &["JavaScript"]),
+    ("ts", &["TypeScript"]),
+    ("typescript", &["TypeScript"]),
+    ("c", &["C"]),
+    ("cpp", &["C++"]),
+    ("cs", &["C#"]),
+    ("java", &["Java"]),
+    ("kotlin", &["Kotlin"]),
+    ("scala", &["Scala"]),
+    ("ruby", &["Ruby"]),
+    ("php", &["PHP"]),
+    ("perl", &["Perl"]),
+    ("lua", &["Lua"]),
+    ("vim", &["Vim"]),
+    ("sh", &["Sh"]),
+    ("haskell", &["Haskell"]),
+    ("ocaml", &["OCaml"]),
+    ("elixir", &["Elixir"]),
+    ("erlang", &["Erlang"]),
+    ("swift", &["Swift"]),
+    ("objc", &["ObjectiveC"]),
+    ("markdown", &["Markdown"]),
+];
+
+fn lookup(
+    table: &[(&str, &'static [&'static str])],
+    name: &str,
+) -> Option<&'static [&'static str]> {
+    table
+        .iter()
+        .find(|(qualifier, _)| qualifier.eq_ignore_ascii_case(name))
+        .map(|(_, values)| *values)
+}
+
+/// Qualifiers parsed from the query.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct TagQualifiers {
+    kinds: Vec<&'static str>,
+    languages: Vec<&'static str>,
+}
+
+impl TagQualifiers {
+    /// Parses the leading qualifiers of `query`, returns the qualifiers and the rest of query.
+    ///
+    /// The single letter prefix is not a qualifier so that the Vim variables like `g:foo` can be
+    /// searched, an error is returned for other unknown qualifiers.
+    pub fn parse(query: &str) -> Result<(Self, &str), String> {
+        let mut qualifiers = Self::default();
+        let mut rest = query;
+        while let Some(idx) = rest.find(':') {
+            let name = &rest[..idx];
+            if name.len() < 2
+                || !name.chars().all(|c| c.is_ascii_alphabetic())
+                || rest[idx + 1..].starts_with(':')
+            {
+                break;
+            }
+            if let Some(kinds) = lookup(KIND_QUALIFIERS, name) {
+                qualifiers.kinds.extend_from_slice(kinds);
+            } else if let Some(languages) = lookup(LANGUAGE_QUALIFIERS, name) {
+                qualifiers.languages.extend_from_slice(languages);
+            } else {
+                return Err(format!("Unknown tag qualifier: {}", name));
+            }
+            rest = &rest[idx + 1..];
+        }
+        Ok((qualifiers, rest))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty() && self.languages.is_empty()
+    }
+
+    /// Returns true if the tag line matches the qualifiers.
+    ///
+    /// The language and kind are extracted from the `[{language}:{kind}@{path}]` part of the tag
+    /// line. The language is unknown for the buffer tags `[{kind}]`, in which case the language
+    /// qualifiers are ignored as all the tags are from the same file.
+    pub fn matches(&self, line: &str) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        let (language, kind, has_path) = match extract_language_and_kind(line) {
+            Some(language_kind) => language_kind,
+            None => return false,
+        };
+
+        if !self.kinds.is_empty() && !self.kinds.contains(&kind) {
+            return false;
+        }
+
+        if self.languages.is_empty() || !has_path {
+            return true;
+        }
+
+        language
+            .map(|language| self.languages.contains(&language))
+            .unwrap_or(false)
+    }
+}
+
+/// Returns the language, kind and whether the path exists of tag line, e.g.,
+/// `new:7\t[Rust:method@src/lib.rs]\t\tpub fn new()`.
+///
+/// The `[{language}:{kind}@{path}]` part is the second field delimited by tab, the name and path
+/// may contain the brackets as well.
+fn extract_language_and_kind(line: &str) -> Option<(Option<&str>, &str, bool)> {
+    let kind_path = line.split('\t').nth(1)?.trim_end();
+    let kind_path = kind_path.strip_prefix('[')?.strip_suffix(']')?;
+    let (language_kind, has_path) = match kind_path.find('@') {
+        Some(idx) => (&kind_path[..idx], true),
+        None => (kind_path, false),
+    };
+    match language_kind.rfind(':') {
+        Some(idx) => Some((
+            Some(&language_kind[..idx]),
+            &language_kind[idx + 1..],
+            has_path,
+        )),
+        None => Some((None, language_kind, has_path)),
+    }
+}
+
+#[test]
+fn test_tag_qualifiers() {
+    let (qualifiers, query) = TagQualifiers::parse("rust:fn:new").unwrap();
+    assert_eq!(query, "new");
+    let line = "new:7\t[Rust:method@src/lib.rs]\tFoo ()\tpub fn new() -> Self {";
+    assert!(qualifiers.matches(line));
+    assert!(!qualifiers.matches(&line.replace("Rust", "Python")));
+    assert!(!qualifiers.matches(&line.replace("method", "struct")));
+    // The language is unknown.
+    assert!(!qualifiers.matches(&line.replace("Rust:", "")));
+
+    let (qualifiers, query) = TagQualifiers::parse("cpp:").unwrap();
+    assert_eq!(query, "");
+    assert!(qualifiers.matches("Vec:3\t[C++:class@a.hpp]\t\tclass Vec {"));
+
+    for query in &["std::fs", "type::Foo", "g:clap_enable_icon"] {
+        let (qualifiers, rest) = TagQualifiers::parse(query).unwrap();
+        assert!(qualifiers.is_empty());
+        assert_eq!(&rest, query);
+    }
+    assert!(TagQualifiers::parse("fn:type::Foo")
+        .unwrap()
+        .0
+        .kinds
+        .contains(&"method"));
+    assert_eq!(
+        TagQualifiers::parse("func:new"),
+        Err("Unknown tag qualifier: func".into())
+    );
+
+    let (qualifiers, query) = TagQualifiers::parse("Struct:").unwrap();
+    assert_eq!(query, "");
+    assert!(qualifiers.matches("Foo:2\t[struct]\tpub struct Foo;"));
+
+    // The language qualifiers are ignored for the buffer tags.
+    let (qualifiers, _) = TagQualifiers::parse("rust:struct:").unwrap();
+    assert!(qualifiers.matches("Foo:2\t[struct]\tpub struct Foo;"));
+
+    // The brackets in the name do not confuse the kind.
+    let (qualifiers, _) = TagQualifiers::parse("fn:").unwrap();
+    assert!(qualifiers.matches("operator []:4\t[function@a] b.hpp]\t\tT& operator [](int i);"));
+    assert!(!qualifiers.matches("Intro [method]:1\t[chapter@README.md]\t\t# Intro [method]"));
+}
@@ -0,0 +1,208 @@
+//! Typo-tolerant matching, the query is matched by fzy first, then approximately as a substring
+//! of the line within a bounded number of edits if fzy fails, e.g., `fitler` matches `filter.rs`.
+//!
+//! The edits are the insertion, deletion, substitution and transposition of the adjacent chars,
+//! i.e., the optimal string alignment distance, a variant of Damerau-Levenshtein distance.
+
+use crate::{fuzzy_indices_fzy, ScorerOutput};
+use extracted_fzy::score_positions;
+
+/// Penalty of each edit, which is more than a consecutive match of fzy so that the typo match
+/// is ranked after the exact match of similar quality.
+const TYPO_PENALTY: i64 = 300;
+
+/// Returns the maximum number of edits allowed for the query of `len` chars.
+#[inline]
+fn max_edits(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Compares two characters case-insensitively.
+#[inline]
+fn eq(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Returns the optimal string alignment distance of `needle` and the substring of `haystack`
+/// ending right before the char `end`, if it's not more than `max_edits`.
+///
+/// The matrix is filled column by column over `haystack`, the cells more than `max_edits` are
+/// cut off, so only the band of rows still within `max_edits` is computed for each column, which
+/// is `O(max_edits * haystack.len())` on average. It returns early on an exact substring as
+/// nothing could be closer.
+fn closest_end(needle: &[char], haystack: &[char], max_edits: usize) -> Option<(usize, usize)> {
+    let height = needle.len() + 1;
+    let cut_off = max_edits + 1;
+    let mut prev_prev = vec![cut_off; height];
+    let mut prev = (0..height).map(|i| i.min(cut_off)).collect::<Vec<_>>();
+    let mut curr = vec![cut_off; height];
+    // The last row within `max_edits` of the previous column.
+    let mut last = max_edits.min(needle.len());
+    // (distance, end) of the closest substring so far.
+    let mut closest = (cut_off, 0);
+
+    for j in 1..=haystack.len() {
+        curr[0] = 0;
+        let bottom = (last + 1).min(needle.len());
+        for i in 1..=bottom {
+            let cost = if eq(needle[i - 1], haystack[j - 1]) {
+                0
+            } else {
+                1
+            };
+            let mut cell = (prev[i - 1] + cost).min(prev[i] + 1).min(curr[i - 1] + 1);
+            if i > 1
+                && j > 1
+                && eq(needle[i - 1], haystack[j - 2])
+                && eq(needle[i - 2], haystack[j - 1])
+            {
+                cell = cell.min(prev_prev[i - 2] + 1);
+            }
+            curr[i] = cell.min(cut_off);
+        }
+        for cell in curr.iter_mut().skip(bottom + 1) {
+            *cell = cut_off;
+        }
+
+        last = bottom;
+        while last > 0 && curr[last] > max_edits {
+            last -= 1;
+        }
+        if last == needle.len() && curr[last] < closest.0 {
+            closest = (curr[last], j);
+            if curr[last] == 0 {
+                break;
+            }
+        }
+
+        std::mem::swap(&mut prev_prev, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    Some(closest).filter(|(distance, _)| *distance <= max_edits)
+}
+
+/// Returns the indices of `haystack` aligned with the chars of `needle` by the optimal string
+/// alignment of `needle` and a substring of `haystack` ending at the end of `haystack`.
+///
+/// The substituted chars are not included, the transposed chars are.
+fn aligned_indices(needle: &[char], haystack: &[char]) -> Vec<usize> {
+    let (height, width) = (needle.len() + 1, haystack.len() + 1);
+    let mut matrix = vec![vec![0; width]; height];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for i in 1..height {
+        for j in 1..width {
+            let cost = if eq(needle[i - 1], haystack[j - 1]) {
+                0
+            } else {
+                1
+            };
+            let mut cell = (matrix[i - 1][j - 1] + cost)
+                .min(matrix[i - 1][j] + 1)
+                .min(matrix[i][j - 1] + 1);
+            if i > 1
+                && j > 1
+                && eq(needle[i - 1], haystack[j - 2])
+                && eq(needle[i - 2], haystack[j - 1])
+            {
+                cell = cell.min(matrix[i - 2][j - 2] + 1);
+            }
+            matrix[i][j] = cell;
+        }
+    }
+
+    let mut indices = Vec::with_capacity(needle.len());
+    let (mut i, mut j) = (needle.len(), haystack.len());
+    while i > 0 {
+        let cell = matrix[i][j];
+        if j > 0 {
+            let matched = eq(needle[i - 1], haystack[j - 1]);
+            if cell == matrix[i - 1][j - 1] + if matched { 0 } else { 1 } {
+                if matched {
+                    indices.push(j - 1);
+                }
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if i > 1
+            && j > 1
+            && eq(needle[i - 1], haystack[j - 2])
+            && eq(needle[i - 2], haystack[j - 1])
+            && cell == matrix[i - 2][j - 2] + 1
+        {
+            indices.push(j - 1);
+            indices.push(j - 2);
+            i -= 2;
+            j -= 2;
+        } else if cell == matrix[i - 1][j] + 1 {
+            i -= 1;
+        } else {
+            j -= 1;
+        }
+    }
+
+    indices.reverse();
+    indices
+}
+
+/// Make the arguments order same to Skim's `fuzzy_indices()`.
+///
+/// The substring found approximately is scored by the positions of fzy, minus `TYPO_PENALTY` for
+/// each edit, the indices are the chars aligned with the query.
+pub fn typo_indices(line: &str, query: &str) -> ScorerOutput {
+    if let Some(matched) = fuzzy_indices_fzy(line, query) {
+        return Some(matched);
+    }
+
+    let needle = query.chars().collect::<Vec<_>>();
+    let max_edits = max_edits(needle.len());
+    if max_edits == 0 {
+        return None;
+    }
+
+    let haystack = line.chars().collect::<Vec<_>>();
+    let (edits, end) = closest_end(&needle, &haystack, max_edits)?;
+    // The closest substring is not longer than the needle plus the edits.
+    let start = end.saturating_sub(needle.len() + edits);
+    let indices = aligned_indices(&needle, &haystack[start..end])
+        .into_iter()
+        .map(|idx| idx + start)
+        .collect::<Vec<_>>();
+    let score = score_positions(line, &indices) as i64 - edits as i64 * TYPO_PENALTY;
+    Some((score, indices))
+}
+
+#[test]
+fn test_typo_indices() {
+    let (score, indices) = typo_indices("src/cmd/filter.rs", "fitler").unwrap();
+    assert_eq!(indices, vec![8, 9, 10, 11, 12, 13]);
+    let (exact_score, _) =
+        extracted_fzy::match_and_score_with_positions("filter", "src/cmd/filter.rs").unwrap();
+    assert_eq!(score, exact_score as i64 - TYPO_PENALTY);
+
+    // Missing and mistyped chars.
+    assert!(typo_indices("src/cmd/filter.rs", "filtr").is_some());
+    // The substituted char is not highlighted.
+    let (_, indices) = typo_indices("crates/maple_cli/src/config.rs", "confog").unwrap();
+    assert_eq!(indices, vec![21, 22, 23, 24, 26]);
+    assert!(typo_indices("crates/maple_cli/src/config.rs", "cnofgi").is_none());
+    assert!(typo_indices("src/cmd/filter.rs", "fli").is_none());
+}
+
+#[test]
+fn test_closest_end() {
+    let chars = |s: &str| s.chars().collect::<Vec<_>>();
+    let haystack = chars("src/cmd/filter.rs");
+    assert_eq!(closest_end(&chars("fitler"), &haystack, 1), Some((1, 14)));
+    assert_eq!(closest_end(&chars("filter"), &haystack, 2), Some((0, 14)));
+    assert_eq!(closest_end(&chars("fitlre"), &haystack, 1), None);
+    assert_eq!(closest_end(&chars("fitlre"), &haystack, 2), Some((2, 13)));
+}
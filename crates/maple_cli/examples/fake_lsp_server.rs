@@ -0,0 +1,96 @@
+//! Fake language server used by the tests of `maple_cli::cmd::lsp`, the symbols are the fixed ones
+//! of `src/lib.rs` in the workspace:
+//!
+//! ```text
+//! pub struct Foo {
+//!     bar: usize,
+//! }
+//!
+//! pub fn foo_bar() {}
+//! ```
+
+use maple_cli::cmd::lsp::{path_to_uri, read_message, uri_to_path, write_message};
+use serde_json::{json, Value};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+
+fn range(line: usize) -> Value {
+    json!({
+        "start": { "line": line, "character": 0 },
+        "end": { "line": line, "character": 1 }
+    })
+}
+
+fn workspace_symbols(root: &Path, query: &str) -> Value {
+    let uri = path_to_uri(&root.join("src").join("lib.rs")).unwrap();
+    let symbols = [("Foo", 23, 0), ("bar", 8, 1), ("foo_bar", 12, 4)]
+        .iter()
+        .filter(|(name, _, _)| name.to_lowercase().contains(&query.to_lowercase()))
+        .map(|(name, kind, line)| {
+            json!({
+                "name": name,
+                "kind": kind,
+                "location": { "uri": uri, "range": range(*line) }
+            })
+        })
+        .collect::<Vec<_>>();
+    json!(symbols)
+}
+
+fn document_symbols() -> Value {
+    json!([
+        {
+            "name": "Foo",
+            "kind": 23,
+            "range": range(0),
+            "selectionRange": range(0),
+            "children": [
+                { "name": "bar", "kind": 8, "range": range(1), "selectionRange": range(1) }
+            ]
+        },
+        { "name": "foo_bar", "kind": 12, "range": range(4), "selectionRange": range(4) }
+    ])
+}
+
+fn main() {
+    let stdin = std::io::stdin();
+    let mut reader = BufReader::new(stdin.lock());
+    let stdout = std::io::stdout();
+    let mut writer = stdout.lock();
+    let mut root = PathBuf::new();
+
+    while let Ok(Some(msg)) = read_message(&mut reader) {
+        let method = msg.get("method").and_then(|x| x.as_str());
+        let id = msg.get("id").cloned();
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            Some("initialize") => {
+                if let Some(root_uri) = params.get("rootUri").and_then(|x| x.as_str()) {
+                    root = uri_to_path(root_uri).unwrap();
+                }
+                json!({ "capabilities": { "workspaceSymbolProvider": true, "documentSymbolProvider": true } })
+            }
+            Some("workspace/symbol") => {
+                // Interleave a request and a notification from server before the response.
+                let progress = json!({ "token": "indexing" });
+                let request = json!({ "jsonrpc": "2.0", "id": "progress", "method": "window/workDoneProgress/create", "params": progress });
+                write_message(&mut writer, &request).unwrap();
+                let log = json!({ "jsonrpc": "2.0", "method": "window/logMessage", "params": { "type": 3, "message": "indexed" } });
+                write_message(&mut writer, &log).unwrap();
+                let query = params.get("query").and_then(|x| x.as_str()).unwrap_or("");
+                workspace_symbols(&root, query)
+            }
+            Some("textDocument/documentSymbol") => document_symbols(),
+            Some("exit") => break,
+            _ => Value::Null,
+        };
+
+        // Notifications and the responses to the requests of server have no reply.
+        if let (Some(id), Some(_)) = (id, method) {
+            let response = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+            write_message(&mut writer, &response).unwrap();
+        }
+    }
+    writer.flush().unwrap();
+}
@@ -1,5 +1,8 @@
+use crate::config::Config;
 use anyhow::Result;
+use fuzzy_filter::Algo;
 use icon::IconPainter;
+use std::path::PathBuf;
 use structopt::clap::AppSettings;
 use structopt::StructOpt;
 
@@ -20,6 +23,24 @@ pub enum Cmd {
     /// Generate the project-wide tags using ctags.
     #[structopt(name = "tags")]
     Tags(crate::cmd::tags::Tags),
+    /// Generate the tags of a single file.
+    #[structopt(name = "buffer-tags")]
+    BufferTags(crate::cmd::tags::BufferTags),
+    /// Filter the tags of the existing tags files.
+    #[structopt(name = "tagfiles")]
+    TagFiles(crate::cmd::tags::TagFiles),
+    /// Filter the symbols provided by the language server.
+    #[structopt(name = "lsp-symbols")]
+    LspSymbols(crate::cmd::lsp::LspSymbols),
+    /// List the definitions and references of a symbol.
+    #[structopt(name = "references")]
+    References(crate::cmd::references::References),
+    /// List the files of git repo without spawning git.
+    #[structopt(name = "git-files")]
+    GitFiles(crate::cmd::git::GitFiles),
+    /// List the commits of git repo without spawning git.
+    #[structopt(name = "commits")]
+    Commits(crate::cmd::git::Commits),
     /// Interact with the cache info.
     #[structopt(name = "cache")]
     Cache(crate::cmd::cache::Cache),
@@ -29,17 +50,46 @@ pub enum Cmd {
     /// Filter against current Vim buffer.
     #[structopt(name = "blines")]
     Blines(crate::cmd::blines::Blines),
+    /// Filter against the lines of multiple files.
+    #[structopt(name = "lines")]
+    Lines(crate::cmd::lines::Lines),
     /// Generate vim help tags.
     #[structopt(name = "helptags")]
     Helptags(crate::cmd::helptags::Helptags),
     /// Start the forerunner job of grep.
     #[structopt(name = "ripgrep-forerunner")]
     RipGrepForerunner(crate::cmd::grep::RipGrepForerunner),
+    /// Show the config files and the effective config.
+    #[structopt(name = "config")]
+    Config(crate::cmd::config::Config),
     /// Retrive the latest remote release info.
     #[structopt(name = "check-release")]
     CheckRelease(crate::cmd::check_release::CheckRelease),
 }
 
+impl Cmd {
+    /// Returns the directory where the project config of command is looked up, the current
+    /// directory by default, `None` if the command does not read the config.
+    fn config_dir(&self) -> Result<Option<PathBuf>> {
+        let dir = match self {
+            Self::Version | Self::CheckRelease(_) => return Ok(None),
+            Self::Config(_) | Self::Cache(_) | Self::Helptags(_) => return Ok(None),
+            Self::Tags(tags) => Some(tags.dir()),
+            Self::LspSymbols(lsp_symbols) => lsp_symbols.dir(),
+            Self::Grep(grep) => grep.cmd_dir(),
+            Self::RipGrepForerunner(rip_grep_forerunner) => rip_grep_forerunner.cmd_dir(),
+            Self::Exec(exec) => exec.cmd_dir(),
+            Self::References(references) => references.cmd_dir(),
+            Self::GitFiles(git_files) => git_files.cmd_dir(),
+            _ => None,
+        };
+        match dir {
+            Some(dir) => Ok(Some(dir.to_path_buf())),
+            None => Ok(Some(std::env::current_dir()?)),
+        }
+    }
+}
+
 #[derive(StructOpt, Debug)]
 #[structopt(
   name = "maple",
@@ -64,6 +114,10 @@ pub struct Maple {
     #[structopt(short, long, possible_values = &IconPainter::variants(), case_insensitive = true)]
     pub icon_painter: Option<IconPainter>,
 
+    /// Filter algorithm of the filter subcommands and the RPC methods, overrides the config.
+    #[structopt(long, possible_values = &Algo::variants(), case_insensitive = true)]
+    pub algo: Option<Algo>,
+
     /// Do not use the cached file for exec subcommand.
     #[structopt(long = "no-cache")]
     pub no_cache: bool,
@@ -81,29 +135,53 @@ impl Maple {
         if let Some(ref log_path) = self.log {
             crate::logger::init(log_path)?;
         }
+        // The config is loaded only once, from the project of command.
+        let config = match self.command.config_dir()? {
+            Some(dir) => Config::load_or_default(dir),
+            None => Config::default(),
+        };
+        // The CLI flags take precedence over the config values.
+        let number = self.number.or(config.number);
+        let winwidth = self.winwidth.or(config.winwidth);
+        let icon_painter = self.icon_painter.or_else(|| config.icon_painter.clone());
+        let algo = self.algo.or_else(|| config.algo.clone());
         match self.command {
             Cmd::Version => unreachable!(),
             Cmd::CheckRelease(_) => unreachable!(),
             Cmd::Helptags(helptags) => helptags.run()?,
-            Cmd::Tags(tags) => tags.run(self.no_cache)?,
+            Cmd::Tags(tags) => tags.run(self.no_cache, &config.tags)?,
+            Cmd::BufferTags(buffer_tags) => buffer_tags.run(number, winwidth)?,
+            Cmd::TagFiles(tag_files) => tag_files.run(number, winwidth)?,
+            Cmd::LspSymbols(lsp_symbols) => lsp_symbols.run(number, winwidth, &config)?,
             Cmd::RPC => {
-                crate::cmd::rpc::run_forever(std::io::BufReader::new(std::io::stdin()));
+                crate::cmd::rpc::run_forever(
+                    std::io::BufReader::new(std::io::stdin()),
+                    Config { algo, ..config },
+                );
             }
             Cmd::Blines(blines) => {
-                blines.run(self.number, self.winwidth)?;
-            }
-            Cmd::RipGrepForerunner(rip_grep_forerunner) => {
-                rip_grep_forerunner.run(self.number, self.icon_painter, self.no_cache)?
+                blines.run(number, winwidth, algo)?;
             }
+            Cmd::Lines(lines) => lines.run(number, winwidth, algo)?,
+            Cmd::RipGrepForerunner(rip_grep_forerunner) => rip_grep_forerunner.run(
+                number,
+                icon_painter,
+                self.no_cache,
+                config.output_threshold,
+            )?,
+            Cmd::GitFiles(git_files) => git_files.run(number, winwidth, icon_painter)?,
+            Cmd::Commits(commits) => commits.run(number, winwidth)?,
             Cmd::Cache(cache) => cache.run()?,
+            Cmd::Config(config_cmd) => config_cmd.run()?,
+            Cmd::References(references) => references.run(number, icon_painter, &config.tags)?,
             Cmd::Filter(filter) => {
-                filter.run(self.number, self.winwidth, self.icon_painter)?;
+                filter.run(number, winwidth, icon_painter, algo)?;
             }
             Cmd::Exec(exec) => {
-                exec.run(self.number, self.icon_painter, self.no_cache)?;
+                exec.run(number, icon_painter, self.no_cache, config.output_threshold)?;
             }
             Cmd::Grep(grep) => {
-                grep.run(self.number, self.winwidth, self.icon_painter, self.no_cache)?;
+                grep.run(number, winwidth, icon_painter, self.no_cache)?;
             }
         }
         Ok(())
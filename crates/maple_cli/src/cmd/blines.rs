@@ -1,6 +1,9 @@
+use crate::cmd::filter::dynamic::{dyn_fuzzy_filter_and_rank, select_algo};
+use crate::cmd::git::{blame_lines, BlameLine};
 use crate::ContentFiltering;
 use anyhow::Result;
-use fuzzy_filter::Source;
+use fuzzy_filter::{get_appropriate_scorer, Algo, FuzzyMatchedLineInfo, Rank, Source};
+use printer::truncate_long_matched_lines;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -14,24 +17,224 @@ pub struct Blines {
     /// File path of current vim buffer.
     #[structopt(index = 2, short, long, parse(from_os_str))]
     input: PathBuf,
+
+    /// Annotate each line with the commit, author and date of the last change.
+    ///
+    /// The query can contain the filters `author:{name}`, `since:{YYYY-MM-DD}` and
+    /// `until:{YYYY-MM-DD}`, e.g., `author:jane since:2020-01-01 foo`.
+    #[structopt(long)]
+    blame: bool,
+}
+
+/// Filters of the blame mode parsed from the query.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+struct BlameFilter {
+    /// Case-insensitive part of the author name.
+    author: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+}
+
+impl BlameFilter {
+    /// Extracts the filters from `query`, returns the filters and the rest of query.
+    fn parse(query: &str) -> (Self, String) {
+        let mut filter = Self::default();
+        let mut rest = Vec::new();
+        for token in query.split_whitespace() {
+            let mut name_value = token.splitn(2, ':');
+            match (name_value.next(), name_value.next()) {
+                (Some("author"), Some(author)) => filter.author = Some(author.to_lowercase()),
+                (Some("since"), Some(since)) => filter.since = Some(since.into()),
+                (Some("until"), Some(until)) => filter.until = Some(until.into()),
+                _ => rest.push(token),
+            }
+        }
+        (filter, rest.join(" "))
+    }
+
+    /// Returns true if the line of `blame` passes all the filters, the dates are compared in the
+    /// form of `YYYY-MM-DD`.
+    fn matches(&self, blame: &BlameLine) -> bool {
+        if let Some(ref author) = self.author {
+            if !blame.author.to_lowercase().contains(author) {
+                return false;
+            }
+        }
+        if let Some(ref since) = self.since {
+            if blame.date.is_empty() || blame.date < *since {
+                return false;
+            }
+        }
+        if let Some(ref until) = self.until {
+            if blame.date.is_empty() || blame.date > *until {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Returns the lines matching `query` along with `extra` of each line, `lines` are the tuples of
+/// (0-based line index, line, extra). The source index of each matched line is the line index.
+///
+/// The lines of the same score are in the original order, all the lines are matched if `query`
+/// is empty.
+pub fn filter_lines<'a, T>(
+    query: &str,
+    lines: impl Iterator<Item = (usize, &'a str, T)>,
+    algo: Option<Algo>,
+    rank: &Rank,
+) -> Vec<(FuzzyMatchedLineInfo, T)> {
+    let scorer = get_appropriate_scorer(&select_algo(query, algo), &ContentFiltering::Full);
+
+    let mut filtered = lines
+        .filter_map(|(idx, line, extra)| {
+            let (score, indices) = if query.is_empty() {
+                (0, Vec::new())
+            } else {
+                scorer(line, query)?
+            };
+            Some(((line.into(), score, indices, idx), extra))
+        })
+        .collect::<Vec<_>>();
+    // Stable sort.
+    filtered.sort_by(|(a, _), (b, _)| rank.compare(a, b));
+    filtered
 }
 
 impl Blines {
     /// Looks for matches of `query` in lines of the current vim buffer.
-    pub fn run(&self, number: Option<usize>, winwidth: Option<usize>) -> Result<()> {
-        crate::cmd::filter::dynamic::dyn_fuzzy_filter_and_rank(
+    pub fn run(
+        &self,
+        number: Option<usize>,
+        winwidth: Option<usize>,
+        algo: Option<Algo>,
+    ) -> Result<()> {
+        if self.blame {
+            return self.run_blame(number, winwidth, algo);
+        }
+
+        dyn_fuzzy_filter_and_rank(
             &self.query,
-            Source::List(
-                std::fs::read_to_string(&self.input)?
-                    .lines()
-                    .enumerate()
-                    .map(|(idx, item)| format!("{} {}", idx + 1, item)),
-            ),
-            None,
+            Source::<std::iter::Empty<_>>::File(self.input.clone()),
+            algo,
             number,
             winwidth,
             None,
             ContentFiltering::Full,
         )
     }
+
+    /// Same with the normal mode, but the blame annotations of the filtered lines are returned in
+    /// a separate field `annotations`, so that the indices of matched elements are unaffected.
+    fn run_blame(
+        &self,
+        number: Option<usize>,
+        winwidth: Option<usize>,
+        algo: Option<Algo>,
+    ) -> Result<()> {
+        let contents = std::fs::read_to_string(&self.input)?;
+        let blames = blame_lines(&self.input, &contents)?;
+        let (filter, query) = BlameFilter::parse(&self.query);
+
+        // The source index of each filtered line is the 0-based line number.
+        let filtered = filter_lines(
+            &query,
+            contents
+                .lines()
+                .zip(blames.iter())
+                .enumerate()
+                .filter(|(_, (_, blame))| filter.matches(blame))
+                .map(|(lnum, (line, blame))| (lnum, line, blame)),
+            algo,
+            &Rank::default(),
+        );
+
+        let total = filtered.len();
+        let top = filtered.into_iter().take(number.unwrap_or(total)).map(
+            |((line, _score, indices, idx), blame)| (line, (idx, blame.annotation()), indices),
+        );
+
+        if number.is_none() {
+            for (text, (source_index, annotation), indices) in top {
+                println_json!(text, indices, source_index, annotation);
+            }
+            return Ok(());
+        }
+
+        let (truncated_lines, truncated_map) =
+            truncate_long_matched_lines(top, winwidth.unwrap_or(62), None);
+        let mut lines = Vec::with_capacity(truncated_lines.len());
+        let mut indices = Vec::with_capacity(truncated_lines.len());
+        let mut source_indices = Vec::with_capacity(truncated_lines.len());
+        let mut annotations = Vec::with_capacity(truncated_lines.len());
+        for (line, (source_index, annotation), idxs) in truncated_lines {
+            lines.push(line);
+            indices.push(idxs);
+            source_indices.push(source_index);
+            annotations.push(annotation);
+        }
+
+        if truncated_map.is_empty() {
+            print_json_with_length!(total, lines, indices, source_indices, annotations);
+        } else {
+            print_json_with_length!(
+                total,
+                lines,
+                indices,
+                source_indices,
+                annotations,
+                truncated_map
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_blame_filter() {
+    let (filter, query) = BlameFilter::parse("author:Jane since:2020-01-01 foo bar");
+    assert_eq!(query, "foo bar");
+    assert_eq!(filter.author.as_deref(), Some("jane"));
+
+    let blame = |author: &str, date: &str| BlameLine {
+        commit: "8a3c1f2".into(),
+        author: author.into(),
+        date: date.into(),
+    };
+    assert!(filter.matches(&blame("Jane Doe", "2020-05-25")));
+    assert!(!filter.matches(&blame("Jane Doe", "2019-12-31")));
+    assert!(!filter.matches(&blame("John", "2020-05-25")));
+
+    let (filter, query) = BlameFilter::parse("until:2020-05-25");
+    assert!(query.is_empty());
+    assert!(filter.matches(&blame("John", "2020-05-25")));
+    assert!(!filter.matches(&blame("Not Committed Yet", "")));
+}
+
+#[test]
+fn test_filter_lines() {
+    let lines = ["fn foo() {}", "", "let foo = 1;"];
+    let source = || lines.iter().enumerate().map(|(idx, line)| (idx, *line, ()));
+
+    let filtered = filter_lines("foo", source(), Some(Algo::SubString), &Rank::default());
+    let matched = filtered
+        .iter()
+        .map(|((line, _score, indices, idx), ())| {
+            let text = indices.iter().map(|&i| &line[i..=i]).collect::<String>();
+            (line.as_str(), text, *idx)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        matched,
+        vec![
+            ("fn foo() {}", "foo".into(), 0),
+            ("let foo = 1;", "foo".into(), 2)
+        ]
+    );
+
+    // The line number is not part of the line.
+    assert!(filter_lines("3", source(), Some(Algo::SubString), &Rank::default()).is_empty());
+    assert_eq!(filter_lines("", source(), None, &Rank::default()).len(), 3);
 }
@@ -1,5 +1,5 @@
 use crate::utils::{
-    calculate_hash, clap_cache_dir, get_cached_entry, read_first_lines, remove_dir_contents,
+    clap_cache_dir, get_cached_entry, hash_cmd_dir, read_first_lines, remove_dir_contents,
 };
 use anyhow::{anyhow, Result};
 use icon::IconPainter;
@@ -74,8 +74,8 @@ impl CacheEntry {
     pub fn new(cmd_args: &[&str], cmd_dir: Option<PathBuf>, total: usize) -> Result<PathBuf> {
         let mut dir = clap_cache_dir();
         dir.push(cmd_args.join("_"));
-        if let Some(mut cmd_dir) = cmd_dir {
-            dir.push(format!("{}", calculate_hash(&mut cmd_dir)));
+        if let Some(cmd_dir) = cmd_dir {
+            dir.push(format!("{}", hash_cmd_dir(&cmd_dir)));
         } else {
             dir.push("no_cmd_dir");
         }
@@ -0,0 +1,37 @@
+use crate::config::config_files;
+use anyhow::Result;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// List the config files and show the effective config.
+#[derive(StructOpt, Debug, Clone)]
+pub struct Config {
+    /// Print the effective config in TOML, i.e., the values used when no CLI flags are passed.
+    #[structopt(short, long)]
+    show: bool,
+
+    /// Specify the project directory, the current working directory by default.
+    #[structopt(long = "cmd-dir", parse(from_os_str))]
+    cmd_dir: Option<PathBuf>,
+}
+
+impl Config {
+    pub fn run(&self) -> Result<()> {
+        let dir = match self.cmd_dir {
+            Some(ref dir) => dir.clone(),
+            None => std::env::current_dir()?,
+        };
+
+        if self.show {
+            let config = crate::config::Config::load(&dir)?;
+            print!("{}", toml::to_string(&config)?);
+            return Ok(());
+        }
+
+        println!("Config files, the latter takes precedence:");
+        for path in config_files(&dir) {
+            println!("\t{}", path.display());
+        }
+        Ok(())
+    }
+}
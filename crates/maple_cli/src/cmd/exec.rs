@@ -1,7 +1,7 @@
 use crate::light_command::{set_current_dir, LightCommand};
 use anyhow::Result;
 use icon::IconPainter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use structopt::StructOpt;
 
@@ -20,12 +20,17 @@ pub struct Exec {
     #[structopt(long = "cmd-dir", parse(from_os_str))]
     cmd_dir: Option<PathBuf>,
 
-    /// Specify the threshold for writing the output of command to a tempfile.
-    #[structopt(long = "output-threshold", default_value = "100000")]
-    output_threshold: usize,
+    /// Specify the threshold for writing the output of command to a tempfile, 100000 by default.
+    #[structopt(long = "output-threshold")]
+    output_threshold: Option<usize>,
 }
 
 impl Exec {
+    /// Returns the working directory of command, where the project config is looked up.
+    pub fn cmd_dir(&self) -> Option<&Path> {
+        self.cmd_dir.as_deref()
+    }
+
     // This can work with the piped command, e.g., git ls-files | uniq.
     fn prepare_exec_cmd(&self) -> Command {
         let mut cmd = if cfg!(target_os = "windows") {
@@ -48,6 +53,7 @@ impl Exec {
         number: Option<usize>,
         icon_painter: Option<IconPainter>,
         no_cache: bool,
+        default_output_threshold: Option<usize>,
     ) -> Result<()> {
         let mut exec_cmd = self.prepare_exec_cmd();
 
@@ -56,7 +62,9 @@ impl Exec {
             number,
             self.output.clone(),
             icon_painter,
-            self.output_threshold,
+            self.output_threshold
+                .or(default_output_threshold)
+                .unwrap_or(100_000),
         );
 
         let args = self
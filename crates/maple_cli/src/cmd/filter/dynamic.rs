@@ -1,7 +1,6 @@
 use super::*;
-use fuzzy_filter::{get_appropriate_scorer, FuzzyMatchedLineInfo};
+use fuzzy_filter::{get_appropriate_scorer, FuzzyMatchedLineInfo, Rank, TagQualifiers};
 use icon::ICON_LEN;
-use rayon::slice::ParallelSliceMut;
 use std::io::{self, BufRead};
 use std::time::{Duration, Instant};
 
@@ -30,53 +29,51 @@ impl<T: Copy> Insert<T> for [T; ITEMS_TO_SHOW] {
 
 /// This macro is a special thing for [`dyn_collect_all`] and [`dyn_collect_number`].
 macro_rules! insert_both {
-    // This macro pushes all things into buffer, pops one worst item from each top queue
-    // and then inserts all things into `top_` queues.
-    (pop; $index:expr, $score:expr, $text:expr, $indices:expr => $buffer:expr, $top_results:expr, $top_scores:expr) => {{
+    // This macro pushes the item into buffer, pops one worst item from the top queue
+    // and then inserts the item into `top_` queue.
+    (pop; $index:expr, $item:expr => $buffer:expr, $top_results:expr) => {{
         match $index {
             // If index is last possible, then the worst item is better than this we want to push in,
             // and we do nothing.
-            Some(MAX_IDX) => $buffer.push(($text, $score, $indices)),
+            Some(MAX_IDX) => $buffer.push($item),
             // Else, one item gets popped from the queue
             // and other is inserted.
             Some(idx) => {
-                insert_both!(idx + 1, $score, $text, $indices => $buffer, $top_results, $top_scores);
+                insert_both!(idx + 1, $item => $buffer, $top_results);
             }
             None => {
-                insert_both!(0, $score, $text, $indices => $buffer, $top_results, $top_scores);
+                insert_both!(0, $item => $buffer, $top_results);
             }
         }
     }};
 
-    // This macro pushes all things into buffer and inserts all things into
-    // `top_` queues.
-    ($index:expr, $score:expr, $text:expr, $indices:expr => $buffer:expr, $top_results:expr, $top_scores:expr) => {{
-        $buffer.push(($text, $score, $indices));
+    // This macro pushes the item into buffer and inserts it into `top_` queue.
+    ($index:expr, $item:expr => $buffer:expr, $top_results:expr) => {{
+        $buffer.push($item);
         $top_results.pop_and_insert($index, $buffer.len() - 1);
-        $top_scores.pop_and_insert($index, $score);
     }};
 }
 
-type SelectedTopItemsInfo = (usize, [i64; ITEMS_TO_SHOW], [usize; ITEMS_TO_SHOW]);
+type SelectedTopItemsInfo = (usize, [usize; ITEMS_TO_SHOW]);
 
 /// Returns Ok if all items in the iterator has been processed.
 ///
-/// First, let's try to produce `ITEMS_TO_SHOW` items to fill the topscores.
+/// First, let's try to produce `ITEMS_TO_SHOW` items to fill the top queue.
 fn select_top_items_to_show(
     buffer: &mut Vec<FuzzyMatchedLineInfo>,
     iter: &mut impl Iterator<Item = FuzzyMatchedLineInfo>,
+    rank: &Rank,
 ) -> std::result::Result<usize, SelectedTopItemsInfo> {
-    let mut top_scores: [i64; ITEMS_TO_SHOW] = [i64::min_value(); ITEMS_TO_SHOW];
     let mut top_results: [usize; ITEMS_TO_SHOW] = [usize::min_value(); ITEMS_TO_SHOW];
 
     let mut total = 0;
-    let res = iter.try_for_each(|(text, score, indices)| {
-        let idx = match find_best_score_idx(&top_scores, score) {
+    let res = iter.try_for_each(|item| {
+        let idx = match find_best_score_idx(&top_results[..total], buffer, &item, rank) {
             Some(idx) => idx + 1,
             None => 0,
         };
 
-        insert_both!(idx, score, text, indices => buffer, top_results, top_scores);
+        insert_both!(idx, item => buffer, top_results);
 
         // Stop iterating after `ITEMS_TO_SHOW` iterations.
         total += 1;
@@ -90,20 +87,26 @@ fn select_top_items_to_show(
     if res.is_ok() {
         Ok(total)
     } else {
-        Err((total, top_scores, top_results))
+        Err((total, top_results))
     }
 }
 
-/// Returns the index of best score in `top_scores`.
+/// Returns the index of the worst item in `top_results` that is ranked before `item`.
 ///
-/// Best results are stored in front, the bigger the better.
+/// Best results are stored in front, the items of the same score and criteria are in the order
+/// of source, same with [`Rank::sort`].
 #[inline]
-fn find_best_score_idx(top_scores: &[i64; ITEMS_TO_SHOW], score: i64) -> Option<usize> {
-    top_scores
+fn find_best_score_idx(
+    top_results: &[usize],
+    buffer: &[FuzzyMatchedLineInfo],
+    item: &FuzzyMatchedLineInfo,
+    rank: &Rank,
+) -> Option<usize> {
+    top_results
         .iter()
         .enumerate()
         .rev() // .rev(), because worse items are at the end.
-        .find(|&(_, &other_score)| other_score > score)
+        .find(|&(_, &other)| rank.compare(&buffer[other], item) != std::cmp::Ordering::Greater)
         .map(|(idx, _)| idx)
 }
 
@@ -124,8 +127,10 @@ fn try_notify_top_results(
         if now > *past + UPDATE_INTERVAL {
             let mut indices = Vec::with_capacity(top_results_len);
             let mut lines = Vec::with_capacity(top_results_len);
+            let mut source_indices = Vec::with_capacity(top_results_len);
             for &idx in top_results.iter() {
-                let (text, _, idxs) = std::ops::Index::index(buffer, idx);
+                let (text, _, idxs, source_index) = std::ops::Index::index(buffer, idx);
+                source_indices.push(*source_index);
                 let text = if let Some(painter) = icon_painter {
                     indices.push(idxs.into_iter().map(|x| x + ICON_LEN).collect::<Vec<_>>());
                     painter.paint(&text)
@@ -137,7 +142,7 @@ fn try_notify_top_results(
             }
 
             if last_lines != lines.as_slice() {
-                print_json_with_length!(total, lines, indices);
+                print_json_with_length!(total, lines, indices, source_indices);
                 return Ok((now, Some(lines)));
             } else {
                 print_json_with_length!(total);
@@ -168,17 +173,18 @@ fn try_notify_top_results(
 fn dyn_collect_all(
     mut iter: impl Iterator<Item = FuzzyMatchedLineInfo>,
     icon_painter: &Option<IconPainter>,
+    rank: &Rank,
 ) -> Vec<FuzzyMatchedLineInfo> {
     let mut buffer = Vec::with_capacity({
         let (low, high) = iter.size_hint();
         high.unwrap_or(low)
     });
 
-    let should_return = select_top_items_to_show(&mut buffer, &mut iter);
+    let should_return = select_top_items_to_show(&mut buffer, &mut iter, rank);
 
-    let (mut total, mut top_scores, mut top_results) = match should_return {
+    let (mut total, mut top_results) = match should_return {
         Ok(_) => return buffer,
-        Err((t, top_scores, top_results)) => (t, top_scores, top_results),
+        Err((t, top_results)) => (t, top_results),
     };
 
     let mut last_lines = Vec::with_capacity(top_results.len());
@@ -186,10 +192,10 @@ fn dyn_collect_all(
     // Now we have the full queue and can just pair `.pop_back()` with `.insert()` to keep
     // the queue with best results the same size.
     let mut past = std::time::Instant::now();
-    iter.for_each(|(text, score, indices)| {
-        let idx = find_best_score_idx(&top_scores, score);
+    iter.for_each(|item| {
+        let idx = find_best_score_idx(&top_results, &buffer, &item, rank);
 
-        insert_both!(pop; idx, score, text, indices => buffer, top_results, top_scores);
+        insert_both!(pop; idx, item => buffer, top_results);
 
         total = total.wrapping_add(1);
 
@@ -227,16 +233,17 @@ fn dyn_collect_number(
     mut iter: impl Iterator<Item = FuzzyMatchedLineInfo>,
     number: usize,
     icon_painter: &Option<IconPainter>,
+    rank: &Rank,
 ) -> (usize, Vec<FuzzyMatchedLineInfo>) {
     // To not have problems with queues after sorting and truncating the buffer,
     // buffer has the lowest bound of `ITEMS_TO_SHOW * 2`, not `number * 2`.
     let mut buffer = Vec::with_capacity(2 * std::cmp::max(ITEMS_TO_SHOW, number));
 
-    let should_return = select_top_items_to_show(&mut buffer, &mut iter);
+    let should_return = select_top_items_to_show(&mut buffer, &mut iter, rank);
 
-    let (mut total, mut top_scores, mut top_results) = match should_return {
+    let (mut total, mut top_results) = match should_return {
         Ok(t) => return (t, buffer),
-        Err((t, top_scores, top_results)) => (t, top_scores, top_results),
+        Err((t, top_results)) => (t, top_results),
     };
 
     let mut last_lines = Vec::with_capacity(top_results.len());
@@ -244,10 +251,10 @@ fn dyn_collect_number(
     // Now we have the full queue and can just pair `.pop_back()` with `.insert()` to keep
     // the queue with best results the same size.
     let mut past = std::time::Instant::now();
-    iter.for_each(|(text, score, indices)| {
-        let idx = find_best_score_idx(&top_scores, score);
+    iter.for_each(|item| {
+        let idx = find_best_score_idx(&top_results, &buffer, &item, rank);
 
-        insert_both!(pop; idx, score, text, indices => buffer, top_results, top_scores);
+        insert_both!(pop; idx, item => buffer, top_results);
 
         total += 1;
 
@@ -267,11 +274,10 @@ fn dyn_collect_number(
         }
 
         if buffer.len() == buffer.capacity() {
-            buffer.par_sort_unstable_by(|(_, v1, _), (_, v2, _)| v2.partial_cmp(&v1).unwrap());
+            rank.sort(&mut buffer);
 
-            for (idx, (_, score, _)) in buffer[..ITEMS_TO_SHOW].iter().enumerate() {
-                top_scores[idx] = *score;
-                top_results[idx] = idx;
+            for (idx, top) in top_results.iter_mut().enumerate() {
+                *top = idx;
             }
 
             let half = buffer.len() / 2;
@@ -287,11 +293,15 @@ fn dyn_collect_number(
 // Generate an filtered iterator from Source::Stdin.
 macro_rules! source_iter_stdin {
     ( $scorer:ident ) => {
-        io::stdin().lock().lines().filter_map(|lines_iter| {
-            lines_iter
-                .ok()
-                .and_then(|line| $scorer(&line).map(|(score, indices)| (line, score, indices)))
-        })
+        io::stdin()
+            .lock()
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, lines_iter)| {
+                lines_iter.ok().and_then(|line| {
+                    $scorer(&line).map(|(score, indices)| (line, score, indices, idx))
+                })
+            })
     };
 }
 
@@ -300,10 +310,11 @@ macro_rules! source_iter_exec {
     ( $scorer:ident, $exec:ident ) => {
         std::io::BufReader::new($exec.stream_stdout()?)
             .lines()
-            .filter_map(|lines_iter| {
-                lines_iter
-                    .ok()
-                    .and_then(|line| $scorer(&line).map(|(score, indices)| (line, score, indices)))
+            .enumerate()
+            .filter_map(|(idx, lines_iter)| {
+                lines_iter.ok().and_then(|line| {
+                    $scorer(&line).map(|(score, indices)| (line, score, indices, idx))
+                })
             })
     };
 }
@@ -315,9 +326,10 @@ macro_rules! source_iter_file {
         // The line stream can contain invalid UTF-8 data.
         std::io::BufReader::new(std::fs::File::open($fpath)?)
             .lines()
-            .filter_map(|x| {
+            .enumerate()
+            .filter_map(|(idx, x)| {
                 x.ok().and_then(|line| {
-                    $scorer(&line).map(|(score, indices)| (line.into(), score, indices))
+                    $scorer(&line).map(|(score, indices)| (line.into(), score, indices, idx))
                 })
             })
     };
@@ -326,11 +338,25 @@ macro_rules! source_iter_file {
 // Generate an filtered iterator from Source::List(list).
 macro_rules! source_iter_list {
     ( $scorer:ident, $list:ident ) => {
-        $list.filter_map(|line| $scorer(&line).map(|(score, indices)| (line, score, indices)))
+        $list.enumerate().filter_map(|(idx, line)| {
+            $scorer(&line).map(|(score, indices)| (line, score, indices, idx))
+        })
     };
 }
 
+/// Returns the algo applied on `query`, the query containing a space is matched as a substring,
+/// `algo` defaults to fzy.
+pub fn select_algo(query: &str, algo: Option<Algo>) -> Algo {
+    if query.contains(' ') {
+        Algo::SubString
+    } else {
+        algo.unwrap_or(Algo::Fzy)
+    }
+}
+
 /// Returns the ranked results after applying fuzzy filter given the query string and a list of candidates.
+///
+/// The items of the same score are in the order of source.
 pub fn dyn_fuzzy_filter_and_rank<I: Iterator<Item = String>>(
     query: &str,
     source: Source<I>,
@@ -340,53 +366,91 @@ pub fn dyn_fuzzy_filter_and_rank<I: Iterator<Item = String>>(
     icon_painter: Option<IconPainter>,
     content_filtering: ContentFiltering,
 ) -> Result<()> {
-    let algo = if query.contains(" ") {
-        Algo::SubString
-    } else {
-        algo.unwrap_or(Algo::Fzy)
-    };
+    dyn_fuzzy_filter_and_rank_by(
+        query,
+        source,
+        algo,
+        number,
+        winwidth,
+        icon_painter,
+        content_filtering,
+        &Rank::default(),
+    )
+}
+
+/// Same with [`dyn_fuzzy_filter_and_rank`], but the ties of score are broken by `rank`.
+#[allow(clippy::too_many_arguments)]
+pub fn dyn_fuzzy_filter_and_rank_by<I: Iterator<Item = String>>(
+    query: &str,
+    source: Source<I>,
+    algo: Option<Algo>,
+    number: Option<usize>,
+    winwidth: Option<usize>,
+    icon_painter: Option<IconPainter>,
+    content_filtering: ContentFiltering,
+    rank: &Rank,
+) -> Result<()> {
+    if let ContentFiltering::TagNameOnly = content_filtering {
+        TagQualifiers::parse(query).map_err(|e| anyhow::anyhow!(e))?;
+    }
+    let algo = select_algo(query, algo);
     let scorer_fn = get_appropriate_scorer(&algo, &content_filtering);
     let scorer = |line: &str| scorer_fn(line, query);
     if let Some(number) = number {
         let (total, mut filtered) = match source {
-            Source::Stdin => dyn_collect_number(source_iter_stdin!(scorer), number, &icon_painter),
-            Source::Exec(exec) => {
-                dyn_collect_number(source_iter_exec!(scorer, exec), number, &icon_painter)
+            Source::Stdin => {
+                dyn_collect_number(source_iter_stdin!(scorer), number, &icon_painter, rank)
             }
-            Source::File(fpath) => {
-                dyn_collect_number(source_iter_file!(scorer, fpath), number, &icon_painter)
+            Source::Exec(exec) => {
+                dyn_collect_number(source_iter_exec!(scorer, exec), number, &icon_painter, rank)
             }
+            Source::File(fpath) => dyn_collect_number(
+                source_iter_file!(scorer, fpath),
+                number,
+                &icon_painter,
+                rank,
+            ),
             Source::List(list) => {
-                dyn_collect_number(source_iter_list!(scorer, list), number, &icon_painter)
+                dyn_collect_number(source_iter_list!(scorer, list), number, &icon_painter, rank)
             }
         };
-        filtered.sort_unstable_by(|a, b| b.1.cmp(&a.1));
-        let (lines, indices, truncated_map) = process_top_items(
-            number,
+        rank.sort(&mut filtered);
+        let items = top_matched_items(
             filtered.into_iter().take(number),
             winwidth.unwrap_or(62),
             icon_painter,
         );
+        let source_indices = items
+            .iter()
+            .map(|item| item.source_index)
+            .collect::<Vec<_>>();
+        let (lines, indices, truncated_map) = split_matched_items(items);
 
         if truncated_map.is_empty() {
-            print_json_with_length!(total, lines, indices);
+            print_json_with_length!(total, lines, indices, source_indices);
         } else {
-            print_json_with_length!(total, lines, indices, truncated_map);
+            print_json_with_length!(total, lines, indices, source_indices, truncated_map);
         }
     } else {
         let mut filtered = match source {
-            Source::Stdin => dyn_collect_all(source_iter_stdin!(scorer), &icon_painter),
-            Source::Exec(exec) => dyn_collect_all(source_iter_exec!(scorer, exec), &icon_painter),
-            Source::File(fpath) => dyn_collect_all(source_iter_file!(scorer, fpath), &icon_painter),
-            Source::List(list) => dyn_collect_all(source_iter_list!(scorer, list), &icon_painter),
+            Source::Stdin => dyn_collect_all(source_iter_stdin!(scorer), &icon_painter, rank),
+            Source::Exec(exec) => {
+                dyn_collect_all(source_iter_exec!(scorer, exec), &icon_painter, rank)
+            }
+            Source::File(fpath) => {
+                dyn_collect_all(source_iter_file!(scorer, fpath), &icon_painter, rank)
+            }
+            Source::List(list) => {
+                dyn_collect_all(source_iter_list!(scorer, list), &icon_painter, rank)
+            }
         };
 
-        filtered.par_sort_unstable_by(|(_, v1, _), (_, v2, _)| v2.partial_cmp(&v1).unwrap());
+        rank.sort(&mut filtered);
 
         let ranked = filtered;
 
-        for (text, _, indices) in ranked.iter() {
-            println_json!(text, indices);
+        for (text, _, indices, source_index) in ranked.iter() {
+            println_json!(text, indices, source_index);
         }
     }
 
@@ -397,6 +461,36 @@ pub fn dyn_fuzzy_filter_and_rank<I: Iterator<Item = String>>(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_top_items_by_rank() {
+        // Returns the source indices of the top items of the same score.
+        let top_source_indices = |rank: &Rank| {
+            let mut iter = (0..ITEMS_TO_SHOW * 2)
+                .map(|idx| ("x".repeat(ITEMS_TO_SHOW * 2 - idx), 1, vec![0], idx));
+            let mut buffer = Vec::new();
+            let (_, mut top_results) =
+                select_top_items_to_show(&mut buffer, &mut iter, rank).unwrap_err();
+            iter.for_each(|item| {
+                let idx = find_best_score_idx(&top_results, &buffer, &item, rank);
+                insert_both!(pop; idx, item => buffer, top_results);
+            });
+            top_results
+                .iter()
+                .map(|&idx| buffer[idx].3)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            top_source_indices(&Rank::default()),
+            (0..ITEMS_TO_SHOW).collect::<Vec<_>>()
+        );
+        // The shorter lines come later in the source.
+        assert_eq!(
+            top_source_indices(&"length".parse().unwrap()),
+            (ITEMS_TO_SHOW..ITEMS_TO_SHOW * 2).rev().collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     // This is a very time-consuming test,
     // results of which could be proved only be inspecting stdout.
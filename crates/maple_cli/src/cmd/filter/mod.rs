@@ -1,11 +1,13 @@
 pub mod dynamic;
 
-pub use dynamic::dyn_fuzzy_filter_and_rank as dyn_run;
+pub use dynamic::{dyn_fuzzy_filter_and_rank as dyn_run, dyn_fuzzy_filter_and_rank_by};
 
 use anyhow::Result;
-use fuzzy_filter::{fuzzy_filter_and_rank, subprocess, Algo, ContentFiltering, Source};
-use icon::{IconPainter, ICON_LEN};
-use printer::{truncate_long_matched_lines, LinesTruncatedMap};
+use fuzzy_filter::{
+    fuzzy_filter_and_rank, subprocess, Algo, ContentFiltering, FuzzyMatchedLineInfo, Rank, Source,
+};
+use icon::IconPainter;
+use printer::{split_matched_items, truncate_into_matched_items, LinesTruncatedMap, MatchedItem};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -39,6 +41,13 @@ pub struct Filter {
     /// Synchronous filtering, returns after the input stream is complete.
     #[structopt(short, long)]
     sync: bool,
+
+    /// Criteria of breaking the ties of score in order, e.g., `length,index`.
+    ///
+    /// Possible values: length, index, depth, begin. The items of the same score are in the
+    /// order of source if omitted.
+    #[structopt(long)]
+    rank: Option<Rank>,
 }
 
 impl Filter {
@@ -62,6 +71,7 @@ impl Filter {
     #[inline]
     fn sync_run(
         &self,
+        algo: Option<Algo>,
         number: Option<usize>,
         winwidth: Option<usize>,
         icon_painter: Option<IconPainter>,
@@ -69,30 +79,33 @@ impl Filter {
         run::<std::iter::Empty<_>>(
             &self.query,
             self.generate_source(),
-            self.algo.clone(),
+            algo,
             number,
             icon_painter,
             winwidth,
+            &self.rank.clone().unwrap_or_default(),
         )
     }
 
     #[inline]
     fn dyn_run(
         &self,
+        algo: Option<Algo>,
         number: Option<usize>,
         winwidth: Option<usize>,
         icon_painter: Option<IconPainter>,
     ) -> Result<()> {
-        dyn_run::<std::iter::Empty<_>>(
+        dyn_fuzzy_filter_and_rank_by::<std::iter::Empty<_>>(
             &self.query,
             self.generate_source(),
-            self.algo.clone(),
+            algo,
             number,
             winwidth,
             icon_painter,
             self.content_filtering
                 .clone()
                 .unwrap_or(ContentFiltering::Full),
+            &self.rank.clone().unwrap_or_default(),
         )
     }
 
@@ -101,43 +114,72 @@ impl Filter {
         number: Option<usize>,
         winwidth: Option<usize>,
         icon_painter: Option<IconPainter>,
+        default_algo: Option<Algo>,
     ) -> Result<()> {
+        let algo = self.algo.clone().or(default_algo);
         if self.sync {
-            self.sync_run(number, winwidth, icon_painter)?;
+            self.sync_run(algo, number, winwidth, icon_painter)?;
         } else {
-            self.dyn_run(number, winwidth, icon_painter)?;
+            self.dyn_run(algo, number, winwidth, icon_painter)?;
         }
         Ok(())
     }
 }
 
+/// Returns the truncated top items ranked by the filtering score, the icon is prepended if
+/// `icon_painter` is given.
+pub fn top_matched_items(
+    top_list: impl IntoIterator<Item = FuzzyMatchedLineInfo>,
+    winwidth: usize,
+    icon_painter: Option<IconPainter>,
+) -> Vec<MatchedItem> {
+    let items = truncate_into_matched_items(top_list, winwidth, None);
+    match icon_painter {
+        Some(painter) => items
+            .into_iter()
+            .map(|item| {
+                let icon = painter.get_icon(&item.text);
+                item.with_icon(icon)
+            })
+            .collect(),
+        None => items,
+    }
+}
+
 /// Returns the info of the truncated top items ranked by the filtering score.
-fn process_top_items<T>(
-    top_size: usize,
-    top_list: impl IntoIterator<Item = (String, T, Vec<usize>)>,
+fn process_top_items(
+    top_list: impl IntoIterator<Item = FuzzyMatchedLineInfo>,
     winwidth: usize,
     icon_painter: Option<IconPainter>,
 ) -> (Vec<String>, Vec<Vec<usize>>, LinesTruncatedMap) {
-    let (truncated_lines, truncated_map) = truncate_long_matched_lines(top_list, winwidth, None);
-    let mut lines = Vec::with_capacity(top_size);
-    let mut indices = Vec::with_capacity(top_size);
-    if let Some(painter) = icon_painter {
-        for (idx, (text, _, idxs)) in truncated_lines.iter().enumerate() {
-            let iconized = if let Some(origin_text) = truncated_map.get(&(idx + 1)) {
-                format!("{} {}", painter.get_icon(origin_text), text)
-            } else {
-                painter.paint(&text)
-            };
-            lines.push(iconized);
-            indices.push(idxs.into_iter().map(|x| x + ICON_LEN).collect());
+    split_matched_items(top_matched_items(top_list, winwidth, icon_painter))
+}
+
+/// Prints the `ranked` items, only the top `number` items are printed along with the total
+/// number if `number` is given.
+pub fn print_ranked_items(
+    ranked: Vec<FuzzyMatchedLineInfo>,
+    number: Option<usize>,
+    winwidth: Option<usize>,
+    icon_painter: Option<IconPainter>,
+) {
+    if let Some(number) = number {
+        let total = ranked.len();
+        let (lines, indices, truncated_map) = process_top_items(
+            ranked.into_iter().take(number),
+            winwidth.unwrap_or(62),
+            icon_painter,
+        );
+        if truncated_map.is_empty() {
+            print_json_with_length!(total, lines, indices);
+        } else {
+            print_json_with_length!(total, lines, indices, truncated_map);
         }
     } else {
-        for (text, _, idxs) in truncated_lines {
-            lines.push(text);
-            indices.push(idxs);
+        for (text, _, indices, _) in ranked {
+            println_json!(text, indices);
         }
     }
-    (lines, indices, truncated_map)
 }
 
 pub fn run<I: Iterator<Item = String>>(
@@ -147,13 +189,13 @@ pub fn run<I: Iterator<Item = String>>(
     number: Option<usize>,
     icon_painter: Option<IconPainter>,
     winwidth: Option<usize>,
+    rank: &Rank,
 ) -> Result<()> {
-    let ranked = fuzzy_filter_and_rank(query, source, algo.unwrap_or(Algo::Fzy))?;
+    let ranked = fuzzy_filter_and_rank(query, source, algo.unwrap_or(Algo::Fzy), rank)?;
 
     if let Some(number) = number {
         let total = ranked.len();
         let (lines, indices, truncated_map) = process_top_items(
-            number,
             ranked.into_iter().take(number),
             winwidth.unwrap_or(62),
             icon_painter,
@@ -164,7 +206,7 @@ pub fn run<I: Iterator<Item = String>>(
             println_json!(total, lines, indices, truncated_map);
         }
     } else {
-        for (text, _, indices) in ranked.iter() {
+        for (text, _, indices, _) in ranked.iter() {
             println_json!(text, indices);
         }
     }
@@ -0,0 +1,202 @@
+//! Blame of the lines of a file, which is cached until the file or HEAD changes.
+
+use super::history::format_time;
+use super::{open_repo, repo_relative_path};
+use crate::utils::{calculate_hash, clap_cache_dir};
+use anyhow::Result;
+use git2::{DiffOptions, Patch, Repository};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Who and when last changed a line.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct BlameLine {
+    /// Short hash of the commit, `0000000` for the uncommitted line.
+    pub commit: String,
+    pub author: String,
+    /// Date of the commit, i.e., `YYYY-MM-DD`, empty for the uncommitted line.
+    pub date: String,
+}
+
+impl BlameLine {
+    fn uncommitted() -> Self {
+        Self {
+            commit: "0000000".into(),
+            author: "Not Committed Yet".into(),
+            date: String::new(),
+        }
+    }
+
+    /// Returns the annotation displayed along with the line, e.g., `8a3c1f2 Jane 2020-05-25`.
+    pub fn annotation(&self) -> String {
+        format!("{} {} {}", self.commit, self.author, self.date)
+            .trim_end()
+            .into()
+    }
+}
+
+/// Blame of a file, `key` is the hash of the contents and HEAD it's computed from.
+#[derive(Serialize, Deserialize, Debug)]
+struct BlameCache {
+    key: u64,
+    lines: Vec<BlameLine>,
+}
+
+fn cache_path(file: &Path) -> PathBuf {
+    let mut path = clap_cache_dir();
+    path.push("blame");
+    path.push(format!("{}.json", calculate_hash(&file)));
+    path
+}
+
+/// Returns the line numbers in HEAD of each line of `contents`, `None` for the uncommitted line.
+///
+/// All the lines are uncommitted if `rel_path` does not exist in HEAD.
+fn map_to_head(repo: &Repository, rel_path: &Path, contents: &str) -> Result<Vec<Option<usize>>> {
+    let total = contents.lines().count();
+
+    let entry = match repo.head()?.peel_to_tree()?.get_path(rel_path) {
+        Ok(entry) => entry,
+        Err(_) => return Ok(vec![None; total]),
+    };
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+
+    let mut opts = DiffOptions::new();
+    opts.context_lines(0);
+    let patch =
+        Patch::from_blob_and_buffer(&blob, None, contents.as_bytes(), None, Some(&mut opts))?;
+
+    let mut mapping = Vec::with_capacity(total);
+    // Next line number of HEAD and the current contents, the lines between the diff lines are
+    // unchanged.
+    let mut old = 1;
+    let mut new = 1;
+    let fill_unchanged = |mapping: &mut Vec<Option<usize>>, old: &mut usize, count: usize| {
+        for _ in 0..count {
+            mapping.push(Some(*old));
+            *old += 1;
+        }
+    };
+    for hunk_idx in 0..patch.num_hunks() {
+        for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+            match (line.origin(), line.old_lineno(), line.new_lineno()) {
+                ('+', _, Some(new_lnum)) => {
+                    let new_lnum = new_lnum as usize;
+                    fill_unchanged(&mut mapping, &mut old, new_lnum - new);
+                    mapping.push(None);
+                    new = new_lnum + 1;
+                }
+                ('-', Some(old_lnum), _) => {
+                    let old_lnum = old_lnum as usize;
+                    let count = old_lnum - old;
+                    fill_unchanged(&mut mapping, &mut old, count);
+                    new += count;
+                    old = old_lnum + 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    let rest = total.saturating_sub(mapping.len());
+    fill_unchanged(&mut mapping, &mut old, rest);
+
+    Ok(mapping)
+}
+
+/// Returns the blame of each line of `contents`, which is the current contents of `file`, the
+/// uncommitted changes are taken into account.
+///
+/// The blame is computed once and cached until the contents of file or HEAD changes.
+pub fn blame_lines(file: &Path, contents: &str) -> Result<Vec<BlameLine>> {
+    let file = file.canonicalize()?;
+    let (repo, prefix) = open_repo(file.parent().unwrap_or(&file))?;
+    let head = repo.head()?.peel_to_commit()?.id();
+
+    let key = calculate_hash(&(contents, head.as_bytes()));
+    let cache = cache_path(&file);
+    if let Some(cached) = File::open(&cache)
+        .ok()
+        .and_then(|f| serde_json::from_reader::<_, BlameCache>(BufReader::new(f)).ok())
+    {
+        if cached.key == key {
+            return Ok(cached.lines);
+        }
+    }
+
+    let rel_path = repo_relative_path(&repo, &prefix, &file)?;
+    let mapping = map_to_head(&repo, &rel_path, contents)?;
+    let blame = if mapping.iter().any(Option::is_some) {
+        Some(repo.blame_file(&rel_path, None)?)
+    } else {
+        None
+    };
+
+    let lines = mapping
+        .into_iter()
+        .map(|head_lnum| {
+            head_lnum
+                .and_then(|lnum| blame.as_ref().and_then(|b| b.get_line(lnum)))
+                .map(|hunk| {
+                    let signature = hunk.final_signature();
+                    BlameLine {
+                        commit: hunk.final_commit_id().to_string()[..7].into(),
+                        author: signature.name().unwrap_or_default().into(),
+                        date: format_time(signature.when(), false),
+                    }
+                })
+                .unwrap_or_else(BlameLine::uncommitted)
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(parent) = cache.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let cached = BlameCache { key, lines };
+    serde_json::to_writer(BufWriter::new(File::create(&cache)?), &cached)?;
+
+    Ok(cached.lines)
+}
+
+#[test]
+fn test_blame_lines() {
+    use git2::{Signature, Time};
+
+    let dir = std::env::temp_dir().join(format!("clap_test_git_blame_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let repo = Repository::init(&dir).unwrap();
+
+    let file = dir.join("a.txt");
+    fs::write(&file, "one\ntwo\nthree\nfour\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("a.txt")).unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = Signature::new("Jane", "jane@example.com", &Time::new(1590386589, 0)).unwrap();
+    let commit = repo
+        .commit(Some("HEAD"), &sig, &sig, "Add a.txt", &tree, &[])
+        .unwrap();
+    let short = commit.to_string()[..7].to_string();
+
+    // Insert a line and delete a line without committing.
+    let contents = "zero\none\ntwo\nfour\n";
+    let lines = blame_lines(&file, contents).unwrap();
+    let annotations = lines.iter().map(BlameLine::annotation).collect::<Vec<_>>();
+    let committed = format!("{} Jane 2020-05-25", short);
+    assert_eq!(
+        annotations,
+        vec![
+            "0000000 Not Committed Yet".to_string(),
+            committed.clone(),
+            committed.clone(),
+            committed,
+        ]
+    );
+    // Read from the cache.
+    assert_eq!(blame_lines(&file, contents).unwrap(), lines);
+
+    fs::remove_file(cache_path(&file.canonicalize().unwrap())).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+}
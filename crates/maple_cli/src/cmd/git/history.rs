@@ -0,0 +1,419 @@
+//! Commits of git repo and the diff of a commit.
+
+use super::{open_repo, print_lines, repo_relative_path};
+use anyhow::Result;
+use git2::{Commit, Delta, DiffFindOptions, Oid, Patch, Repository, Sort, Time};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// A commit in the history.
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub date: String,
+    pub subject: String,
+}
+
+impl CommitInfo {
+    fn new(commit: &Commit) -> Self {
+        let hash = commit.id().to_string();
+        Self {
+            short_hash: hash[..7].into(),
+            hash,
+            author: commit.author().name().unwrap_or_default().into(),
+            date: format_time(commit.time(), false),
+            subject: commit.summary().unwrap_or_default().into(),
+        }
+    }
+
+    /// Returns the display line, i.e., `git log --format='%cd %h %s (%an)' --date=short`.
+    pub fn format(&self) -> String {
+        format!(
+            "{} {} {} ({})",
+            self.date, self.short_hash, self.subject, self.author
+        )
+    }
+}
+
+/// Returns the civil date `(year, month, day)` of the days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = (if z >= 0 { z } else { z - 146_096 }) / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Returns `2020-05-25` or `2020-05-25 14:03:09 +0800` if `with_clock` is true, in the timezone
+/// of the commit.
+pub(super) fn format_time(time: Time, with_clock: bool) -> String {
+    let offset = i64::from(time.offset_minutes());
+    let secs = time.seconds() + offset * 60;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+    let date = format!("{:04}-{:02}-{:02}", year, month, day);
+    if !with_clock {
+        return date;
+    }
+    let secs_of_day = secs.rem_euclid(86400);
+    format!(
+        "{} {:02}:{:02}:{:02} {}{:02}{:02}",
+        date,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// Returns the id of file `path` in the tree of `commit`.
+fn entry_id(commit: &Commit, path: &Path) -> Option<Oid> {
+    commit
+        .tree()
+        .ok()
+        .and_then(|tree| tree.get_path(path).ok())
+        .map(|entry| entry.id())
+}
+
+/// Returns the old path of `path` if it's renamed from another file by `commit`.
+fn renamed_from(
+    repo: &Repository,
+    parent: &Commit,
+    commit: &Commit,
+    path: &Path,
+) -> Result<Option<PathBuf>> {
+    let mut diff = repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&commit.tree()?), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    Ok(diff
+        .deltas()
+        .find(|delta| delta.status() == Delta::Renamed && delta.new_file().path() == Some(path))
+        .and_then(|delta| delta.old_file().path().map(Path::to_path_buf)))
+}
+
+/// Returns true if `commit` changes the file `path`, `path` is updated to the old path if the
+/// file is renamed by `commit`, which is used for the older commits.
+fn touches_path(repo: &Repository, commit: &Commit, path: &mut PathBuf) -> Result<bool> {
+    let id = match entry_id(commit, path) {
+        Some(id) => id,
+        None => return Ok(false),
+    };
+
+    let parents = commit.parents().collect::<Vec<_>>();
+    // Same as git log, the commit having the same file with one of its parents is skipped.
+    if parents
+        .iter()
+        .any(|parent| entry_id(parent, path) == Some(id))
+    {
+        return Ok(false);
+    }
+
+    if let Some(parent) = parents.first() {
+        if entry_id(parent, path).is_none() {
+            if let Some(old_path) = renamed_from(repo, parent, commit, path)? {
+                *path = old_path;
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Returns the commits reachable from HEAD in the order of time, only the commits changing `file`
+/// are returned if it's specified, the renames of `file` are followed, i.e., `git log --follow`.
+///
+/// `file` is relative to `dir` if it's not absolute.
+pub fn list_commits(dir: &Path, file: Option<&Path>) -> Result<Vec<CommitInfo>> {
+    let (repo, prefix) = open_repo(dir)?;
+
+    let mut path = match file {
+        Some(file) => Some(repo_relative_path(&repo, &prefix, file)?),
+        None => None,
+    };
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(Sort::TIME)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if let Some(ref mut path) = path {
+            if !touches_path(&repo, &commit, path)? {
+                continue;
+            }
+        }
+        commits.push(CommitInfo::new(&commit));
+    }
+
+    Ok(commits)
+}
+
+/// Metadata of a hunk in the diff.
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct HunkInfo {
+    /// Path of the file the hunk belongs to.
+    pub path: String,
+    /// Index of the hunk header `@@ -a,b +c,d @@` in the lines of diff.
+    pub line: usize,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+}
+
+/// Diff of a commit, i.e., the output of `git show`.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct CommitDiff {
+    pub lines: Vec<String>,
+    pub hunks: Vec<HunkInfo>,
+}
+
+/// Returns the diff of commit `rev` against its first parent, only the diff of `file` is included
+/// if it's specified, the renames are detected so that the rename of `file` is included too. At
+/// most `max_lines` lines are returned.
+///
+/// The whole diff is returned if `file` is not changed by `rev`.
+pub fn commit_diff(
+    dir: &Path,
+    rev: &str,
+    file: Option<&Path>,
+    max_lines: Option<usize>,
+) -> Result<CommitDiff> {
+    let (repo, prefix) = open_repo(dir)?;
+    let commit = repo.revparse_single(rev)?.peel_to_commit()?;
+    let max_lines = max_lines.unwrap_or(usize::MAX);
+
+    let author = commit.author();
+    let mut lines = vec![
+        format!("commit {}", commit.id()),
+        format!(
+            "Author: {} <{}>",
+            author.name().unwrap_or_default(),
+            author.email().unwrap_or_default()
+        ),
+        format!("Date:   {}", format_time(author.when(), true)),
+        String::new(),
+    ];
+    lines.extend(
+        commit
+            .message()
+            .unwrap_or_default()
+            .lines()
+            .map(|line| format!("    {}", line)),
+    );
+    lines.push(String::new());
+
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parents().next() {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+
+    // The pathspec is not used for `file` as the old path of a renamed file would be excluded,
+    // then the rename could not be detected.
+    let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+    let mut deltas = (0..diff.deltas().len()).collect::<Vec<_>>();
+    if let Some(file) = file {
+        let path = repo_relative_path(&repo, &prefix, file)?;
+        let touched = diff
+            .deltas()
+            .enumerate()
+            .filter(|(_, delta)| {
+                delta.new_file().path() == Some(&path) || delta.old_file().path() == Some(&path)
+            })
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        if !touched.is_empty() {
+            deltas = touched;
+        }
+    }
+
+    let mut hunks = Vec::new();
+    for idx in deltas {
+        if lines.len() >= max_lines {
+            break;
+        }
+        let mut patch = match Patch::from_diff(&diff, idx)? {
+            Some(patch) => patch,
+            None => continue,
+        };
+        let printed = patch.print(&mut |delta, hunk, line| {
+            if lines.len() >= max_lines {
+                return false;
+            }
+            if let (Some(hunk), 'H') = (hunk, line.origin()) {
+                hunks.push(HunkInfo {
+                    path: delta
+                        .new_file()
+                        .path()
+                        .map(|p| p.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    line: lines.len(),
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                });
+            }
+            let content = String::from_utf8_lossy(line.content());
+            match line.origin() {
+                origin @ '+' | origin @ '-' | origin @ ' ' => {
+                    lines.push(format!("{}{}", origin, content.trim_end_matches('\n')))
+                }
+                _ => lines.extend(content.lines().map(Into::into)),
+            }
+            true
+        });
+
+        // Aborting the print once the lines are enough is reported as an error.
+        if lines.len() < max_lines {
+            printed?;
+        }
+    }
+    lines.truncate(max_lines);
+
+    Ok(CommitDiff { lines, hunks })
+}
+
+/// List the commits of git repo without spawning git.
+#[derive(StructOpt, Debug, Clone)]
+pub struct Commits {
+    /// Initial query string, print all the commits if it's empty.
+    #[structopt(index = 1, short, long, default_value = "")]
+    query: String,
+
+    /// Specify the working directory, the project root normally.
+    #[structopt(long = "cmd-dir", parse(from_os_str))]
+    cmd_dir: Option<PathBuf>,
+
+    /// List only the commits changing this file, the renames are followed.
+    #[structopt(long, parse(from_os_str))]
+    file: Option<PathBuf>,
+}
+
+impl Commits {
+    pub fn run(&self, number: Option<usize>, winwidth: Option<usize>) -> Result<()> {
+        let dir = match self.cmd_dir {
+            Some(ref dir) => dir.clone(),
+            None => std::env::current_dir()?,
+        };
+
+        let lines = list_commits(&dir, self.file.as_deref())?
+            .iter()
+            .map(CommitInfo::format)
+            .collect();
+
+        print_lines(&self.query, lines, number, winwidth, None)
+    }
+}
+
+#[test]
+fn test_format_time() {
+    // 2020-05-25 06:03:09 UTC
+    assert_eq!(format_time(Time::new(1590386589, 0), false), "2020-05-25");
+    assert_eq!(
+        format_time(Time::new(1590386589, 480), true),
+        "2020-05-25 14:03:09 +0800"
+    );
+    assert_eq!(
+        format_time(Time::new(1590386589, -420), true),
+        "2020-05-24 23:03:09 -0700"
+    );
+    assert_eq!(format_time(Time::new(951782400, 0), false), "2000-02-29");
+}
+
+#[test]
+fn test_file_history() {
+    use git2::{IndexAddOption, Signature};
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("clap_test_git_history_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let repo = Repository::init(&dir).unwrap();
+
+    let commit_all = |time: i64, message: &str| {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.update_all(["*"].iter(), None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::new("Jane", "jane@example.com", &Time::new(time, 0)).unwrap();
+        let parents = repo
+            .head()
+            .ok()
+            .and_then(|head| head.peel_to_commit().ok())
+            .into_iter()
+            .collect::<Vec<_>>();
+        let parents = parents.iter().collect::<Vec<_>>();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap();
+    };
+
+    let contents = (1..=20)
+        .map(|i| format!("line {}\n", i))
+        .collect::<String>();
+    fs::write(dir.join("old.rs"), &contents).unwrap();
+    fs::write(dir.join("other.rs"), "other").unwrap();
+    commit_all(1590000000, "Add old.rs");
+
+    fs::write(dir.join("other.rs"), "changed").unwrap();
+    commit_all(1590000100, "Change other.rs");
+
+    fs::rename(dir.join("old.rs"), dir.join("new.rs")).unwrap();
+    commit_all(1590000200, "Rename old.rs");
+
+    fs::write(dir.join("new.rs"), contents.replace("line 10", "line ten")).unwrap();
+    commit_all(1590000300, "Change new.rs");
+
+    let subjects =
+        |commits: Vec<CommitInfo>| commits.into_iter().map(|c| c.subject).collect::<Vec<_>>();
+
+    assert_eq!(list_commits(&dir, None).unwrap().len(), 4);
+    assert_eq!(
+        subjects(list_commits(&dir, Some(Path::new("new.rs"))).unwrap()),
+        vec!["Change new.rs", "Rename old.rs", "Add old.rs"]
+    );
+
+    let commit = list_commits(&dir, None).unwrap().remove(0);
+    assert_eq!(
+        commit.format(),
+        format!("2020-05-20 {} Change new.rs (Jane)", commit.short_hash)
+    );
+
+    let diff = commit_diff(&dir, &commit.hash, Some(Path::new("new.rs")), None).unwrap();
+    assert_eq!(diff.lines[0], format!("commit {}", commit.hash));
+    assert_eq!(diff.hunks.len(), 1);
+    let hunk = &diff.hunks[0];
+    assert_eq!(hunk.path, "new.rs");
+    assert!(diff.lines[hunk.line].starts_with("@@ -7,7 +7,7 @@"));
+    assert!(diff.lines.contains(&"-line 10".to_string()));
+    assert!(diff.lines.contains(&"+line ten".to_string()));
+
+    let diff = commit_diff(&dir, &commit.hash, None, Some(3)).unwrap();
+    assert_eq!(diff.lines.len(), 3);
+
+    // The rename is shown instead of the deletion of old.rs and the addition of new.rs.
+    let renamed = list_commits(&dir, Some(Path::new("new.rs")))
+        .unwrap()
+        .remove(1);
+    let diff = commit_diff(&dir, &renamed.hash, Some(Path::new("new.rs")), None).unwrap();
+    assert!(diff.lines.contains(&"rename from old.rs".to_string()));
+    assert!(diff.lines.contains(&"rename to new.rs".to_string()));
+    assert!(diff.hunks.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,345 @@
+//! Lists the files, commits and blame of git repo by reading the index, status and history via libgit2
+//! directly instead of spawning git, which is much faster for the large repos.
+
+mod blame;
+mod history;
+
+pub use self::blame::{blame_lines, BlameLine};
+pub use self::history::{commit_diff, list_commits, CommitDiff, CommitInfo, Commits, HunkInfo};
+
+use crate::cmd::filter::dynamic::select_algo;
+use crate::cmd::filter::print_ranked_items;
+use crate::ContentFiltering;
+use anyhow::{anyhow, Result};
+use fuzzy_filter::{get_appropriate_scorer, Algo, FuzzyMatchedLineInfo, Rank, Source};
+use git2::{Repository, Status, StatusOptions};
+use icon::IconPainter;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Opens the git repo containing `dir`, returns the repo and the path of `dir` relative to the
+/// workdir of repo, which is used to output the paths relative to `dir` as git does.
+fn open_repo(dir: &Path) -> Result<(Repository, PathBuf)> {
+    let repo = Repository::discover(dir)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("{} is in a bare repo", dir.display()))?
+        .canonicalize()?;
+    let prefix = dir
+        .canonicalize()?
+        .strip_prefix(&workdir)
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    Ok((repo, prefix))
+}
+
+/// Returns the path of `file` relative to the workdir of repo, `file` is relative to the
+/// directory `prefix` of repo if it's not absolute.
+fn repo_relative_path(repo: &Repository, prefix: &Path, file: &Path) -> Result<PathBuf> {
+    if file.is_absolute() {
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| anyhow!("{} is in a bare repo", file.display()))?
+            .canonicalize()?;
+        let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        Ok(file.strip_prefix(&workdir)?.to_path_buf())
+    } else {
+        Ok(prefix.join(file))
+    }
+}
+
+/// Returns `path` relative to `prefix`, `None` if `path` is not under `prefix`.
+fn relative_to(path: &str, prefix: &Path) -> Option<String> {
+    Path::new(path)
+        .strip_prefix(prefix)
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+}
+
+/// Returns the tracked files under `dir`, i.e., `git ls-files | uniq`.
+pub fn tracked_files(dir: &Path) -> Result<Vec<String>> {
+    let (repo, prefix) = open_repo(dir)?;
+    let mut files: Vec<String> = repo
+        .index()?
+        .iter()
+        .filter_map(|entry| relative_to(&String::from_utf8_lossy(&entry.path), &prefix))
+        .collect();
+    // The conflicted file has multiple entries of different stages.
+    files.dedup();
+    Ok(files)
+}
+
+/// Returns the two-letter status marker `XY` of `git status --short`.
+fn status_marker(status: Status) -> String {
+    if status.is_conflicted() {
+        return "UU".into();
+    }
+    if status.is_wt_new() {
+        return "??".into();
+    }
+    let x = if status.is_index_new() {
+        'A'
+    } else if status.is_index_modified() {
+        'M'
+    } else if status.is_index_deleted() {
+        'D'
+    } else if status.is_index_renamed() {
+        'R'
+    } else if status.is_index_typechange() {
+        'T'
+    } else {
+        ' '
+    };
+    let y = if status.is_wt_modified() {
+        'M'
+    } else if status.is_wt_deleted() {
+        'D'
+    } else if status.is_wt_renamed() {
+        'R'
+    } else if status.is_wt_typechange() {
+        'T'
+    } else {
+        ' '
+    };
+    format!("{}{}", x, y)
+}
+
+/// Returns the path of line in the format of `git status --short`, the renamed file
+/// `R  old -> new` is resolved to the new path.
+pub fn strip_status_marker(line: &str) -> &str {
+    let path = match line.get(2..3) {
+        Some(" ") => &line[3..],
+        _ => line,
+    };
+    path.rsplit(" -> ").next().unwrap_or(path)
+}
+
+/// Returns the files matching `query`, the path is matched in the same way as the `files`
+/// provider, i.e., [`ContentFiltering::FilePath`]. The status marker of the `changed` files is
+/// not matched, the indices are still of the full line.
+///
+/// The files of the same score are in the original order, all the files are matched if `query`
+/// is empty.
+pub fn filter_files(
+    query: &str,
+    files: Vec<String>,
+    changed: bool,
+    algo: Option<Algo>,
+    rank: &Rank,
+) -> Vec<FuzzyMatchedLineInfo> {
+    let scorer = get_appropriate_scorer(&select_algo(query, algo), &ContentFiltering::FilePath);
+    let mut filtered = files
+        .into_iter()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            if query.is_empty() {
+                return Some((line, 0, Vec::new(), idx));
+            }
+            let path = if changed {
+                strip_status_marker(&line)
+            } else {
+                &line
+            };
+            // The path is always the tail of line.
+            let offset = line[..line.len() - path.len()].chars().count();
+            let (score, indices) = scorer(path, query)?;
+            let indices = indices.into_iter().map(|x| x + offset).collect();
+            Some((line, score, indices, idx))
+        })
+        .collect::<Vec<_>>();
+    rank.sort(&mut filtered);
+    filtered
+}
+
+/// Returns the changed files under `dir` with the status markers, e.g., `M  src/lib.rs`, the
+/// same format with `git status --short`. The renamed file is listed by the new path.
+pub fn changed_files(dir: &Path, untracked: bool) -> Result<Vec<String>> {
+    let (repo, prefix) = open_repo(dir)?;
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(untracked)
+        .recurse_untracked_dirs(untracked)
+        .include_ignored(false)
+        .renames_head_to_index(true);
+    let statuses = repo.statuses(Some(&mut opts))?;
+    Ok(statuses
+        .iter()
+        .filter_map(|entry| {
+            let path = entry
+                .head_to_index()
+                .or_else(|| entry.index_to_workdir())
+                .and_then(|delta| {
+                    delta
+                        .new_file()
+                        .path()
+                        .map(|p| p.to_string_lossy().into_owned())
+                })
+                .or_else(|| entry.path().map(Into::into))?;
+            relative_to(&path, &prefix)
+                .map(|path| format!("{} {}", status_marker(entry.status()), path))
+        })
+        .collect())
+}
+
+/// List the files of git repo without spawning git.
+#[derive(StructOpt, Debug, Clone)]
+pub struct GitFiles {
+    /// Initial query string, print all the files if it's empty.
+    #[structopt(index = 1, short, long, default_value = "")]
+    query: String,
+
+    /// Specify the working directory, the project root normally.
+    #[structopt(long = "cmd-dir", parse(from_os_str))]
+    cmd_dir: Option<PathBuf>,
+
+    /// List the changed files with the status markers instead of the tracked files.
+    #[structopt(long)]
+    changed: bool,
+
+    /// Include the untracked files, valid only when --changed is used.
+    #[structopt(long)]
+    untracked: bool,
+}
+
+impl GitFiles {
+    /// Returns the working directory of command, where the project config is looked up.
+    pub fn cmd_dir(&self) -> Option<&Path> {
+        self.cmd_dir.as_deref()
+    }
+
+    /// Returns the files according to the options.
+    pub fn list(&self, dir: &Path) -> Result<Vec<String>> {
+        if self.changed {
+            changed_files(dir, self.untracked)
+        } else {
+            tracked_files(dir)
+        }
+    }
+
+    pub fn run(
+        &self,
+        number: Option<usize>,
+        winwidth: Option<usize>,
+        icon_painter: Option<IconPainter>,
+    ) -> Result<()> {
+        let dir = match self.cmd_dir {
+            Some(ref dir) => dir.clone(),
+            None => std::env::current_dir()?,
+        };
+
+        let files = self.list(&dir)?;
+
+        if self.query.is_empty() {
+            return print_lines(&self.query, files, number, winwidth, icon_painter);
+        }
+
+        let ranked = filter_files(&self.query, files, self.changed, None, &Rank::default());
+        print_ranked_items(ranked, number, winwidth, icon_painter);
+        Ok(())
+    }
+}
+
+/// Prints all the `lines` if `query` is empty, otherwise the filtered ones.
+fn print_lines(
+    query: &str,
+    lines: Vec<String>,
+    number: Option<usize>,
+    winwidth: Option<usize>,
+    icon_painter: Option<IconPainter>,
+) -> Result<()> {
+    if !query.is_empty() {
+        return crate::cmd::filter::dynamic::dyn_fuzzy_filter_and_rank(
+            query,
+            Source::List(lines.into_iter()),
+            None,
+            number,
+            winwidth,
+            icon_painter,
+            ContentFiltering::Full,
+        );
+    }
+
+    let paint = |line: &String| match icon_painter {
+        Some(ref painter) => painter.paint(line),
+        None => line.clone(),
+    };
+
+    if let Some(number) = number {
+        let total = lines.len();
+        let lines = lines.iter().take(number).map(paint).collect::<Vec<_>>();
+        println_json!(total, lines);
+    } else {
+        for line in lines.iter().map(paint) {
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_git_files() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("clap_test_git_files_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let repo = Repository::init(&dir).unwrap();
+
+    fs::write(dir.join("README.md"), "readme").unwrap();
+    fs::write(dir.join("src/lib.rs"), "lib").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("README.md")).unwrap();
+    index.add_path(Path::new("src/lib.rs")).unwrap();
+    index.write().unwrap();
+
+    fs::write(dir.join("src/lib.rs"), "changed").unwrap();
+    fs::write(dir.join("untracked.txt"), "new").unwrap();
+
+    assert_eq!(
+        tracked_files(&dir).unwrap(),
+        vec!["README.md", "src/lib.rs"]
+    );
+    assert_eq!(tracked_files(&dir.join("src")).unwrap(), vec!["lib.rs"]);
+
+    assert_eq!(
+        changed_files(&dir, false).unwrap(),
+        vec!["A  README.md", "AM src/lib.rs"]
+    );
+    assert!(changed_files(&dir, true)
+        .unwrap()
+        .contains(&"?? untracked.txt".to_string()));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_strip_status_marker() {
+    assert_eq!(strip_status_marker("AM src/lib.rs"), "src/lib.rs");
+    assert_eq!(strip_status_marker(" M src/lib.rs"), "src/lib.rs");
+    assert_eq!(strip_status_marker("R  old.rs -> new.rs"), "new.rs");
+}
+
+#[test]
+fn test_filter_files() {
+    let files = || {
+        vec![
+            "M  src/main.rs".to_string(),
+            "AM crates/maple_cli/src/lib.rs".to_string(),
+        ]
+    };
+
+    // The status markers are not matched.
+    let lines = vec!["AM src/lib.rs".to_string()];
+    assert!(filter_files("am", lines, true, None, &Rank::default()).is_empty());
+
+    let filtered = filter_files("mcl", files(), true, None, &Rank::default());
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].0, "AM crates/maple_cli/src/lib.rs");
+    assert_eq!(filtered[0].2[0], 10);
+    assert_eq!(filtered[0].3, 1);
+
+    assert_eq!(
+        filter_files("", files(), true, None, &Rank::default()).len(),
+        2
+    );
+}
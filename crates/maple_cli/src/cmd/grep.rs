@@ -2,11 +2,11 @@ use crate::cmd::cache::{cache_exists, send_response_from_cache, SendResponse};
 use crate::light_command::{set_current_dir, LightCommand};
 use crate::utils::is_git_repo;
 use crate::ContentFiltering;
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use fuzzy_filter::{subprocess::Exec, Source};
 use icon::IconPainter;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
 use structopt::StructOpt;
 
 const RG_ARGS: [&str; 7] = [
@@ -19,8 +19,57 @@ const RG_ARGS: [&str; 7] = [
     "",
 ];
 
+/// Args of rg for searching the whole word literally and case-sensitively.
+const RG_WORD_ARGS: [&str; 8] = [
+    "--column",
+    "--line-number",
+    "--no-heading",
+    "--color=never",
+    "--case-sensitive",
+    "--word-regexp",
+    "--fixed-strings",
+    "--",
+];
+
 const RG_EXEC_CMD: &str = "rg --column --line-number --no-heading --color=never --smart-case ''";
 
+/// Returns the grep lines of rg `output`.
+///
+/// rg exits with 1 if nothing is found, and with 2 on the errors like the unreadable files, in
+/// which case the matches found in the other files are still kept.
+fn rg_output_lines(output: Output) -> Result<Vec<String>> {
+    let lines = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(Into::into)
+        .collect::<Vec<String>>();
+    match output.status.code() {
+        Some(0) | Some(1) => Ok(lines),
+        _ if !lines.is_empty() => {
+            log::warn!(
+                "rg exited with {}, stderr: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            Ok(lines)
+        }
+        _ => Err(anyhow!(
+            "rg exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        )),
+    }
+}
+
+/// Searches the whole word `word` in `dir` case-sensitively, returns the grep lines.
+pub fn word_grep(word: &str, dir: &Path) -> Result<Vec<String>> {
+    let output = Command::new(RG_ARGS[0])
+        .args(&RG_WORD_ARGS[..])
+        .arg(word)
+        .current_dir(dir)
+        .output()?;
+    rg_output_lines(output)
+}
+
 #[derive(StructOpt, Debug, Clone)]
 pub struct Grep {
     /// Specify the query string for GREP_CMD.
@@ -63,6 +112,11 @@ fn prepare_grep_and_args(cmd_str: &str, cmd_dir: Option<PathBuf>) -> (Command, V
 }
 
 impl Grep {
+    /// Returns the working directory of command, where the project config is looked up.
+    pub fn cmd_dir(&self) -> Option<&Path> {
+        self.cmd_dir.as_deref()
+    }
+
     pub fn run(
         &self,
         number: Option<usize>,
@@ -155,12 +209,17 @@ pub struct RipGrepForerunner {
     /// Specify the working directory of CMD
     #[structopt(long = "cmd-dir", parse(from_os_str))]
     cmd_dir: Option<PathBuf>,
-    /// Specify the threshold for writing the output of command to a tempfile.
-    #[structopt(long = "output-threshold", default_value = "30000")]
-    output_threshold: usize,
+    /// Specify the threshold for writing the output of command to a tempfile, 30000 by default.
+    #[structopt(long = "output-threshold")]
+    output_threshold: Option<usize>,
 }
 
 impl RipGrepForerunner {
+    /// Returns the working directory of command, where the project config is looked up.
+    pub fn cmd_dir(&self) -> Option<&Path> {
+        self.cmd_dir.as_deref()
+    }
+
     /// Skip the forerunner job if `cmd_dir` is not a git repo.
     ///
     /// Only spawn the forerunner job for git repo for now.
@@ -182,6 +241,7 @@ impl RipGrepForerunner {
         number: Option<usize>,
         icon_painter: Option<IconPainter>,
         no_cache: bool,
+        default_output_threshold: Option<usize>,
     ) -> Result<()> {
         if !no_cache {
             if let Some(ref dir) = self.cmd_dir {
@@ -212,7 +272,11 @@ impl RipGrepForerunner {
             self.cmd_dir,
             number,
             icon_painter,
-            Some(self.output_threshold),
+            Some(
+                self.output_threshold
+                    .or(default_output_threshold)
+                    .unwrap_or(30_000),
+            ),
         );
 
         light_cmd.execute(&RG_ARGS)?;
@@ -220,3 +284,56 @@ impl RipGrepForerunner {
         Ok(())
     }
 }
+
+#[cfg(unix)]
+#[test]
+fn test_rg_output_lines() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    let output = |code: i32, stdout: &str| Output {
+        status: ExitStatus::from_raw(code << 8),
+        stdout: stdout.as_bytes().to_vec(),
+        stderr: b"a.rs: Permission denied".to_vec(),
+    };
+    assert_eq!(
+        rg_output_lines(output(1, "")).unwrap(),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        rg_output_lines(output(2, "b.rs:1:4:fn foo() {}\n")).unwrap(),
+        vec!["b.rs:1:4:fn foo() {}"]
+    );
+    assert!(rg_output_lines(output(2, "")).is_err());
+}
+
+#[test]
+fn test_word_grep() {
+    if Command::new(RG_ARGS[0]).arg("--version").output().is_err() {
+        eprintln!("rg is not installed, skipped");
+        return;
+    }
+
+    let dir = std::env::temp_dir().join(format!("clap_test_word_grep_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.rs"), "fn foo() {}\nlet foobar = Foo(foo);\n").unwrap();
+    std::fs::write(dir.join("b.rs"), "// foo.* is not a regex\nfoo_bar();\n").unwrap();
+
+    let mut lines = word_grep("foo", &dir).unwrap();
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec![
+            "a.rs:1:4:fn foo() {}",
+            "a.rs:2:18:let foobar = Foo(foo);",
+            "b.rs:1:4:// foo.* is not a regex"
+        ]
+    );
+    assert_eq!(
+        word_grep("foo.*", &dir).unwrap(),
+        vec!["b.rs:1:4:// foo.* is not a regex"]
+    );
+    assert!(word_grep("-foo", &dir).unwrap().is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
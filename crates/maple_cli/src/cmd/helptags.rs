@@ -1,7 +1,16 @@
-use crate::utils::read_lines;
-use anyhow::Result;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::cmd::filter::dynamic::select_algo;
+use crate::cmd::tags::{resolve_address, TagsFileEntry};
+use crate::utils::{calculate_hash, clap_cache_dir, read_lines};
+use anyhow::{Context, Result};
+use fuzzy_filter::{
+    fuzzy_filter_and_rank_by, Algo, ContentFiltering, FuzzyMatchedLineInfo, Rank, Source,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use structopt::StructOpt;
 
 /// Parse and display Vim helptags.
@@ -12,15 +21,182 @@ pub struct Helptags {
     meta_info: PathBuf,
 }
 
+/// An entry of the tags file of Vim help, i.e., `{tag}\t{doc}\t{pattern}`.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct HelpTag {
+    pub tag: String,
+    /// File name of the help doc, e.g., `clap.txt`.
+    pub doc: String,
+    /// Search command locating the tag in the doc, e.g., `/*:Clap*`.
+    pub pattern: String,
+}
+
+impl HelpTag {
+    fn from_line(line: &str) -> Option<Self> {
+        TagsFileEntry::parse(line).map(|entry| Self {
+            tag: entry.name,
+            doc: entry.path,
+            pattern: entry.address,
+        })
+    }
+
+    /// Returns the line displayed in Vim, the tag name is at the beginning.
+    pub fn format(&self) -> String {
+        format!("{:<60}\t{}", self.tag, self.doc)
+    }
+}
+
+/// Helptags of `runtimepath`, `key` is the hash of the paths and mtimes of the tags files.
+#[derive(Serialize, Deserialize, Debug)]
+struct HelptagsCache {
+    key: u64,
+    tags: Vec<HelpTag>,
+}
+
 #[inline]
-fn strip_trailing_slash(x: &str) -> String {
-    if x.ends_with('/') {
-        let mut x: String = x.into();
-        x.pop();
-        x
-    } else {
-        x.into()
+fn strip_trailing_slash(x: &str) -> &str {
+    x.trim_end_matches('/')
+}
+
+/// Returns the existing tags files, `doc_tags` are like `/doc/tags` and `/doc/tags-cn`.
+fn tags_files(doc_tags: &[String], runtimepath: &str) -> Vec<PathBuf> {
+    doc_tags
+        .iter()
+        .flat_map(|dt| {
+            runtimepath
+                .split(',')
+                .map(move |rtp| PathBuf::from(format!("{}{}", strip_trailing_slash(rtp), dt)))
+        })
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Reads the tags of the files, the tags of each `doc_tags` are deduplicated and sorted.
+fn read_helptags(doc_tags: &[String], runtimepath: &str) -> Vec<HelpTag> {
+    let mut helptags = Vec::new();
+    for dt in doc_tags {
+        let mut seen = HashSet::new();
+        let mut tags = Vec::new();
+        for tags_file in tags_files(std::slice::from_ref(dt), runtimepath) {
+            if let Ok(lines) = read_lines(tags_file) {
+                for helptag in lines.filter_map(|line| HelpTag::from_line(&line.ok()?)) {
+                    if seen.insert(helptag.tag.clone()) {
+                        tags.push(helptag);
+                    }
+                }
+            }
+        }
+        tags.sort_by(|a, b| a.tag.cmp(&b.tag));
+        helptags.extend(tags);
+    }
+    helptags
+}
+
+fn cache_path(cache_dir: &Path, doc_tags: &[String], runtimepath: &str) -> PathBuf {
+    let mut path = cache_dir.join("helptags");
+    path.push(format!("{}.json", calculate_hash(&(doc_tags, runtimepath))));
+    path
+}
+
+/// Returns the helptags of `runtimepath`, which are cached until any tags file changes.
+pub fn load_helptags(doc_tags: &[String], runtimepath: &str) -> Result<Vec<HelpTag>> {
+    load_helptags_in(&clap_cache_dir(), doc_tags, runtimepath)
+}
+
+/// Same with [`load_helptags`], but the cache is in `cache_dir`.
+fn load_helptags_in(
+    cache_dir: &Path,
+    doc_tags: &[String],
+    runtimepath: &str,
+) -> Result<Vec<HelpTag>> {
+    let mtimes = tags_files(doc_tags, runtimepath)
+        .into_iter()
+        .map(|path| {
+            let mtime = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, mtime)
+        })
+        .collect::<Vec<(PathBuf, Option<SystemTime>)>>();
+    let key = calculate_hash(&mtimes);
+
+    let cache = cache_path(cache_dir, doc_tags, runtimepath);
+    if let Some(cached) = File::open(&cache)
+        .ok()
+        .and_then(|f| serde_json::from_reader::<_, HelptagsCache>(BufReader::new(f)).ok())
+    {
+        if cached.key == key {
+            return Ok(cached.tags);
+        }
+    }
+
+    let tags = read_helptags(doc_tags, runtimepath);
+
+    if let Some(parent) = cache.parent() {
+        fs::create_dir_all(parent)?;
     }
+    let cached = HelptagsCache { key, tags };
+    serde_json::to_writer(BufWriter::new(File::create(&cache)?), &cached)?;
+
+    Ok(cached.tags)
+}
+
+/// Returns the `helptags` matching `query` in the form of [`HelpTag::format`], only the tag is
+/// filtered. All the helptags are returned in order if `query` is empty.
+pub fn filter_helptags(
+    helptags: &[HelpTag],
+    query: &str,
+    algo: Option<Algo>,
+    rank: &Rank,
+) -> Result<Vec<FuzzyMatchedLineInfo>> {
+    if query.is_empty() {
+        return Ok(helptags
+            .iter()
+            .enumerate()
+            .map(|(idx, helptag)| (helptag.format(), 0, Vec::new(), idx))
+            .collect());
+    }
+
+    // The tag is at the beginning of the formatted line, so the indices are unchanged.
+    let ranked = fuzzy_filter_and_rank_by(
+        query,
+        Source::List(helptags.iter().map(|helptag| helptag.tag.clone())),
+        select_algo(query, algo),
+        ContentFiltering::Full,
+        rank,
+    )?;
+    Ok(ranked
+        .into_iter()
+        .map(|(_tag, score, indices, idx)| (helptags[idx].format(), score, indices, idx))
+        .collect())
+}
+
+/// Returns the path of help doc `doc` and the line number of `tag` in it.
+///
+/// The tag is located by the pattern in the tags file at the same directory of the doc, e.g.,
+/// `doc/tags` for `clap.txt` and `doc/tags-cn` for `clap.cnx`.
+pub fn find_helptag(runtimepath: &str, doc: &str, tag: &str) -> Result<(PathBuf, usize)> {
+    let path = runtimepath
+        .split(',')
+        .map(|rtp| Path::new(rtp).join("doc").join(doc))
+        .find(|p| p.exists())
+        .with_context(|| format!("Couldn't find help file {}", doc))?;
+
+    let tags_fname = match Path::new(doc).extension().and_then(|x| x.to_str()) {
+        Some(ext) if ext.len() == 3 && ext.ends_with('x') => format!("tags-{}", &ext[..2]),
+        _ => "tags".into(),
+    };
+    let tags_file = path.with_file_name(tags_fname);
+    let pattern = read_lines(&tags_file)
+        .ok()
+        .and_then(|lines| {
+            lines
+                .filter_map(|line| HelpTag::from_line(&line.ok()?))
+                .find(|helptag| helptag.tag == tag)
+        })
+        .map(|helptag| helptag.pattern)
+        .unwrap_or_else(|| format!("/*{}*", tag));
+
+    let lnum = resolve_address(&pattern, &path).unwrap_or(1);
+    Ok((path, lnum))
 }
 
 impl Helptags {
@@ -30,35 +206,66 @@ impl Helptags {
         // line 2:&runtimepath
         if let Some(Ok(doc_tags)) = lines.next() {
             if let Some(Ok(runtimepath)) = lines.next() {
-                for dt in doc_tags.split(',') {
-                    let tags_files = runtimepath
-                        .split(',')
-                        .map(|x| format!("{}{}", strip_trailing_slash(x), dt));
-                    let mut seen = HashMap::new();
-                    let mut v: Vec<String> = Vec::new();
-                    for tags_file in tags_files {
-                        if let Ok(lines) = read_lines(tags_file) {
-                            lines.for_each(|line| {
-                                if let Ok(helptag) = line {
-                                    v = helptag.split('\t').map(Into::into).collect();
-                                    if !seen.contains_key(&v[0]) {
-                                        seen.insert(
-                                            v[0].clone(),
-                                            format!("{:<60}\t{}", v[0], v[1]),
-                                        );
-                                    }
-                                }
-                            });
-                        }
-                    }
-                    let mut tag_lines = seen.values().collect::<Vec<_>>();
-                    tag_lines.sort();
-                    for line in tag_lines {
-                        println!("{}", line);
-                    }
+                let doc_tags = doc_tags.split(',').map(Into::into).collect::<Vec<_>>();
+                for helptag in load_helptags(&doc_tags, &runtimepath)? {
+                    println!("{}", helptag.format());
                 }
             }
         }
         Ok(())
     }
 }
+
+#[test]
+fn test_helptags() {
+    let dir = std::env::temp_dir().join(format!("clap_test_helptags_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let (rtp1, rtp2) = (dir.join("a"), dir.join("b"));
+    fs::create_dir_all(rtp1.join("doc")).unwrap();
+    fs::create_dir_all(rtp2.join("doc")).unwrap();
+    fs::write(
+        rtp1.join("doc").join("tags"),
+        "clap\tclap.txt\t/*clap*\nclap-foo\tclap.txt\t/*clap-foo*\n",
+    )
+    .unwrap();
+    fs::write(
+        rtp1.join("doc").join("clap.txt"),
+        "*clap*\n\nfoo\t\t*clap-foo*\n",
+    )
+    .unwrap();
+    fs::write(rtp2.join("doc").join("tags"), "clap\tother.txt\t/*clap*\n").unwrap();
+
+    let runtimepath = format!("{},{}/", rtp1.display(), rtp2.display());
+    let doc_tags = vec!["/doc/tags".to_string()];
+    let cache_dir = dir.join("cache");
+    let tags = load_helptags_in(&cache_dir, &doc_tags, &runtimepath).unwrap();
+    let docs = tags
+        .iter()
+        .map(|t| (t.tag.as_str(), t.doc.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(docs, vec![("clap", "clap.txt"), ("clap-foo", "clap.txt")]);
+    // Read from the cache.
+    assert!(cache_path(&cache_dir, &doc_tags, &runtimepath).is_file());
+    assert_eq!(
+        load_helptags_in(&cache_dir, &doc_tags, &runtimepath).unwrap(),
+        tags
+    );
+
+    let filtered = filter_helptags(&tags, "foo", None, &Rank::default()).unwrap();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].0, tags[1].format());
+    assert_eq!(filtered[0].2, vec![5, 6, 7]);
+    assert_eq!(
+        filter_helptags(&tags, "", None, &Rank::default())
+            .unwrap()
+            .len(),
+        2
+    );
+
+    assert_eq!(
+        find_helptag(&runtimepath, "clap.txt", "clap-foo").unwrap(),
+        (rtp1.join("doc").join("clap.txt"), 3)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
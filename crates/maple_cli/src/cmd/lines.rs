@@ -0,0 +1,163 @@
+use crate::cmd::filter::dynamic::select_algo;
+use crate::cmd::filter::print_ranked_items;
+use crate::ContentFiltering;
+use anyhow::{Context, Result};
+use fuzzy_filter::{get_appropriate_scorer, Algo, FuzzyMatchedLineInfo, Rank};
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Fuzzy filter the lines of multiple files, the matched lines are in the form of
+/// `{bufname}:{lnum}:{line}`.
+#[derive(StructOpt, Debug, Clone)]
+pub struct Lines {
+    /// Initial query string
+    #[structopt(index = 1, short, long)]
+    query: String,
+
+    /// Files to search, displayed as is.
+    #[structopt(index = 2, parse(from_os_str))]
+    files: Vec<PathBuf>,
+}
+
+/// A Vim buffer to search, `lines` or `path` is only given if the buffer is changed since it was
+/// sent, the lines are read from `path` in the case of unmodified buffer.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BufferSource {
+    pub bufnr: u64,
+    pub bufname: String,
+    pub changedtick: u64,
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    #[serde(default)]
+    pub lines: Option<Vec<String>>,
+}
+
+/// Returns the lines of file `path`, the invalid UTF-8 chars are replaced.
+pub fn read_lines(path: &Path) -> Result<Vec<String>> {
+    let contents =
+        std::fs::read(path).with_context(|| format!("Couldn't read {}", path.display()))?;
+    Ok(String::from_utf8_lossy(&contents)
+        .lines()
+        .map(Into::into)
+        .collect())
+}
+
+/// Returns the lines matching `query` of all the `buffers` of (bufname, lines) in the form of
+/// `{bufname}:{lnum}:{line}` along with the index of buffer and the 1-based line number, only the
+/// line content is filtered and the indices take the prefix into account. The source index is the
+/// index of line in all the lines of `buffers`. The empty lines are skipped.
+///
+/// The buffers are searched in parallel, the lines of the same score are in the order of
+/// `buffers`.
+pub fn filter_buffers<S: AsRef<str> + Sync>(
+    query: &str,
+    buffers: &[(&str, &[S])],
+    algo: Option<Algo>,
+    rank: &Rank,
+) -> Vec<(FuzzyMatchedLineInfo, (usize, usize))> {
+    let scorer = get_appropriate_scorer(&select_algo(query, algo), &ContentFiltering::Full);
+
+    let offsets = buffers
+        .iter()
+        .scan(0, |offset, (_, lines)| {
+            let start = *offset;
+            *offset += lines.len();
+            Some(start)
+        })
+        .collect::<Vec<_>>();
+
+    let matched = buffers
+        .par_iter()
+        .zip(offsets.par_iter())
+        .enumerate()
+        .map(|(buffer_idx, ((bufname, lines), offset))| {
+            lines
+                .iter()
+                .map(AsRef::as_ref)
+                .enumerate()
+                .filter(|(_, line)| !line.is_empty())
+                .filter_map(|(idx, line)| {
+                    let prefix = format!("{}:{}:", bufname, idx + 1);
+                    let (score, indices) = if query.is_empty() {
+                        (0, Vec::new())
+                    } else {
+                        scorer(line, query)?
+                    };
+                    let indices = indices.into_iter().map(|x| x + prefix.len()).collect();
+                    Some((
+                        (format!("{}{}", prefix, line), score, indices, offset + idx),
+                        (buffer_idx, idx + 1),
+                    ))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut filtered = matched.into_iter().flatten().collect::<Vec<_>>();
+    filtered.sort_by(|(a, _), (b, _)| rank.compare(a, b));
+    filtered
+}
+
+impl Lines {
+    pub fn run(
+        &self,
+        number: Option<usize>,
+        winwidth: Option<usize>,
+        algo: Option<Algo>,
+    ) -> Result<()> {
+        let bufnames = self
+            .files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+        let lines = self
+            .files
+            .iter()
+            .map(|path| read_lines(path))
+            .collect::<Result<Vec<_>>>()?;
+        let buffers = bufnames
+            .iter()
+            .zip(lines.iter())
+            .map(|(bufname, lines)| (bufname.as_str(), lines.as_slice()))
+            .collect::<Vec<_>>();
+
+        let filtered = filter_buffers(&self.query, &buffers, algo, &Rank::default());
+        print_ranked_items(
+            filtered.into_iter().map(|(item, _)| item).collect(),
+            number,
+            winwidth,
+            None,
+        );
+        Ok(())
+    }
+}
+
+#[test]
+fn test_filter_buffers() {
+    let lib: &[&str] = &["fn foo() {}", "", "fn bar() {}"];
+    let no_name: &[&str] = &["let foo = 1"];
+    let buffers = vec![("src/lib.rs", lib), ("[No Name 3]", no_name)];
+
+    let filtered = filter_buffers("foo", &buffers, Some(Algo::SubString), &Rank::default());
+    let lines = filtered
+        .iter()
+        .map(|x| (x.0).0.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec!["src/lib.rs:1:fn foo() {}", "[No Name 3]:1:let foo = 1"]
+    );
+    // The indices point to the matched text after the prefix.
+    let ((line, _score, indices, idx), position) = &filtered[1];
+    assert_eq!(*idx, 3);
+    assert_eq!(*position, (1, 1));
+    let matched = indices.iter().map(|&i| &line[i..=i]).collect::<String>();
+    assert_eq!(matched, "foo");
+
+    // The empty line is skipped.
+    let filtered = filter_buffers("", &buffers, None, &Rank::default());
+    let positions = filtered.iter().map(|x| x.1).collect::<Vec<_>>();
+    assert_eq!(positions, vec![(0, 1), (0, 3), (1, 1)]);
+}
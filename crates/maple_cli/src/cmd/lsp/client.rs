@@ -0,0 +1,225 @@
+//! Minimal client of the language server started over stdio, only the requests of maple are sent,
+//! the requests from the server are answered with `null` and the notifications are ignored.
+
+use anyhow::{anyhow, Context, Result};
+use crossbeam_channel::Receiver;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Reads a message in the form of `Content-Length: {n}\r\n\r\n{json}`, returns `None` at EOF.
+pub fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        let mut name_value = header.splitn(2, ':');
+        if let (Some(name), Some(value)) = (name_value.next(), name_value.next()) {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+
+    let mut content = vec![0; content_length.unwrap_or_default()];
+    reader.read_exact(&mut content)?;
+    Ok(Some(serde_json::from_slice(&content)?))
+}
+
+/// Writes `msg` with the `Content-Length` header.
+pub fn write_message(writer: &mut impl Write, msg: &Value) -> Result<()> {
+    let content = serde_json::to_string(msg)?;
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Returns the `file://` URI of `path`, which has to be absolute.
+pub fn path_to_uri(path: &Path) -> Result<String> {
+    Url::from_file_path(path)
+        .map(String::from)
+        .map_err(|_| anyhow!("Invalid file path: {}", path.display()))
+}
+
+/// Returns the file path of `file://` URI.
+pub fn uri_to_path(uri: &str) -> Option<std::path::PathBuf> {
+    Url::parse(uri).ok()?.to_file_path().ok()
+}
+
+pub struct LspClient {
+    process: Child,
+    stdin: ChildStdin,
+    /// Messages read from the stdout of server in a separate thread.
+    messages: Receiver<Result<Value>>,
+    next_id: u64,
+    timeout: Duration,
+}
+
+impl LspClient {
+    /// Starts the server `command` in `root_dir` and initializes the workspace.
+    pub fn start(command: &[String], root_dir: &Path, timeout: Duration) -> Result<Self> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| anyhow!("Empty command of language server"))?;
+        let mut process = Command::new(program)
+            .args(args)
+            .current_dir(root_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Couldn't start language server {}", program))?;
+        let stdin = process.stdin.take().context("Missing stdin of server")?;
+        let stdout = process.stdout.take().context("Missing stdout of server")?;
+
+        let (sender, messages) = crossbeam_channel::unbounded();
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                match read_message(&mut reader) {
+                    Ok(Some(msg)) => {
+                        if sender.send(Ok(msg)).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        let _ = sender.send(Err(e));
+                        break;
+                    }
+                }
+            }
+        });
+
+        let mut client = Self {
+            process,
+            stdin,
+            messages,
+            next_id: 1,
+            timeout,
+        };
+        client.request(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": path_to_uri(root_dir)?,
+                "capabilities": {
+                    "textDocument": {
+                        "documentSymbol": { "hierarchicalDocumentSymbolSupport": true }
+                    }
+                },
+            }),
+        )?;
+        client.notify("initialized", json!({}))?;
+        Ok(client)
+    }
+
+    /// Sends the request and waits for the result.
+    pub fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        write_message(
+            &mut self.stdin,
+            &json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+        )?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let msg = self
+                .messages
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|_| anyhow!("No response of {} from language server", method))??;
+
+            if msg.get("method").is_some() {
+                // The server may ask for something, e.g., `window/workDoneProgress/create`.
+                if let Some(req_id) = msg.get("id") {
+                    write_message(
+                        &mut self.stdin,
+                        &json!({ "jsonrpc": "2.0", "id": req_id, "result": null }),
+                    )?;
+                }
+                continue;
+            }
+
+            if msg.get("id").and_then(|x| x.as_u64()) != Some(id) {
+                continue;
+            }
+
+            if let Some(error) = msg.get("error") {
+                let message = error
+                    .get("message")
+                    .and_then(|x| x.as_str())
+                    .unwrap_or_default();
+                return Err(anyhow!("{} failed: {}", method, message));
+            }
+            return Ok(msg.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    pub fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        write_message(
+            &mut self.stdin,
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+        )
+    }
+
+    /// Returns the process id of the server.
+    pub fn id(&self) -> u32 {
+        self.process.id()
+    }
+
+    /// Returns true if the server process has not exited.
+    pub fn is_running(&mut self) -> bool {
+        self.process.try_wait().ok() == Some(None)
+    }
+
+    /// Asks the server to exit.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.request("shutdown", Value::Null)?;
+        self.notify("exit", Value::Null)
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        let deadline = Instant::now() + Duration::from_millis(200);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.process.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+#[test]
+fn test_read_write_message() {
+    let msg = json!({ "jsonrpc": "2.0", "id": 1, "result": "ü" });
+    let mut buf = Vec::new();
+    write_message(&mut buf, &msg).unwrap();
+    write_message(&mut buf, &msg).unwrap();
+
+    let mut reader = std::io::Cursor::new(buf);
+    assert_eq!(read_message(&mut reader).unwrap(), Some(msg.clone()));
+    assert_eq!(read_message(&mut reader).unwrap(), Some(msg));
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+}
@@ -0,0 +1,323 @@
+//! Symbols provided by the language servers configured in the `[lsp]` section of config, e.g.,
+//!
+//! ```toml
+//! [lsp.rust]
+//! command = ["rust-analyzer"]
+//! extensions = ["rs"]
+//! ```
+//!
+//! `maple lsp-symbols` starts the server for each search and shuts it down once the symbols are
+//! returned, the daemon keeps the server running for each workspace root, see
+//! [`cached_formatted_symbols`].
+
+use crate::config::{Config, LspServerConfig};
+use crate::ContentFiltering;
+use anyhow::{anyhow, Context, Result};
+use fuzzy_filter::Source;
+use lazy_static::lazy_static;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use structopt::StructOpt;
+
+mod client;
+mod symbols;
+
+pub use self::client::{path_to_uri, read_message, uri_to_path, write_message, LspClient};
+pub use self::symbols::{format_symbols, parse_symbols, Symbol};
+
+/// Seconds to wait for each response if the timeout is not configured.
+const DEFAULT_TIMEOUT: u64 = 30;
+
+/// Language servers keyed by the server name and workspace root, the slot of each key is `None`
+/// until the server is started.
+type Servers = HashMap<(String, PathBuf), Arc<Mutex<Option<LspClient>>>>;
+
+lazy_static! {
+    /// Language servers kept running by the daemon.
+    static ref SERVERS: Mutex<Servers> = Mutex::new(HashMap::new());
+}
+
+/// Returns the name and config of the language server, which is `name` if given, otherwise the
+/// one handling the extension of `file`, or the only configured server.
+fn find_server<'a>(
+    servers: &'a BTreeMap<String, LspServerConfig>,
+    name: Option<&str>,
+    file: Option<&Path>,
+) -> Result<(&'a str, &'a LspServerConfig)> {
+    if let Some(name) = name {
+        return servers
+            .get_key_value(name)
+            .map(|(name, server)| (name.as_str(), server))
+            .ok_or_else(|| anyhow!("Language server {} is not configured", name));
+    }
+
+    if let Some(ext) = file.and_then(|f| f.extension()).and_then(|x| x.to_str()) {
+        return servers
+            .iter()
+            .find(|(_, server)| server.extensions.iter().any(|e| e == ext))
+            .map(|(name, server)| (name.as_str(), server))
+            .ok_or_else(|| anyhow!("No language server is configured for .{} files", ext));
+    }
+
+    match servers.len() {
+        1 => Ok(servers
+            .iter()
+            .next()
+            .map(|(name, server)| (name.as_str(), server))
+            .unwrap()),
+        0 => Err(anyhow!("No language server is configured")),
+        _ => Err(anyhow!(
+            "Multiple language servers are configured, specify one with --server"
+        )),
+    }
+}
+
+fn start_server(server: &LspServerConfig, root_dir: &Path) -> Result<LspClient> {
+    let timeout = Duration::from_secs(server.timeout.unwrap_or(DEFAULT_TIMEOUT));
+    LspClient::start(&server.command, root_dir, timeout)
+}
+
+/// Returns the symbols matching `query` in the workspace `root_dir`, or all the symbols of `file`
+/// if given, in which case `language_id` is used to open the document.
+pub fn request_symbols(
+    client: &mut LspClient,
+    root_dir: &Path,
+    query: &str,
+    file: Option<(&Path, &str)>,
+) -> Result<Vec<Symbol>> {
+    match file {
+        Some((file, language_id)) => {
+            let path = root_dir.join(file);
+            let uri = path_to_uri(&path)?;
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Couldn't read {}", path.display()))?;
+            client.notify(
+                "textDocument/didOpen",
+                json!({
+                    "textDocument": {
+                        "uri": uri,
+                        "languageId": language_id,
+                        "version": 1,
+                        "text": text,
+                    }
+                }),
+            )?;
+            let result = client.request(
+                "textDocument/documentSymbol",
+                json!({ "textDocument": { "uri": uri } }),
+            );
+            // The document is opened again by the next request of the server kept running.
+            client.notify(
+                "textDocument/didClose",
+                json!({ "textDocument": { "uri": uri } }),
+            )?;
+            parse_symbols(result?, Some(&path))
+        }
+        None => {
+            let result = client.request("workspace/symbol", json!({ "query": query }))?;
+            parse_symbols(result, None)
+        }
+    }
+}
+
+/// Returns the formatted symbols from the language server kept running for `root_dir`.
+///
+/// The server is started on the first request of the workspace and restarted if it has exited.
+/// Only the slot of the workspace is locked while starting the server or waiting for the symbols,
+/// the requests of other workspaces are not blocked.
+pub fn cached_formatted_symbols(
+    config: &Config,
+    server: Option<&str>,
+    root_dir: &Path,
+    query: &str,
+    file: Option<&Path>,
+) -> Result<Vec<String>> {
+    let (name, server) = find_server(&config.lsp, server, file)?;
+    let key = (name.to_string(), root_dir.to_path_buf());
+
+    let slot = SERVERS.lock().unwrap().entry(key).or_default().clone();
+    let mut slot = slot.lock().unwrap();
+    let running = match slot.as_mut() {
+        Some(client) => client.is_running(),
+        None => false,
+    };
+    if !running {
+        *slot = Some(start_server(server, root_dir)?);
+    }
+    let client = slot.as_mut().expect("Server is started above; qed");
+    let symbols = request_symbols(client, root_dir, query, file.map(|f| (f, name)))?;
+    Ok(format_symbols(symbols, root_dir))
+}
+
+/// Fuzzy filter the symbols provided by the language server.
+///
+/// The symbols of workspace are from `workspace/symbol`, the symbols of a single file are from
+/// `textDocument/documentSymbol`.
+#[derive(StructOpt, Debug, Clone)]
+pub struct LspSymbols {
+    /// Initial query string
+    #[structopt(index = 1, short, long)]
+    query: String,
+
+    /// Root directory of the workspace, the current directory by default.
+    #[structopt(long, parse(from_os_str))]
+    dir: Option<PathBuf>,
+
+    /// List the symbols of this file instead of the workspace.
+    #[structopt(long, parse(from_os_str))]
+    file: Option<PathBuf>,
+
+    /// Name of the language server in the config, which is also the languageId of `--file`.
+    ///
+    /// Inferred from the extension of `--file` if omitted.
+    #[structopt(long)]
+    server: Option<String>,
+}
+
+impl LspSymbols {
+    /// Returns the project root, where the project config is looked up.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Returns the formatted symbols of the workspace or file.
+    fn formatted_symbols(&self, root_dir: &Path, config: &Config) -> Result<Vec<String>> {
+        let (name, server) =
+            find_server(&config.lsp, self.server.as_deref(), self.file.as_deref())?;
+        let mut client = start_server(server, root_dir)?;
+        let symbols = request_symbols(
+            &mut client,
+            root_dir,
+            &self.query,
+            self.file.as_deref().map(|f| (f, name)),
+        )?;
+        if let Err(e) = client.shutdown() {
+            log::debug!("Failed to shutdown language server {}: {}", name, e);
+        }
+
+        Ok(format_symbols(symbols, root_dir))
+    }
+
+    pub fn run(
+        &self,
+        number: Option<usize>,
+        winwidth: Option<usize>,
+        config: &Config,
+    ) -> Result<()> {
+        let root_dir = match self.dir {
+            Some(ref dir) => dir.canonicalize()?,
+            None => std::env::current_dir()?,
+        };
+        let formatted_symbols = self.formatted_symbols(&root_dir, config)?;
+        crate::cmd::filter::dynamic::dyn_fuzzy_filter_and_rank(
+            &self.query,
+            Source::List(crate::cmd::tags::filter_kinds(
+                formatted_symbols.into_iter(),
+                &config.tags,
+            )),
+            None,
+            number,
+            winwidth,
+            None,
+            ContentFiltering::TagNameOnly,
+        )
+    }
+}
+
+#[test]
+fn test_find_server() {
+    let config: Config = toml::from_str(
+        r#"
+[lsp.rust]
+command = ["rust-analyzer"]
+extensions = ["rs"]
+
+[lsp.python]
+command = ["pyls"]
+extensions = ["py", "pyi"]
+"#,
+    )
+    .unwrap();
+    let name = |server: Option<&str>, file: Option<&str>| {
+        find_server(&config.lsp, server, file.map(Path::new)).map(|(name, _)| name)
+    };
+    assert_eq!(name(Some("python"), None).unwrap(), "python");
+    assert_eq!(name(None, Some("src/a.pyi")).unwrap(), "python");
+    assert_eq!(name(None, Some("src/lib.rs")).unwrap(), "rust");
+    assert!(name(None, Some("main.go")).is_err());
+    assert!(name(None, None).is_err());
+    assert!(name(Some("go"), None).is_err());
+}
+
+/// Runs against `examples/fake_lsp_server.rs`, which is built along with the tests by
+/// `cargo test`, but not by `cargo test --lib`.
+#[test]
+fn test_fake_lsp_server() {
+    let server = std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.parent()?.join("examples")))
+        .map(|dir| dir.join(format!("fake_lsp_server{}", std::env::consts::EXE_SUFFIX)))
+        .filter(|path| path.is_file())
+        .expect("fake_lsp_server is not built, run `cargo build --examples` first");
+
+    let dir = std::env::temp_dir().join(format!("clap_test_lsp_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("src").join("lib.rs"),
+        "pub struct Foo {\n    bar: usize,\n}\n\npub fn foo_bar() {}\n",
+    )
+    .unwrap();
+    let root_dir = dir.canonicalize().unwrap();
+
+    let command = vec![server.display().to_string()];
+    let mut client = LspClient::start(&command, &root_dir, Duration::from_secs(10)).unwrap();
+
+    let symbols = request_symbols(&mut client, &root_dir, "foo", None).unwrap();
+    let formatted = format_symbols(symbols, &root_dir);
+    assert_eq!(formatted.len(), 2);
+    assert_eq!(
+        crate::cmd::tags::TagInfo::extract_position(&formatted[1]),
+        Some(("src/lib.rs".into(), 5))
+    );
+    assert!(formatted[1].ends_with("pub fn foo_bar() {}"));
+
+    let symbols = request_symbols(
+        &mut client,
+        &root_dir,
+        "",
+        Some((Path::new("src/lib.rs"), "rust")),
+    )
+    .unwrap();
+    let kinds = format_symbols(symbols, &root_dir)
+        .iter()
+        .filter_map(|line| crate::cmd::tags::TagInfo::extract_kind(line).map(String::from))
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec!["struct", "field", "function"]);
+
+    client.shutdown().unwrap();
+
+    // The server kept running for the root is reused by the next request.
+    let config: Config = toml::from_str(&format!(
+        "[lsp.rust]\ncommand = [{:?}]\nextensions = [\"rs\"]\n",
+        server.display().to_string()
+    ))
+    .unwrap();
+    let cached = cached_formatted_symbols(&config, None, &root_dir, "foo", None).unwrap();
+    assert_eq!(cached, formatted);
+    let key = ("rust".to_string(), root_dir.clone());
+    let slot = SERVERS.lock().unwrap().get(&key).cloned().unwrap();
+    let server_id =
+        |slot: &Arc<Mutex<Option<LspClient>>>| slot.lock().unwrap().as_ref().unwrap().id();
+    let kept_id = server_id(&slot);
+    let file = Some(Path::new("src/lib.rs"));
+    let cached = cached_formatted_symbols(&config, None, &root_dir, "", file).unwrap();
+    assert_eq!(cached.len(), 3);
+    assert_eq!(server_id(&slot), kept_id);
+    drop(slot);
+    SERVERS.lock().unwrap().remove(&key);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,214 @@
+//! Converts the symbols of `workspace/symbol` and `textDocument/documentSymbol` to the tags of
+//! `proj_tags` format.
+
+use super::client::uri_to_path;
+use crate::cmd::tags::TagInfo;
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Debug)]
+struct Position {
+    /// 0-based.
+    line: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct Range {
+    start: Position,
+}
+
+#[derive(Deserialize, Debug)]
+struct Location {
+    uri: String,
+    range: Range,
+}
+
+#[derive(Deserialize, Debug)]
+struct SymbolInformation {
+    name: String,
+    kind: u32,
+    location: Location,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct DocumentSymbol {
+    name: String,
+    kind: u32,
+    selection_range: Range,
+    #[serde(default)]
+    children: Vec<DocumentSymbol>,
+}
+
+/// The result is either flat or hierarchical.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Symbols {
+    Flat(Vec<SymbolInformation>),
+    Nested(Vec<DocumentSymbol>),
+}
+
+/// A symbol with the 1-based line number.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: &'static str,
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+/// Returns the lowercase name of `SymbolKind`, e.g., `function` for 12.
+fn kind_name(kind: u32) -> &'static str {
+    match kind {
+        1 => "file",
+        2 => "module",
+        3 => "namespace",
+        4 => "package",
+        5 => "class",
+        6 => "method",
+        7 => "property",
+        8 => "field",
+        9 => "constructor",
+        10 => "enum",
+        11 => "interface",
+        12 => "function",
+        13 => "variable",
+        14 => "constant",
+        15 => "string",
+        16 => "number",
+        17 => "boolean",
+        18 => "array",
+        19 => "object",
+        20 => "key",
+        21 => "null",
+        22 => "enum_member",
+        23 => "struct",
+        24 => "event",
+        25 => "operator",
+        26 => "type_parameter",
+        _ => "unknown",
+    }
+}
+
+fn flatten_document_symbols(symbols: Vec<DocumentSymbol>, path: &Path, acc: &mut Vec<Symbol>) {
+    for symbol in symbols {
+        acc.push(Symbol {
+            name: symbol.name,
+            kind: kind_name(symbol.kind),
+            path: path.to_path_buf(),
+            line: symbol.selection_range.start.line + 1,
+        });
+        flatten_document_symbols(symbol.children, path, acc);
+    }
+}
+
+/// Parses the result of symbol request, `path` is the document of `textDocument/documentSymbol`
+/// as the hierarchical symbols have no location. The symbols of non-file URI are skipped.
+pub fn parse_symbols(result: Value, path: Option<&Path>) -> Result<Vec<Symbol>> {
+    let symbols = match serde_json::from_value::<Option<Symbols>>(result)? {
+        Some(symbols) => symbols,
+        None => return Ok(Vec::new()),
+    };
+    let mut acc = Vec::new();
+    match symbols {
+        Symbols::Flat(symbols) => {
+            for symbol in symbols {
+                if let Some(path) = uri_to_path(&symbol.location.uri) {
+                    acc.push(Symbol {
+                        name: symbol.name,
+                        kind: kind_name(symbol.kind),
+                        path,
+                        line: symbol.location.range.start.line + 1,
+                    });
+                }
+            }
+        }
+        Symbols::Nested(symbols) => {
+            if let Some(path) = path {
+                flatten_document_symbols(symbols, path, &mut acc);
+            }
+        }
+    }
+    Ok(acc)
+}
+
+/// Returns the tags of `symbols` in the `proj_tags` format, the paths are relative to `root_dir`.
+///
+/// Each file is read once for the line of definition, the line text is empty if the file is
+/// unreadable.
+pub fn format_symbols(symbols: Vec<Symbol>, root_dir: &Path) -> Vec<String> {
+    let mut files: HashMap<PathBuf, Vec<String>> = HashMap::new();
+    symbols
+        .into_iter()
+        .map(|symbol| {
+            let lines = files.entry(symbol.path.clone()).or_insert_with(|| {
+                std::fs::read(&symbol.path)
+                    .map(|contents| {
+                        String::from_utf8_lossy(&contents)
+                            .lines()
+                            .map(Into::into)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            });
+            let text = lines
+                .get(symbol.line - 1)
+                .map(|x| x.as_str())
+                .unwrap_or_default();
+            let path = symbol.path.strip_prefix(root_dir).unwrap_or(&symbol.path);
+            TagInfo::new(
+                symbol.name,
+                path.display().to_string(),
+                symbol.line,
+                symbol.kind.into(),
+                text,
+            )
+            .format()
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_symbols() {
+    let flat = serde_json::json!([{
+        "name": "main",
+        "kind": 12,
+        "location": {
+            "uri": "file:///proj/src/main.rs",
+            "range": { "start": { "line": 2, "character": 0 }, "end": { "line": 4, "character": 1 } }
+        },
+        "containerName": "crate"
+    }]);
+    let range = serde_json::json!({ "start": { "line": 0, "character": 0 }, "end": { "line": 9, "character": 1 } });
+    let nested = serde_json::json!([{
+        "name": "Foo",
+        "kind": 23,
+        "range": range,
+        "selectionRange": range,
+        "children": [{ "name": "bar", "kind": 8, "range": range, "selectionRange": range }]
+    }]);
+
+    if cfg!(unix) {
+        assert_eq!(
+            parse_symbols(flat, None).unwrap(),
+            vec![Symbol {
+                name: "main".into(),
+                kind: "function",
+                path: "/proj/src/main.rs".into(),
+                line: 3,
+            }]
+        );
+    }
+
+    let symbols = parse_symbols(nested, Some(Path::new("lib.rs"))).unwrap();
+    let names = symbols
+        .iter()
+        .map(|s| (s.name.as_str(), s.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![("Foo", "struct"), ("bar", "field")]);
+
+    assert!(parse_symbols(Value::Null, None).unwrap().is_empty());
+}
@@ -1,9 +1,14 @@
 pub mod blines;
 pub mod cache;
 pub mod check_release;
+pub mod config;
 pub mod exec;
 pub mod filter;
+pub mod git;
 pub mod grep;
 pub mod helptags;
+pub mod lines;
+pub mod lsp;
+pub mod references;
 pub mod rpc;
 pub mod tags;
@@ -0,0 +1,148 @@
+use crate::cmd::grep::word_grep;
+use crate::cmd::tags::{find_definitions, Backend};
+use crate::config::TagsConfig;
+use anyhow::Result;
+use icon::IconPainter;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// List the definitions and references of a symbol, the output is in the grep format.
+#[derive(StructOpt, Debug, Clone)]
+pub struct References {
+    /// Name of the symbol.
+    #[structopt(index = 1)]
+    symbol: String,
+
+    /// Specify the working directory, the project root normally.
+    #[structopt(long = "cmd-dir", parse(from_os_str))]
+    cmd_dir: Option<PathBuf>,
+
+    /// Tool used to find the definitions, `auto` uses ctags if available.
+    #[structopt(
+        long,
+        default_value = "auto",
+        possible_values = &Backend::variants(),
+        case_insensitive = true
+    )]
+    backend: Backend,
+}
+
+/// A grep line, i.e., `{path}:{lnum}:{col}:{text}`.
+struct Reference {
+    path: PathBuf,
+    lnum: usize,
+    line: String,
+}
+
+/// Normalizes the relative path for the comparison, e.g., `./src/lib.rs` => `src/lib.rs`.
+fn normalize(path: &Path) -> PathBuf {
+    path.strip_prefix(".").unwrap_or(path).to_path_buf()
+}
+
+/// Returns the grep line of the definition at `lnum` of `path`.
+///
+/// The definition may not be found by grep, e.g., the file is ignored by `.gitignore`.
+fn definition_line(dir: &Path, path: &Path, lnum: usize, symbol: &str) -> Option<String> {
+    let text = crate::utils::read_lines(dir.join(path))
+        .ok()?
+        .nth(lnum - 1)?
+        .ok()?;
+    let col = text.find(symbol).map(|idx| idx + 1).unwrap_or(1);
+    Some(format!("{}:{}:{}:{}", path.display(), lnum, col, text))
+}
+
+/// Returns the grep lines of the definitions and references of `symbol` in `dir`.
+///
+/// The definitions come first, each line appears only once.
+pub fn find_references(
+    symbol: &str,
+    dir: &Path,
+    backend: Backend,
+    config: &TagsConfig,
+) -> Result<Vec<String>> {
+    let definitions = find_definitions(symbol, dir, backend, config)?
+        .into_iter()
+        .map(|(path, lnum)| (normalize(&path), lnum))
+        .collect::<Vec<_>>();
+
+    let mut seen = HashSet::new();
+    let mut defs = Vec::new();
+    let mut refs = Vec::new();
+    for line in word_grep(symbol, dir)? {
+        if let Some((path, lnum, _col)) = pattern::extract_grep_position(&line) {
+            let path = normalize(&path);
+            if !seen.insert((path.clone(), lnum)) {
+                continue;
+            }
+            let reference = Reference { path, lnum, line };
+            if definitions.contains(&(reference.path.clone(), lnum)) {
+                defs.push(reference);
+            } else {
+                refs.push(reference);
+            }
+        }
+    }
+
+    for (path, lnum) in definitions {
+        if !seen.contains(&(path.clone(), lnum)) {
+            if let Some(line) = definition_line(dir, &path, lnum, symbol) {
+                seen.insert((path.clone(), lnum));
+                defs.push(Reference { path, lnum, line });
+            }
+        }
+    }
+
+    // The output order of rg is not stable as it searches in parallel.
+    defs.sort_by(|a, b| (&a.path, a.lnum).cmp(&(&b.path, b.lnum)));
+    refs.sort_by(|a, b| (&a.path, a.lnum).cmp(&(&b.path, b.lnum)));
+
+    Ok(defs.into_iter().chain(refs).map(|r| r.line).collect())
+}
+
+impl References {
+    /// Returns the working directory of command, where the project config is looked up.
+    pub fn cmd_dir(&self) -> Option<&Path> {
+        self.cmd_dir.as_deref()
+    }
+
+    pub fn run(
+        &self,
+        number: Option<usize>,
+        icon_painter: Option<IconPainter>,
+        config: &TagsConfig,
+    ) -> Result<()> {
+        let dir = match self.cmd_dir {
+            Some(ref dir) => dir.clone(),
+            None => std::env::current_dir()?,
+        };
+
+        let references = find_references(&self.symbol, &dir, self.backend, config)?;
+
+        let total = references.len();
+        let lines = references
+            .iter()
+            .take(number.unwrap_or(total))
+            .map(|line| match icon_painter {
+                Some(ref painter) => painter.paint(line),
+                None => line.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        println_json!(total, lines);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(
+        normalize(Path::new("./src/lib.rs")),
+        PathBuf::from("src/lib.rs")
+    );
+    assert_eq!(
+        normalize(Path::new("src/lib.rs")),
+        PathBuf::from("src/lib.rs")
+    );
+}
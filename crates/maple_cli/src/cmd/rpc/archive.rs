@@ -0,0 +1,139 @@
+use crate::utils::human_size;
+use anyhow::Result;
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Archive formats we are able to list the members of.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Detects the archive kind from the file name of `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let fname = path
+            .as_ref()
+            .file_name()
+            .and_then(std::ffi::OsStr::to_str)?
+            .to_lowercase();
+        if fname.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if fname.ends_with(".tar.gz") || fname.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if fname.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Members of an archive, at most `max` names are kept.
+#[derive(Debug, Default)]
+pub struct ArchiveEntries {
+    pub names: Vec<String>,
+    pub total: usize,
+    pub size: u64,
+    /// Whether the archive has more members than counted in `total` and `size`.
+    pub truncated: bool,
+}
+
+impl ArchiveEntries {
+    /// Returns the summary line of this archive, e.g., `42 entries, 1.3M`, or `42+ entries, 1.3M+`
+    /// if the reading stopped before the end.
+    pub fn summary(&self) -> String {
+        let more = if self.truncated { "+" } else { "" };
+        format!(
+            "{}{} entries, {}{}",
+            self.total,
+            more,
+            human_size(self.size),
+            more
+        )
+    }
+
+    fn push(&mut self, name: String, size: u64, max: usize) {
+        if self.names.len() < max {
+            self.names.push(name);
+        }
+        self.total += 1;
+        self.size += size;
+    }
+}
+
+/// The tar archive has no index, so the reading stops after `max` members instead of
+/// decompressing the whole archive just for the totals.
+fn read_tar_entries<R: Read>(reader: R, max: usize) -> Result<ArchiveEntries> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = ArchiveEntries::default();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entries.total == max {
+            entries.truncated = true;
+            break;
+        }
+        let name = entry.path()?.display().to_string();
+        entries.push(name, entry.header().size()?, max);
+    }
+    Ok(entries)
+}
+
+fn read_zip_entries(file: File, max: usize) -> Result<ArchiveEntries> {
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut entries = ArchiveEntries::default();
+    for idx in 0..archive.len() {
+        // The raw entry is enough for listing, no need to decompress it.
+        let member = archive.by_index_raw(idx)?;
+        entries.push(member.name().into(), member.size(), max);
+    }
+    Ok(entries)
+}
+
+/// Lists the members of archive `path`, only the first `max` names are returned.
+pub fn read_archive_entries<P: AsRef<Path>>(
+    path: P,
+    kind: &ArchiveKind,
+    max: usize,
+) -> Result<ArchiveEntries> {
+    let file = File::open(path.as_ref())?;
+    match kind {
+        ArchiveKind::Zip => read_zip_entries(file, max),
+        ArchiveKind::Tar => read_tar_entries(file, max),
+        ArchiveKind::TarGz => read_tar_entries(GzDecoder::new(file), max),
+    }
+}
+
+#[test]
+fn test_read_tar_entries() {
+    let mut builder = tar::Builder::new(Vec::new());
+    for name in &["a.txt", "b.txt", "c.txt"] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, &b"data"[..])
+            .unwrap();
+    }
+    let tarball = builder.into_inner().unwrap();
+
+    let entries = read_tar_entries(tarball.as_slice(), 2).unwrap();
+    assert_eq!(entries.names, vec!["a.txt", "b.txt"]);
+    assert_eq!(entries.summary(), "2+ entries, 8B+");
+
+    let entries = read_tar_entries(tarball.as_slice(), 3).unwrap();
+    assert_eq!(entries.summary(), "3 entries, 12B");
+}
+
+#[test]
+fn test_archive_kind() {
+    assert_eq!(ArchiveKind::from_path("a/b.zip"), Some(ArchiveKind::Zip));
+    assert_eq!(ArchiveKind::from_path("b.TAR"), Some(ArchiveKind::Tar));
+    assert_eq!(ArchiveKind::from_path("b.tar.gz"), Some(ArchiveKind::TarGz));
+    assert_eq!(ArchiveKind::from_path("b.tgz"), Some(ArchiveKind::TarGz));
+    assert_eq!(ArchiveKind::from_path("b.rs"), None);
+}
@@ -0,0 +1,289 @@
+//! Lines of the Vim buffers kept in memory, so that `blines` works on the unsaved changes and the
+//! buffers without name, Vim only has to send the changed lines once the buffer is sent.
+
+use super::{filtered_result, write_response, Message};
+use crate::cmd::blines::filter_lines;
+use crate::config::Config;
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use log::debug;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+lazy_static! {
+    /// Lines of the Vim buffers sent by `blines` and `lines`, indexed by bufnr.
+    pub(super) static ref BUFFERS: Mutex<HashMap<u64, BufferLines>> = Mutex::new(HashMap::new());
+}
+
+/// Lines of a Vim buffer at `changedtick`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BufferLines {
+    pub changedtick: u64,
+    pub lines: Vec<String>,
+}
+
+/// Lines `[lnum, end)` of the buffer are replaced by `lines`, both are 1-based, which is similar
+/// to the changes reported by `listener_add()`.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct LineChange {
+    pub lnum: usize,
+    pub end: usize,
+    #[serde(default)]
+    pub lines: Vec<String>,
+}
+
+/// The buffer kept in memory is missing or not at the changedtick the changes are based on, so
+/// the full lines of buffer have to be sent again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutdatedBuffer {
+    pub bufnr: u64,
+    /// changedtick of the buffer kept in memory, `None` if it's not sent yet.
+    pub changedtick: Option<u64>,
+}
+
+impl std::fmt::Display for OutdatedBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.changedtick {
+            Some(changedtick) => write!(
+                f,
+                "Buffer {} is outdated, changedtick: {}",
+                self.bufnr, changedtick
+            ),
+            None => write!(f, "Buffer {} is not sent yet", self.bufnr),
+        }
+    }
+}
+
+impl std::error::Error for OutdatedBuffer {}
+
+impl BufferLines {
+    fn apply(&mut self, change: LineChange) -> Result<()> {
+        let LineChange { lnum, end, lines } = change;
+        if lnum == 0 || lnum > end || end > self.lines.len() + 1 {
+            return Err(anyhow!(
+                "Invalid change of lines [{}, {}) in a buffer of {} lines",
+                lnum,
+                end,
+                self.lines.len()
+            ));
+        }
+        self.lines.splice(lnum - 1..end - 1, lines);
+        Ok(())
+    }
+}
+
+/// Returns the lines of buffer `bufnr` kept in `buffers`, [`OutdatedBuffer`] is returned if it's
+/// not at `changedtick`.
+pub(super) fn buffer_at(
+    buffers: &HashMap<u64, BufferLines>,
+    bufnr: u64,
+    changedtick: u64,
+) -> Result<&BufferLines, OutdatedBuffer> {
+    match buffers.get(&bufnr) {
+        Some(buffer) if buffer.changedtick == changedtick => Ok(buffer),
+        buffer => Err(OutdatedBuffer {
+            bufnr,
+            changedtick: buffer.map(|x| x.changedtick),
+        }),
+    }
+}
+
+/// Updates the lines of buffer `bufnr` in `buffers` to `changedtick`, returns the updated buffer.
+///
+/// The buffer is replaced if the full `lines` are given, otherwise `changes` are applied in order
+/// to the buffer at `prev_changedtick`. [`OutdatedBuffer`] is returned if the buffer kept in
+/// memory is outdated, in which case the full lines have to be sent.
+///
+/// `buffers` is the locked [`BUFFERS`], the caller keeps it locked while using the returned
+/// buffer so that it can't be deleted in between.
+fn update_buffer<'a>(
+    buffers: &'a mut HashMap<u64, BufferLines>,
+    bufnr: u64,
+    changedtick: u64,
+    params: &serde_json::Map<String, Value>,
+) -> Result<&'a BufferLines> {
+    if let Some(lines) = params.get("lines") {
+        let lines = serde_json::from_value(lines.clone())?;
+        buffers.insert(bufnr, BufferLines { changedtick, lines });
+    } else if let Some(changes) = params.get("changes") {
+        let prev_changedtick = params
+            .get("prev_changedtick")
+            .and_then(|x| x.as_u64())
+            .unwrap_or_default();
+        let mut updated = buffer_at(buffers, bufnr, prev_changedtick)?.clone();
+        let changes: Vec<LineChange> = serde_json::from_value(changes.clone())?;
+        for change in changes {
+            updated.apply(change)?;
+        }
+        updated.changedtick = changedtick;
+        buffers.insert(bufnr, updated);
+    }
+
+    Ok(buffer_at(buffers, bufnr, changedtick)?)
+}
+
+/// Returns the lines around `lnum` of buffer `bufnr` in the same way of previewing a file, `None`
+/// if the buffer is not kept in memory.
+pub fn preview_buffer_lines(bufnr: u64, lnum: usize, size: usize) -> Option<(Vec<String>, usize)> {
+    let buffers = BUFFERS.lock().unwrap();
+    let buffer = buffers.get(&bufnr)?;
+    let (start, end, hl_line) = if lnum > size {
+        (lnum - size, lnum + size, size)
+    } else {
+        (0, size, lnum)
+    };
+    let lines = buffer
+        .lines
+        .iter()
+        .skip(start)
+        .take(end - start)
+        .cloned()
+        .collect();
+    Some((lines, hl_line))
+}
+
+/// Returns true if the lines of buffer `bufnr` are kept in memory.
+pub fn has_buffer(bufnr: u64) -> bool {
+    BUFFERS.lock().unwrap().contains_key(&bufnr)
+}
+
+/// Removes the lines of the deleted buffer `bufnr` from memory.
+pub(super) fn handle_delete_buffer(msg: Message) {
+    if let Some(bufnr) = msg.params.get("bufnr").and_then(|x| x.as_u64()) {
+        debug!("Recv delete_buffer params: bufnr:{}", bufnr);
+        BUFFERS.lock().unwrap().remove(&bufnr);
+    }
+}
+
+pub(super) fn handle_message(msg: Message, config: &Config) {
+    let get_u64 = |key: &str| msg.params.get(key).and_then(|x| x.as_u64());
+
+    let bufnr = match get_u64("bufnr") {
+        Some(bufnr) => bufnr,
+        None => {
+            let error = json!({ "message": "Missing bufnr" });
+            write_response(json!({ "id": msg.id, "provider_id": "blines", "error": error }));
+            return;
+        }
+    };
+    let changedtick = get_u64("changedtick").unwrap_or_default();
+    let query = msg
+        .params
+        .get("query")
+        .and_then(|x| x.as_str())
+        .unwrap_or_default();
+    let number = get_u64("number").map(|x| x as usize);
+    let winwidth = get_u64("winwidth").map(|x| x as usize);
+
+    debug!(
+        "Recv blines params: bufnr:{}, changedtick:{}, query:{}",
+        bufnr, changedtick, query
+    );
+
+    let (version, rank) = match msg.output_version().and_then(|v| Ok((v, msg.rank()?))) {
+        Ok(version_rank) => version_rank,
+        Err(err) => {
+            let error = json!({ "message": format!("{}", err) });
+            write_response(json!({ "id": msg.id, "provider_id": "blines", "error": error }));
+            return;
+        }
+    };
+
+    // The buffer is updated and filtered under the same lock, otherwise it could be deleted by
+    // `delete_buffer` in between.
+    let mut buffers = BUFFERS.lock().unwrap();
+    let buffer = match update_buffer(&mut buffers, bufnr, changedtick, &msg.params) {
+        Ok(buffer) => buffer,
+        Err(err) => {
+            let outdated = err.downcast_ref::<OutdatedBuffer>().is_some();
+            let error =
+                json!({ "message": format!("{}", err), "bufnr": bufnr, "outdated": outdated });
+            write_response(json!({ "id": msg.id, "provider_id": "blines", "error": error }));
+            return;
+        }
+    };
+    let filtered = filter_lines(
+        query,
+        buffer
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| (idx, line.as_str(), ())),
+        config.algo.clone(),
+        &rank,
+    );
+
+    let total = filtered.len();
+    let top = filtered
+        .into_iter()
+        .take(number.unwrap_or(total))
+        .map(|(item, ())| item);
+    let mut result = filtered_result(total, top, winwidth.unwrap_or(62), version);
+    result.insert("changedtick".into(), json!(buffer.changedtick));
+    write_response(json!({ "id": msg.id, "provider_id": "blines", "result": result }));
+}
+
+#[test]
+fn test_apply_line_changes() {
+    let mut buffer = BufferLines {
+        changedtick: 1,
+        lines: vec!["a".into(), "b".into(), "c".into()],
+    };
+    let change = |lnum, end, lines: &[&str]| LineChange {
+        lnum,
+        end,
+        lines: lines.iter().map(|x| x.to_string()).collect(),
+    };
+
+    // Change the second line.
+    buffer.apply(change(2, 3, &["B"])).unwrap();
+    // Insert two lines above the first line.
+    buffer.apply(change(1, 1, &["x", "y"])).unwrap();
+    // Delete the last line.
+    buffer.apply(change(5, 6, &[])).unwrap();
+    // Append a line.
+    buffer.apply(change(5, 5, &["z"])).unwrap();
+    assert_eq!(buffer.lines, vec!["x", "y", "a", "B", "z"]);
+
+    assert!(buffer.apply(change(0, 1, &[])).is_err());
+    assert!(buffer.apply(change(3, 8, &[])).is_err());
+}
+
+#[test]
+fn test_update_buffer() {
+    let bufnr = u64::from(std::process::id()) + 1_000_000;
+    let update = |changedtick: u64, value: Value| -> Result<()> {
+        let mut buffers = BUFFERS.lock().unwrap();
+        update_buffer(&mut buffers, bufnr, changedtick, value.as_object().unwrap())?;
+        Ok(())
+    };
+
+    let is_outdated = |result: Result<()>| {
+        result
+            .unwrap_err()
+            .downcast_ref::<OutdatedBuffer>()
+            .is_some()
+    };
+
+    assert!(is_outdated(update(1, json!({}))));
+
+    update(2, json!({ "lines": ["foo", "bar"] })).unwrap();
+    let changes =
+        json!({ "prev_changedtick": 2, "changes": [{ "lnum": 3, "end": 3, "lines": ["baz"] }] });
+    update(3, changes.clone()).unwrap();
+    // Not based on the current changedtick.
+    assert!(is_outdated(update(4, changes)));
+    assert!(is_outdated(update(4, json!({}))));
+    update(3, json!({})).unwrap();
+    // The invalid changes are not reported as outdated.
+    let invalid = json!({ "prev_changedtick": 3, "changes": [{ "lnum": 9, "end": 9 }] });
+    assert!(!is_outdated(update(4, invalid)));
+
+    assert_eq!(
+        preview_buffer_lines(bufnr, 3, 5),
+        Some((vec!["foo".into(), "bar".into(), "baz".into()], 3))
+    );
+    BUFFERS.lock().unwrap().remove(&bufnr);
+}
@@ -0,0 +1,84 @@
+use super::{filtered_result, write_response, Message};
+use crate::cmd::filter::dynamic::select_algo;
+use crate::cmd::tags::{cached_buffer_tags, Backend};
+use crate::config::Config;
+use crate::ContentFiltering;
+use anyhow::anyhow;
+use fuzzy_filter::{get_appropriate_scorer, TagQualifiers};
+use log::debug;
+use printer::OutputVersion;
+use serde_json::json;
+use std::path::Path;
+
+pub(super) fn handle_message(msg: Message, config: &Config) {
+    let get_str = |key: &str| msg.params.get(key).and_then(|x| x.as_str());
+    let get_u64 = |key: &str| msg.params.get(key).and_then(|x| x.as_u64());
+
+    let fpath = get_str("fpath").unwrap_or_default();
+    let query = get_str("query").unwrap_or_default();
+    let backend = get_str("backend")
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(Backend::Auto);
+    let algo = get_str("algo")
+        .and_then(|x| x.parse().ok())
+        .or_else(|| config.algo.clone());
+    let number = get_u64("number").map(|x| x as usize);
+    let winwidth = get_u64("winwidth").map(|x| x as usize);
+
+    debug!(
+        "Recv buffer_tags params: fpath:{}, query:{}, backend:{:?}, algo:{:?}",
+        fpath, query, backend, algo
+    );
+
+    let tags = msg.output_version().and_then(|version| {
+        let rank = msg.rank()?;
+        TagQualifiers::parse(query).map_err(|e| anyhow!(e))?;
+        Ok((
+            version,
+            rank,
+            cached_buffer_tags(Path::new(fpath), backend)?,
+        ))
+    });
+
+    let result = match tags {
+        // The symbols are displayed as a tree in the output schema version 1.
+        Ok((OutputVersion::V1, _, tags)) if query.is_empty() => {
+            let lines = tags.iter().map(|tag| tag.format(true)).collect::<Vec<_>>();
+            json!({ "tags": tags.as_slice(), "lines": lines, "total": tags.len() })
+        }
+        Ok((version, rank, tags)) => {
+            let mut filtered = if query.is_empty() {
+                tags.iter()
+                    .enumerate()
+                    .map(|(idx, tag)| (tag.format(true), 0, Vec::new(), idx))
+                    .collect::<Vec<_>>()
+            } else {
+                let scorer = get_appropriate_scorer(
+                    &select_algo(query, algo),
+                    &ContentFiltering::TagNameOnly,
+                );
+                tags.iter()
+                    .enumerate()
+                    .filter_map(|(idx, tag)| {
+                        let line = tag.format(false);
+                        scorer(&line, query).map(|(score, indices)| (line, score, indices, idx))
+                    })
+                    .collect::<Vec<_>>()
+            };
+            rank.sort(&mut filtered);
+            let total = filtered.len();
+            filtered.truncate(number.unwrap_or(total));
+            let matched_tags = filtered.iter().map(|x| &tags[x.3]).collect::<Vec<_>>();
+            let mut result = filtered_result(total, filtered, winwidth.unwrap_or(62), version);
+            result.insert("tags".into(), json!(matched_tags));
+            serde_json::Value::Object(result)
+        }
+        Err(err) => {
+            let error = json!({ "message": format!("{}", err), "fpath": fpath });
+            write_response(json!({ "id": msg.id, "provider_id": "tags", "error": error }));
+            return;
+        }
+    };
+
+    write_response(json!({ "id": msg.id, "provider_id": "tags", "result": result }));
+}
@@ -1,7 +1,9 @@
-use super::{write_response, Message};
+use super::{filtered_result, write_response, Message};
+use crate::config::Config;
 use anyhow::Result;
 use icon::prepend_filer_icon;
 use log::debug;
+use printer::OutputVersion;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::path::{self, Path, PathBuf};
@@ -68,10 +70,60 @@ pub(super) fn read_dir_entries<P: AsRef<Path>>(
     Ok(entries)
 }
 
+/// Statistics of the direct entries of a directory.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub(super) struct DirSummary {
+    pub dirs: usize,
+    pub files: usize,
+    /// Total size in bytes of the files directly under the directory.
+    pub size: u64,
+}
+
+impl DirSummary {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let mut summary = Self::default();
+        for entry in fs::read_dir(dir)? {
+            let metadata = entry?.metadata()?;
+            if metadata.is_dir() {
+                summary.dirs += 1;
+            } else {
+                summary.files += 1;
+                summary.size += metadata.len();
+            }
+        }
+        Ok(summary)
+    }
+}
+
+impl std::fmt::Display for DirSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} directories, {} files, {}",
+            self.dirs,
+            self.files,
+            crate::utils::human_size(self.size)
+        )
+    }
+}
+
+/// Returns the preview lines of a directory, the first line is the summary of it.
+pub(super) fn preview_dir_lines<P: AsRef<Path>>(
+    dir: P,
+    enable_icon: bool,
+    max: usize,
+) -> Result<Vec<String>> {
+    let summary = DirSummary::new(dir.as_ref())?;
+    let entries = read_dir_entries(dir, enable_icon, Some(max))?;
+    Ok(std::iter::once(summary.to_string())
+        .chain(entries)
+        .collect())
+}
+
 #[derive(Serialize, Deserialize)]
 struct FilerParams {
     cwd: String,
-    enable_icon: bool,
+    enable_icon: Option<bool>,
 }
 
 impl From<serde_json::Map<String, serde_json::Value>> for FilerParams {
@@ -83,23 +135,38 @@ impl From<serde_json::Map<String, serde_json::Value>> for FilerParams {
                     .and_then(|x| x.as_str())
                     .unwrap_or("Missing cwd when deserializing into FilerParams"),
             ),
-            enable_icon: serde_map
-                .get("enable_icon")
-                .and_then(|x| x.as_bool())
-                .unwrap_or(false),
+            enable_icon: serde_map.get("enable_icon").and_then(|x| x.as_bool()),
         }
     }
 }
 
-pub(super) fn handle_message(msg: Message) {
+pub(super) fn handle_message(msg: Message, config: &Config) {
+    let winwidth = msg.params.get("winwidth").and_then(|x| x.as_u64());
+    let version = msg.output_version();
+    let rank = msg.rank();
     let FilerParams { cwd, enable_icon } = msg.params.into();
+    // Enable the icon by default if the icon painter is set in the config.
+    let enable_icon = enable_icon.unwrap_or_else(|| config.icon_painter.is_some());
     debug!(
         "Recv filer params: cwd:{}, enable_icon:{}",
         cwd, enable_icon
     );
 
-    let result = match read_dir_entries(&cwd, enable_icon, None) {
-        Ok(entries) => {
+    // All the entries are the items of the same score without any matched indices.
+    let entries = version.and_then(|version| {
+        let rank = rank?;
+        let mut items = read_dir_entries(&cwd, enable_icon, None)?
+            .into_iter()
+            .enumerate()
+            .map(|(idx, entry)| (entry, 0, Vec::new(), idx))
+            .collect::<Vec<_>>();
+        rank.sort(&mut items);
+        Ok((version, items))
+    });
+
+    let result = match entries {
+        Ok((OutputVersion::V1, items)) => {
+            let entries = items.into_iter().map(|item| item.0).collect::<Vec<_>>();
             let result = json!({
             "entries": entries,
             "dir": cwd,
@@ -107,6 +174,13 @@ pub(super) fn handle_message(msg: Message) {
             });
             json!({ "id": msg.id, "provider_id": "filer", "result": result })
         }
+        Ok((version, items)) => {
+            let total = items.len();
+            let winwidth = winwidth.map(|x| x as usize).unwrap_or(62);
+            let mut result = filtered_result(total, items, winwidth, version);
+            result.insert("dir".into(), json!(cwd));
+            json!({ "id": msg.id, "provider_id": "filer", "result": result })
+        }
         Err(err) => {
             let error = json!({"message": format!("{}", err), "dir": cwd});
             json!({ "id": msg.id, "provider_id": "filer", "error": error })
@@ -130,3 +204,22 @@ fn test_dir() {
     .unwrap();
     println!("entry: {:?}", entries);
 }
+
+#[test]
+fn test_dir_summary() {
+    let dir = std::env::temp_dir().join(format!("clap_test_dir_summary_{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.txt"), "abc").unwrap();
+    fs::write(dir.join("b.txt"), "de").unwrap();
+
+    let summary = DirSummary::new(&dir).unwrap();
+    assert_eq!(
+        summary,
+        DirSummary {
+            dirs: 1,
+            files: 2,
+            size: 5
+        }
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,54 @@
+use super::{filtered_result, write_response, Message};
+use crate::cmd::git::{changed_files, filter_files, tracked_files};
+use crate::config::Config;
+use log::debug;
+use serde_json::json;
+use std::path::Path;
+
+pub(super) fn handle_message(msg: Message, config: &Config) {
+    let get_str = |key: &str| msg.params.get(key).and_then(|x| x.as_str());
+    let get_u64 = |key: &str| msg.params.get(key).and_then(|x| x.as_u64());
+    let get_bool = |key: &str| {
+        msg.params
+            .get(key)
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false)
+    };
+
+    let cwd = get_str("cwd").unwrap_or_default();
+    let query = get_str("query").unwrap_or_default();
+    let changed = get_bool("changed");
+    let untracked = get_bool("untracked");
+    let number = get_u64("number").map(|x| x as usize);
+    let winwidth = get_u64("winwidth").map(|x| x as usize);
+
+    debug!(
+        "Recv git_files params: cwd:{}, query:{}, changed:{}, untracked:{}",
+        cwd, query, changed, untracked
+    );
+
+    let result = msg.output_version().and_then(|version| {
+        let rank = msg.rank()?;
+        let lines = if changed {
+            changed_files(Path::new(cwd), untracked)?
+        } else {
+            tracked_files(Path::new(cwd))?
+        };
+        let total = lines.len();
+        let filtered = filter_files(query, lines, changed, config.algo.clone(), &rank);
+        // `total` is the number of files before filtering.
+        let top = filtered.into_iter().take(number.unwrap_or(total));
+        Ok(filtered_result(total, top, winwidth.unwrap_or(62), version))
+    });
+
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => {
+            let error = json!({ "message": format!("{}", err), "dir": cwd });
+            write_response(json!({ "id": msg.id, "provider_id": "git_files", "error": error }));
+            return;
+        }
+    };
+
+    write_response(json!({ "id": msg.id, "provider_id": "git_files", "result": result }));
+}
@@ -0,0 +1,47 @@
+use super::{filtered_result, write_response, Message};
+use crate::cmd::helptags::{filter_helptags, load_helptags};
+use crate::config::Config;
+use log::debug;
+use serde_json::json;
+
+pub(super) fn handle_message(msg: Message, config: &Config) {
+    let get_str = |key: &str| msg.params.get(key).and_then(|x| x.as_str());
+    let get_u64 = |key: &str| msg.params.get(key).and_then(|x| x.as_u64());
+
+    let runtimepath = get_str("runtimepath").unwrap_or_default();
+    let doc_tags = get_str("doc_tags")
+        .unwrap_or("/doc/tags")
+        .split(',')
+        .map(Into::into)
+        .collect::<Vec<String>>();
+    let query = get_str("query").unwrap_or_default();
+    let number = get_u64("number").map(|x| x as usize);
+    let winwidth = get_u64("winwidth").map(|x| x as usize);
+
+    debug!(
+        "Recv help_tags params: doc_tags:{:?}, query:{}",
+        doc_tags, query
+    );
+
+    let filtered = msg.output_version().and_then(|version| {
+        let rank = msg.rank()?;
+        let helptags = load_helptags(&doc_tags, runtimepath)?;
+        Ok((
+            version,
+            filter_helptags(&helptags, query, config.algo.clone(), &rank)?,
+        ))
+    });
+    let (version, filtered) = match filtered {
+        Ok(filtered) => filtered,
+        Err(err) => {
+            let error = json!({ "message": format!("{}", err) });
+            write_response(json!({ "id": msg.id, "provider_id": "help_tags", "error": error }));
+            return;
+        }
+    };
+
+    let total = filtered.len();
+    let top = filtered.into_iter().take(number.unwrap_or(total));
+    let result = filtered_result(total, top, winwidth.unwrap_or(62), version);
+    write_response(json!({ "id": msg.id, "provider_id": "help_tags", "result": result }));
+}
@@ -0,0 +1,136 @@
+use super::blines::{buffer_at, BufferLines, OutdatedBuffer, BUFFERS};
+use super::{filtered_result, write_response, Message};
+use crate::cmd::lines::{filter_buffers, read_lines, BufferSource};
+use crate::config::Config;
+use anyhow::Result;
+use fuzzy_filter::{Algo, FuzzyMatchedLineInfo, Rank};
+use log::debug;
+use serde_json::json;
+
+/// Returns the matched lines of `buffers` along with the (bufnr, lnum) of each line.
+///
+/// The changed buffers are updated in memory first, the lines of the others are the ones kept in
+/// memory, [`OutdatedBuffer`] is returned if the buffer kept in memory is not at the changedtick.
+fn filter_kept_buffers(
+    query: &str,
+    mut buffers: Vec<BufferSource>,
+    algo: Option<Algo>,
+    rank: &Rank,
+) -> Result<Vec<(FuzzyMatchedLineInfo, (u64, usize))>> {
+    // The files are read before locking the buffers.
+    let changed = buffers
+        .iter()
+        .map(|buffer| match (&buffer.lines, &buffer.path) {
+            (Some(_), _) | (None, None) => Ok(None),
+            (None, Some(path)) => read_lines(path).map(Some),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut kept = BUFFERS.lock().unwrap();
+    for (buffer, lines) in buffers.iter_mut().zip(changed) {
+        if let Some(lines) = lines.or_else(|| buffer.lines.take()) {
+            let changedtick = buffer.changedtick;
+            kept.insert(buffer.bufnr, BufferLines { changedtick, lines });
+        }
+    }
+
+    let sources = buffers
+        .iter()
+        .map(|buffer| {
+            buffer_at(&kept, buffer.bufnr, buffer.changedtick)
+                .map(|kept| (buffer.bufname.as_str(), kept.lines.as_slice()))
+        })
+        .collect::<Result<Vec<_>, OutdatedBuffer>>()?;
+
+    Ok(filter_buffers(query, &sources, algo, rank)
+        .into_iter()
+        .map(|(item, (buffer_idx, lnum))| (item, (buffers[buffer_idx].bufnr, lnum)))
+        .collect())
+}
+
+pub(super) fn handle_message(msg: Message, config: &Config) {
+    let get_u64 = |key: &str| msg.params.get(key).and_then(|x| x.as_u64());
+
+    let query = msg
+        .params
+        .get("query")
+        .and_then(|x| x.as_str())
+        .unwrap_or_default();
+    let number = get_u64("number").map(|x| x as usize);
+    let winwidth = get_u64("winwidth").map(|x| x as usize);
+    let buffers = msg
+        .params
+        .get("buffers")
+        .cloned()
+        .map(serde_json::from_value::<Vec<BufferSource>>)
+        .unwrap_or_else(|| Ok(Vec::new()));
+
+    let filtered = buffers.map_err(anyhow::Error::from).and_then(|buffers| {
+        debug!(
+            "Recv lines params: query:{}, buffers:{}",
+            query,
+            buffers.len()
+        );
+        let version = msg.output_version()?;
+        let rank = msg.rank()?;
+        Ok((
+            version,
+            filter_kept_buffers(query, buffers, config.algo.clone(), &rank)?,
+        ))
+    });
+
+    let (version, filtered) = match filtered {
+        Ok(filtered) => filtered,
+        Err(err) => {
+            let mut error = json!({ "message": format!("{}", err) });
+            // The outdated buffer has to be sent again.
+            if let Some(outdated) = err.downcast_ref::<OutdatedBuffer>() {
+                error["bufnr"] = json!(outdated.bufnr);
+                error["outdated"] = json!(true);
+            }
+            write_response(json!({ "id": msg.id, "provider_id": "lines", "error": error }));
+            return;
+        }
+    };
+
+    let total = filtered.len();
+    let (top, positions): (Vec<_>, Vec<_>) =
+        filtered.into_iter().take(number.unwrap_or(total)).unzip();
+    let mut result = filtered_result(total, top, winwidth.unwrap_or(62), version);
+    result.insert("positions".into(), json!(positions));
+    write_response(json!({ "id": msg.id, "provider_id": "lines", "result": result }));
+}
+
+#[test]
+fn test_filter_kept_buffers() {
+    let bufnr = u64::from(std::process::id()) + 2_000_000;
+    let buffer = |changedtick, lines: Option<&[&str]>| BufferSource {
+        bufnr,
+        bufname: "[No Name]".into(),
+        changedtick,
+        path: None,
+        lines: lines.map(|lines| lines.iter().map(|x| x.to_string()).collect()),
+    };
+
+    let outdated = filter_kept_buffers("foo", vec![buffer(1, None)], None, &Rank::default());
+    assert!(outdated
+        .unwrap_err()
+        .downcast_ref::<OutdatedBuffer>()
+        .is_some());
+
+    let filtered = filter_kept_buffers(
+        "foo",
+        vec![buffer(1, Some(&["bar", "foo"]))],
+        None,
+        &Rank::default(),
+    )
+    .unwrap();
+    assert_eq!(filtered[0].1, (bufnr, 2));
+
+    // The lines kept in memory are used if the buffer is not changed.
+    let filtered = filter_kept_buffers("foo", vec![buffer(1, None)], None, &Rank::default());
+    assert_eq!(filtered.unwrap()[0].1, (bufnr, 2));
+    assert!(filter_kept_buffers("foo", vec![buffer(2, None)], None, &Rank::default()).is_err());
+
+    BUFFERS.lock().unwrap().remove(&bufnr);
+}
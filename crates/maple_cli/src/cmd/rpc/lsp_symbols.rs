@@ -0,0 +1,75 @@
+use super::{filtered_result, write_response, Message};
+use crate::cmd::filter::dynamic::select_algo;
+use crate::cmd::lsp::cached_formatted_symbols;
+use crate::cmd::tags::filter_kinds;
+use crate::config::Config;
+use crate::ContentFiltering;
+use anyhow::Result;
+use fuzzy_filter::{fuzzy_filter_and_rank_by, Algo, FuzzyMatchedLineInfo, Rank, Source};
+use log::debug;
+use serde_json::json;
+use std::path::Path;
+
+fn filter_symbols(
+    symbols: Vec<String>,
+    query: &str,
+    algo: Option<Algo>,
+    rank: &Rank,
+) -> Result<Vec<FuzzyMatchedLineInfo>> {
+    if query.is_empty() {
+        return Ok(symbols
+            .into_iter()
+            .enumerate()
+            .map(|(idx, symbol)| (symbol, 0, Vec::new(), idx))
+            .collect());
+    }
+
+    fuzzy_filter_and_rank_by(
+        query,
+        Source::List(symbols.into_iter()),
+        select_algo(query, algo),
+        ContentFiltering::TagNameOnly,
+        rank,
+    )
+}
+
+pub(super) fn handle_message(msg: Message, config: &Config) {
+    let get_str = |key: &str| msg.params.get(key).and_then(|x| x.as_str());
+    let get_u64 = |key: &str| msg.params.get(key).and_then(|x| x.as_u64());
+
+    let cwd = get_str("cwd").unwrap_or_default();
+    let query = get_str("query").unwrap_or_default();
+    let file = get_str("file").map(Path::new);
+    let server = get_str("server");
+    let number = get_u64("number").map(|x| x as usize);
+    let winwidth = get_u64("winwidth").map(|x| x as usize);
+
+    debug!(
+        "Recv lsp_symbols params: cwd:{}, query:{}, file:{:?}, server:{:?}",
+        cwd, query, file, server
+    );
+
+    let filtered = msg.output_version().and_then(|version| {
+        let rank = msg.rank()?;
+        let root_dir = Path::new(cwd).canonicalize()?;
+        let symbols = cached_formatted_symbols(config, server, &root_dir, query, file)?;
+        let symbols = filter_kinds(symbols.into_iter(), &config.tags).collect();
+        Ok((
+            version,
+            filter_symbols(symbols, query, config.algo.clone(), &rank)?,
+        ))
+    });
+    let (version, filtered) = match filtered {
+        Ok(filtered) => filtered,
+        Err(err) => {
+            let error = json!({ "message": format!("{}", err) });
+            write_response(json!({ "id": msg.id, "provider_id": "lsp_symbols", "error": error }));
+            return;
+        }
+    };
+
+    let total = filtered.len();
+    let top = filtered.into_iter().take(number.unwrap_or(total));
+    let result = filtered_result(total, top, winwidth.unwrap_or(62), version);
+    write_response(json!({ "id": msg.id, "provider_id": "lsp_symbols", "result": result }));
+}
@@ -1,12 +1,25 @@
+mod archive;
+mod blines;
+mod buffer_tags;
 mod filer;
+mod git_files;
+mod help_tags;
+mod lines;
+mod lsp_symbols;
 mod on_move;
+mod providers;
 mod types;
 
+use crate::config::Config;
+use anyhow::{anyhow, Result};
 use crossbeam_channel::Sender;
+use fuzzy_filter::{FuzzyMatchedLineInfo, Rank};
 use log::{debug, error};
+use printer::{split_matched_items, OutputVersion};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::io::prelude::*;
+use std::sync::Arc;
 use std::thread;
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
@@ -17,12 +30,63 @@ pub struct Message {
     pub id: u64,
 }
 
+impl Message {
+    /// Returns the output schema of the filtered items, version 1 if `version` is missing.
+    fn output_version(&self) -> Result<OutputVersion> {
+        match self.params.get("version") {
+            Some(version) => version
+                .as_u64()
+                .and_then(OutputVersion::from_u64)
+                .ok_or_else(|| anyhow!("Unsupported output version: {}", version)),
+            None => Ok(OutputVersion::V1),
+        }
+    }
+
+    /// Returns the criteria of breaking the ties of score, e.g., `"rank": "length,index"`.
+    fn rank(&self) -> Result<Rank> {
+        match self.params.get("rank") {
+            Some(rank) => rank
+                .as_str()
+                .ok_or_else(|| anyhow!("Invalid rank: {}", rank))?
+                .parse()
+                .map_err(|e: String| anyhow!(e)),
+            None => Ok(Rank::default()),
+        }
+    }
+}
+
 fn write_response<T: Serialize>(msg: T) {
     if let Ok(s) = serde_json::to_string(&msg) {
         println!("Content-length: {}\n\n{}", s.len(), s);
     }
 }
 
+/// Returns the `result` of the top filtered items, which has `lines`, `indices` and
+/// `truncated_map` in the output schema version 1, or `items` in version 2.
+fn filtered_result(
+    total: usize,
+    top: impl IntoIterator<Item = FuzzyMatchedLineInfo>,
+    winwidth: usize,
+    version: OutputVersion,
+) -> serde_json::Map<String, Value> {
+    let items = crate::cmd::filter::top_matched_items(top, winwidth, None);
+    let mut result = serde_json::Map::new();
+    result.insert("total".into(), json!(total));
+    match version {
+        OutputVersion::V1 => {
+            let (lines, indices, truncated_map) = split_matched_items(items);
+            result.insert("lines".into(), json!(lines));
+            result.insert("indices".into(), json!(indices));
+            result.insert("truncated_map".into(), json!(truncated_map));
+        }
+        OutputVersion::V2 => {
+            result.insert("version".into(), json!(2));
+            result.insert("items".into(), json!(items));
+        }
+    }
+    result
+}
+
 fn loop_read(reader: impl BufRead, sink: &Sender<String>) {
     let mut reader = reader;
     loop {
@@ -42,14 +106,22 @@ fn loop_read(reader: impl BufRead, sink: &Sender<String>) {
     }
 }
 
-fn loop_handle_message(rx: &crossbeam_channel::Receiver<String>) {
+fn loop_handle_message(rx: &crossbeam_channel::Receiver<String>, config: Arc<Config>) {
     for msg in rx.iter() {
+        let config = config.clone();
         thread::spawn(move || {
             // Ignore the invalid message.
             if let Ok(msg) = serde_json::from_str::<Message>(&msg.trim()) {
                 debug!("Recv: {:?}", msg);
                 match &msg.method[..] {
-                    "filer" => filer::handle_message(msg),
+                    "filer" => filer::handle_message(msg, &config),
+                    "blines" => blines::handle_message(msg, &config),
+                    "delete_buffer" => blines::handle_delete_buffer(msg),
+                    "buffer_tags" => buffer_tags::handle_message(msg, &config),
+                    "git_files" => git_files::handle_message(msg, &config),
+                    "lines" => lines::handle_message(msg, &config),
+                    "help_tags" => help_tags::handle_message(msg, &config),
+                    "lsp_symbols" => lsp_symbols::handle_message(msg, &config),
                     "client.on_move" => {
                         let msg_id = msg.id;
                         if let Err(e) = on_move::handle_message_on_move(msg) {
@@ -67,7 +139,8 @@ fn loop_handle_message(rx: &crossbeam_channel::Receiver<String>) {
     }
 }
 
-pub fn run_forever<R>(reader: R)
+/// Starts the service, `config` provides the defaults of the params missing in the messages.
+pub fn run_forever<R>(reader: R, config: Config)
 where
     R: BufRead + Send + 'static,
 {
@@ -78,5 +151,44 @@ where
             loop_read(reader, &tx);
         })
         .expect("Failed to spawn rpc reader thread");
-    loop_handle_message(&rx);
+    loop_handle_message(&rx, Arc::new(config));
+}
+
+#[test]
+fn test_filtered_result_versions() {
+    let msg = |params: Value| Message {
+        method: "lines".into(),
+        params: params.as_object().unwrap().clone(),
+        id: 1,
+    };
+    assert_eq!(msg(json!({})).output_version().unwrap(), OutputVersion::V1);
+    assert_eq!(
+        msg(json!({ "version": 2 })).output_version().unwrap(),
+        OutputVersion::V2
+    );
+    assert!(msg(json!({ "version": 3 })).output_version().is_err());
+    assert_eq!(msg(json!({})).rank().unwrap(), Rank::default());
+    assert_eq!(
+        msg(json!({ "rank": "length,index" })).rank().unwrap(),
+        "length,index".parse().unwrap()
+    );
+    assert!(msg(json!({ "rank": "score" })).rank().is_err());
+
+    let long_line = format!("{}foo", "x".repeat(100));
+    let top = vec![
+        ("foo".to_string(), 2, vec![0, 1, 2], 0),
+        (long_line, 1, vec![100, 101, 102], 5),
+    ];
+
+    let result = filtered_result(2, top.clone(), 62, OutputVersion::V1);
+    assert_eq!(result["lines"][0], "foo");
+    assert_eq!(result["truncated_map"]["2"].as_str().unwrap().len(), 103);
+
+    let result = filtered_result(2, top, 62, OutputVersion::V2);
+    let items = result["items"].as_array().unwrap();
+    assert_eq!(items[0]["display"], "foo");
+    assert_eq!(items[0]["truncated_from"], Value::Null);
+    assert_eq!(items[1]["text"].as_str().unwrap().len(), 103);
+    assert!(items[1]["truncated_from"].as_u64().is_some());
+    assert_eq!(items[1]["source_index"], 5);
 }
@@ -1,9 +1,14 @@
-use super::types::{PreviewEnv, Provider};
+use super::archive::{read_archive_entries, ArchiveKind};
+use super::blines::preview_buffer_lines;
+use super::filer::preview_dir_lines;
+use super::providers::PreviewTarget;
+use super::types::PreviewEnv;
 use super::*;
-use anyhow::Result;
-use log::error;
+use crate::cmd::git::{commit_diff, CommitDiff};
+use anyhow::{anyhow, Context, Result};
 use std::convert::TryInto;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 #[inline]
 fn canonicalize_and_as_str<P: AsRef<Path>>(path: P) -> String {
@@ -14,82 +19,124 @@ fn canonicalize_and_as_str<P: AsRef<Path>>(path: P) -> String {
         .unwrap()
 }
 
+/// Returns the preview lines of file `path`, the first line is the absolute path of it.
+fn preview_file(path: &Path, size: usize) -> Result<(Vec<String>, String)> {
+    // Archives are previewed as the list of their members instead of the raw binary content.
+    let body = if let Some(kind) = ArchiveKind::from_path(path) {
+        let entries = read_archive_entries(path, &kind, 2 * size)?;
+        std::iter::once(entries.summary())
+            .chain(entries.names)
+            .collect::<Vec<_>>()
+    } else {
+        crate::utils::read_first_lines(path, 2 * size)?.collect()
+    };
+    let abs_path = canonicalize_and_as_str(path);
+    Ok((
+        std::iter::once(abs_path.clone()).chain(body).collect(),
+        abs_path,
+    ))
+}
+
+/// Returns the first `2 * size` lines of the output of command `args`, the first line is the
+/// command itself.
+fn preview_command_output(args: &[String], cwd: &PathBuf, size: usize) -> Result<Vec<String>> {
+    let (program, program_args) = args
+        .split_first()
+        .ok_or_else(|| anyhow!("Empty command of preview"))?;
+    let cmd = args.join(" ");
+    let output = Command::new(program)
+        .args(program_args)
+        .current_dir(cwd)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed: {}",
+            cmd,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(std::iter::once(cmd)
+        .chain(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .take(2 * size)
+                .map(Into::into),
+        )
+        .collect())
+}
+
 pub(super) fn handle_message_on_move(msg: Message) -> Result<()> {
     let msg_id = msg.id;
 
-    let PreviewEnv { size, provider } = msg.try_into()?;
-
-    let file_preview_impl = |path: &Path| {
-        crate::utils::read_first_lines(path, 2 * size).map(|lines_iter| {
-            let abs_path = canonicalize_and_as_str(path);
-            (
-                std::iter::once(abs_path.clone())
-                    .chain(lines_iter)
-                    .collect::<Vec<_>>(),
-                abs_path,
-            )
-        })
-    };
+    let PreviewEnv {
+        size,
+        provider_id,
+        target,
+    } = msg.try_into()?;
 
-    match provider {
-        Provider::Grep(preview_entry) => {
-            match crate::utils::read_preview_lines(&preview_entry.fpath, preview_entry.lnum, size) {
-                Ok((lines_iter, hi_lnum)) => {
-                    let fname = format!("{}", preview_entry.fpath.display());
-                    let lines = std::iter::once(fname.clone())
-                        .chain(lines_iter)
-                        .collect::<Vec<_>>();
-                    write_response(
-                        json!({ "id": msg_id, "provider_id": "grep", "lines": lines, "fname": fname, "hi_lnum": hi_lnum }),
-                    );
-                }
-                Err(err) => {
-                    error!(
-                        "[grep]Couldn't read first lines of {}, error: {:?}",
-                        preview_entry.fpath.display(),
-                        err
-                    );
-                }
-            }
+    match target {
+        PreviewTarget::LineInFile { path, lnum } => {
+            let (lines_iter, hi_lnum) = crate::utils::read_preview_lines(&path, lnum, size)
+                .with_context(|| format!("Couldn't read the lines of {}", path.display()))?;
+            let fname = format!("{}", path.display());
+            let lines = std::iter::once(fname.clone())
+                .chain(lines_iter)
+                .collect::<Vec<_>>();
+            write_response(
+                json!({ "id": msg_id, "provider_id": provider_id, "type": "preview", "lines": lines, "fname": fname, "hi_lnum": hi_lnum }),
+            );
+        }
+        PreviewTarget::LineInBuffer { bufnr, lnum } => {
+            let (lines, hi_lnum) = preview_buffer_lines(bufnr, lnum, size)
+                .ok_or_else(|| anyhow!("Buffer {} is not sent yet", bufnr))?;
+            let header = format!("[buffer {}]", bufnr);
+            let lines = std::iter::once(header).chain(lines).collect::<Vec<_>>();
+            write_response(
+                json!({ "id": msg_id, "provider_id": provider_id, "type": "preview", "lines": lines, "hi_lnum": hi_lnum }),
+            );
         }
-        Provider::Filer { path, enable_icon } => {
-            if path.is_dir() {
-                let lines = super::filer::read_dir_entries(&path, enable_icon, Some(2 * size))?;
+        PreviewTarget::Directory { path, enable_icon } => {
+            let lines = preview_dir_lines(&path, enable_icon, 2 * size)?;
+            if provider_id == "filer" {
                 write_response(
-                    json!({ "id": msg_id, "provider_id": "filer", "type": "preview", "lines": lines, "is_dir": true }),
+                    json!({ "id": msg_id, "provider_id": provider_id, "type": "preview", "lines": lines, "is_dir": true }),
                 );
             } else {
-                match file_preview_impl(&path) {
-                    Ok((lines, abs_path)) => {
-                        write_response(
-                            json!({ "id": msg_id, "provider_id": "filer", "type": "preview", "lines": lines, "fname": abs_path }),
-                        );
-                    }
-                    Err(err) => {
-                        error!(
-                            "[filer]Couldn't read first lines of {}, error: {:?}",
-                            path.display(),
-                            err
-                        );
-                    }
-                }
-            }
-        }
-        Provider::Files(fpath) => match file_preview_impl(&fpath) {
-            Ok((lines, abs_path)) => {
+                let abs_path = canonicalize_and_as_str(&path);
+                let lines = std::iter::once(abs_path.clone())
+                    .chain(lines)
+                    .collect::<Vec<_>>();
                 write_response(
-                    json!({ "id": msg_id, "provider_id": "files", "lines": lines, "fname": abs_path }),
+                    json!({ "id": msg_id, "provider_id": provider_id, "type": "preview", "lines": lines, "fname": abs_path }),
                 );
             }
-            Err(err) => {
-                error!(
-                    "[files]Couldn't read first lines of {}, error: {:?}",
-                    fpath.display(),
-                    err
-                );
-            }
-        },
+        }
+        PreviewTarget::File(path) => {
+            let (lines, abs_path) = preview_file(&path, size)
+                .with_context(|| format!("Couldn't read the first lines of {}", path.display()))?;
+            write_response(
+                json!({ "id": msg_id, "provider_id": provider_id, "type": "preview", "lines": lines, "fname": abs_path }),
+            );
+        }
+        PreviewTarget::CommandOutput { args, cwd } => {
+            let lines = preview_command_output(&args, &cwd, size)?;
+            write_response(
+                json!({ "id": msg_id, "provider_id": provider_id, "type": "preview", "lines": lines, "syntax": "diff" }),
+            );
+        }
+        PreviewTarget::Commit { rev, cwd, path } => {
+            let CommitDiff { lines, hunks } =
+                commit_diff(&cwd, &rev, path.as_deref(), Some(2 * size))?;
+            write_response(
+                json!({ "id": msg_id, "provider_id": provider_id, "type": "preview", "lines": lines, "hunks": hunks, "syntax": "git" }),
+            );
+        }
     }
 
     Ok(())
 }
+
+#[test]
+fn test_preview_empty_command() {
+    assert!(preview_command_output(&[], &std::env::temp_dir(), 10).is_err());
+}
@@ -0,0 +1,352 @@
+//! Each provider declares how to turn the line under the cursor into a preview target.
+
+use super::types::GrepPreviewEntry;
+use crate::cmd::helptags::find_helptag;
+use crate::cmd::tags::TagInfo;
+use anyhow::{anyhow, Context, Result};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    static ref REGISTRY: PreviewRegistry = PreviewRegistry::default();
+}
+
+/// What to show in the preview window.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PreviewTarget {
+    /// The first lines of a file.
+    File(PathBuf),
+    /// The lines around `lnum` of a file, `lnum` is 1-based.
+    LineInFile { path: PathBuf, lnum: usize },
+    /// The lines around `lnum` of a buffer kept in memory, `lnum` is 1-based.
+    LineInBuffer { bufnr: u64, lnum: usize },
+    /// The entries of a directory.
+    Directory { path: PathBuf, enable_icon: bool },
+    /// The output of a command, `args[0]` is the program.
+    CommandOutput { args: Vec<String>, cwd: PathBuf },
+    /// A git commit, only the diff of `path` is shown if it's specified.
+    Commit {
+        rev: String,
+        cwd: PathBuf,
+        path: Option<PathBuf>,
+    },
+}
+
+/// Info of the Vim CursorMoved event.
+#[derive(Clone, Debug)]
+pub struct PreviewContext {
+    pub cwd: PathBuf,
+    /// Line under the cursor.
+    pub curline: String,
+    pub enable_icon: bool,
+    /// File path of the buffer where clap is invoked from.
+    pub source_fpath: Option<PathBuf>,
+    /// Number of the buffer where clap is invoked from.
+    pub source_bufnr: Option<u64>,
+    /// Vim `&runtimepath`, only necessary for help_tags provider.
+    pub runtimepath: Option<String>,
+    /// Index of the current line in the source, e.g., the 0-based line number of blines.
+    pub source_index: Option<usize>,
+}
+
+impl PreviewContext {
+    /// Returns the current line without the leading icon.
+    pub fn curline_without_icon(&self) -> String {
+        if self.enable_icon {
+            self.curline.chars().skip(2).collect()
+        } else {
+            self.curline.clone()
+        }
+    }
+
+    /// Joins `path` with cwd if it's relative, `~` is expanded.
+    fn join_cwd<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        let path = path.as_ref();
+        if let Ok(stripped) = path.strip_prefix("~") {
+            if let Some(home) = std::env::var_os("HOME") {
+                return PathBuf::from(home).join(stripped);
+            }
+        }
+        self.cwd.join(path)
+    }
+
+    fn source_fpath(&self) -> Result<PathBuf> {
+        self.source_fpath
+            .clone()
+            .context("Missing source_fpath for the buffer-local provider")
+    }
+}
+
+/// Turns the line under the cursor into a preview target.
+pub trait PreviewProvider: Send + Sync {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget>;
+}
+
+/// Map of provider id to its preview provider.
+pub struct PreviewRegistry {
+    providers: HashMap<&'static str, Box<dyn PreviewProvider>>,
+}
+
+impl PreviewRegistry {
+    pub fn register<P: PreviewProvider + 'static>(
+        &mut self,
+        provider_id: &'static str,
+        provider: P,
+    ) {
+        self.providers.insert(provider_id, Box::new(provider));
+    }
+
+    pub fn get(&self, provider_id: &str) -> Option<&dyn PreviewProvider> {
+        self.providers.get(provider_id).map(|x| x.as_ref())
+    }
+}
+
+impl Default for PreviewRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            providers: HashMap::new(),
+        };
+        registry.register("files", Files);
+        registry.register("filer", Filer);
+        registry.register("grep", Grep);
+        registry.register("grep2", Grep);
+        registry.register("tags", BufferTags);
+        registry.register("proj_tags", ProjTags);
+        // The line numbers of the tags files are resolved by maple, same with proj_tags.
+        registry.register("tagfiles", ProjTags);
+        registry.register("lsp_symbols", ProjTags);
+        registry.register("blines", Blines);
+        registry.register("buffers", Buffers);
+        registry.register("help_tags", HelpTags);
+        registry.register("quickfix", Quickfix);
+        registry.register("loclist", Quickfix);
+        registry.register("git_diff_files", GitDiffFiles);
+        registry.register("commits", Commits);
+        registry.register("bcommits", BufferCommits);
+        registry
+    }
+}
+
+/// Returns the preview target of `ctx` using the provider registered as `provider_id`.
+pub fn preview_target(provider_id: &str, ctx: &PreviewContext) -> Result<PreviewTarget> {
+    REGISTRY
+        .get(provider_id)
+        .ok_or_else(|| anyhow!("unknown provider_id: {}", provider_id))?
+        .preview_target(ctx)
+}
+
+struct Files;
+
+impl PreviewProvider for Files {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let path = ctx.join_cwd(ctx.curline_without_icon());
+        if path.is_dir() {
+            Ok(PreviewTarget::Directory {
+                path,
+                enable_icon: false,
+            })
+        } else {
+            Ok(PreviewTarget::File(path))
+        }
+    }
+}
+
+struct Filer;
+
+impl PreviewProvider for Filer {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let path = ctx.cwd.join(ctx.curline_without_icon());
+        if path.is_dir() {
+            Ok(PreviewTarget::Directory {
+                path,
+                enable_icon: ctx.enable_icon,
+            })
+        } else {
+            Ok(PreviewTarget::File(path))
+        }
+    }
+}
+
+struct Grep;
+
+impl PreviewProvider for Grep {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let entry: GrepPreviewEntry = ctx.curline_without_icon().try_into()?;
+        Ok(PreviewTarget::LineInFile {
+            path: ctx.join_cwd(&entry.fpath),
+            lnum: entry.lnum,
+        })
+    }
+}
+
+/// Tags of the current buffer, provided by vista.vim.
+struct BufferTags;
+
+impl PreviewProvider for BufferTags {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let lnum = pattern::extract_tag_lnum(&ctx.curline).context("Couldn't extract tag lnum")?;
+        Ok(PreviewTarget::LineInFile {
+            path: ctx.source_fpath()?,
+            lnum,
+        })
+    }
+}
+
+struct ProjTags;
+
+impl PreviewProvider for ProjTags {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let (path, lnum) = TagInfo::extract_position(&ctx.curline)
+            .context("Couldn't extract proj_tags position")?;
+        Ok(PreviewTarget::LineInFile {
+            path: ctx.join_cwd(path),
+            lnum,
+        })
+    }
+}
+
+struct Blines;
+
+impl PreviewProvider for Blines {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        // The line number is extracted if the lines are filtered by Vim.
+        let lnum = match ctx.source_index {
+            Some(idx) => idx + 1,
+            None => pattern::extract_blines_lnum(&ctx.curline)
+                .context("Couldn't extract blines lnum")?,
+        };
+        // Prefer the latest lines sent via `blines` method, the file could be unsaved or unnamed.
+        if let Some(bufnr) = ctx
+            .source_bufnr
+            .filter(|bufnr| super::blines::has_buffer(*bufnr))
+        {
+            return Ok(PreviewTarget::LineInBuffer { bufnr, lnum });
+        }
+        Ok(PreviewTarget::LineInFile {
+            path: ctx.source_fpath()?,
+            lnum,
+        })
+    }
+}
+
+struct Buffers;
+
+impl PreviewProvider for Buffers {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let (_bufnr, lnum, bufname) = pattern::extract_buffer_position(&ctx.curline)
+            .context("Couldn't extract buffer position")?;
+        if bufname == "[No Name]" {
+            return Err(anyhow!("Can not preview the buffer without name"));
+        }
+        Ok(PreviewTarget::LineInFile {
+            path: ctx.join_cwd(bufname),
+            lnum,
+        })
+    }
+}
+
+struct HelpTags;
+
+impl PreviewProvider for HelpTags {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let mut items = ctx.curline.split('\t');
+        let tag = items.next().map(str::trim).context("Missing help tag")?;
+        let doc_fname = items.next().map(str::trim).context("Missing help file")?;
+        let runtimepath = ctx
+            .runtimepath
+            .as_ref()
+            .context("Missing runtimepath for help_tags provider")?;
+        let (path, lnum) = find_helptag(runtimepath, doc_fname, tag)?;
+        Ok(PreviewTarget::LineInFile { path, lnum })
+    }
+}
+
+struct Quickfix;
+
+impl PreviewProvider for Quickfix {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let (path, lnum, _col) = pattern::extract_quickfix_position(&ctx.curline)
+            .context("Couldn't extract quickfix position")?;
+        Ok(PreviewTarget::LineInFile {
+            path: ctx.join_cwd(path),
+            lnum,
+        })
+    }
+}
+
+struct GitDiffFiles;
+
+impl PreviewProvider for GitDiffFiles {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let fpath =
+            crate::cmd::git::strip_status_marker(ctx.curline_without_icon().trim_end()).to_string();
+        Ok(PreviewTarget::CommandOutput {
+            args: vec!["git".into(), "diff".into(), "--".into(), fpath],
+            cwd: ctx.cwd.clone(),
+        })
+    }
+}
+
+struct Commits;
+
+impl PreviewProvider for Commits {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let rev = pattern::extract_commit_rev(&ctx.curline).context("Couldn't extract commit")?;
+        Ok(PreviewTarget::Commit {
+            rev: rev.into(),
+            cwd: ctx.cwd.clone(),
+            path: None,
+        })
+    }
+}
+
+struct BufferCommits;
+
+impl PreviewProvider for BufferCommits {
+    fn preview_target(&self, ctx: &PreviewContext) -> Result<PreviewTarget> {
+        let rev = pattern::extract_commit_rev(&ctx.curline).context("Couldn't extract commit")?;
+        Ok(PreviewTarget::Commit {
+            rev: rev.into(),
+            cwd: ctx.cwd.clone(),
+            path: Some(ctx.source_fpath()?),
+        })
+    }
+}
+
+#[test]
+fn test_preview_target() {
+    let ctx = PreviewContext {
+        cwd: "/tmp".into(),
+        curline: "src/lib.rs|10 col 5| unused variable".into(),
+        enable_icon: false,
+        source_fpath: None,
+        source_bufnr: None,
+        runtimepath: None,
+        source_index: None,
+    };
+    assert_eq!(
+        preview_target("quickfix", &ctx).unwrap(),
+        PreviewTarget::LineInFile {
+            path: "/tmp/src/lib.rs".into(),
+            lnum: 10
+        }
+    );
+    assert!(preview_target("blines", &ctx).is_err());
+    assert!(preview_target("unknown", &ctx).is_err());
+
+    let ctx = PreviewContext {
+        curline: "fn foo() {}".into(),
+        source_fpath: Some("/tmp/src/lib.rs".into()),
+        source_index: Some(4),
+        ..ctx
+    };
+    assert_eq!(
+        preview_target("blines", &ctx).unwrap(),
+        PreviewTarget::LineInFile {
+            path: "/tmp/src/lib.rs".into(),
+            lnum: 5
+        }
+    );
+}
@@ -1,7 +1,7 @@
+use super::providers::{preview_target, PreviewContext, PreviewTarget};
 use super::Message;
-use anyhow::anyhow;
 use anyhow::Context;
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 use std::path::PathBuf;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -24,23 +24,16 @@ impl TryFrom<String> for GrepPreviewEntry {
 pub struct PreviewEnv {
     /// Number of lines to preview.
     pub size: usize,
-    pub provider: Provider,
-}
-
-pub enum Provider {
-    Files(PathBuf),
-    Filer { path: PathBuf, enable_icon: bool },
-    Grep(GrepPreviewEntry),
+    pub provider_id: String,
+    pub target: PreviewTarget,
 }
 
 impl TryFrom<Message> for PreviewEnv {
     type Error = anyhow::Error;
     fn try_from(msg: Message) -> std::result::Result<Self, Self::Error> {
-        let provider_id = msg
-            .params
-            .get("provider_id")
-            .and_then(|x| x.as_str())
-            .unwrap_or("Unknown provider id");
+        let get_str = |key: &str| msg.params.get(key).and_then(|x| x.as_str());
+
+        let provider_id = String::from(get_str("provider_id").unwrap_or("Unknown provider id"));
 
         let enable_icon = msg
             .params
@@ -49,64 +42,41 @@ impl TryFrom<Message> for PreviewEnv {
             .unwrap_or(false);
 
         let cwd = String::from(
-            msg.params
-                .get("cwd")
-                .and_then(|x| x.as_str())
-                .unwrap_or("Missing cwd when deserializing into FilerParams"),
+            get_str("cwd").unwrap_or("Missing cwd when deserializing into FilerParams"),
         );
 
-        let fname_with_icon = String::from(
-            msg.params
-                .get("curline")
-                .and_then(|x| x.as_str())
-                .unwrap_or("Missing fname when deserializing into FilerParams"),
+        let curline = String::from(
+            get_str("curline").unwrap_or("Missing fname when deserializing into FilerParams"),
         );
 
-        let fname = if enable_icon {
-            fname_with_icon.chars().skip(2).collect()
-        } else {
-            fname_with_icon
-        };
-
         let size = msg
             .params
             .get("preview_size")
             .and_then(|x| x.as_u64().map(|x| x as usize))
             .unwrap_or(5);
 
-        let provider = match provider_id {
-            "files" => {
-                let mut fpath: PathBuf = cwd.into();
-                fpath.push(&fname);
-                Provider::Files(fpath)
-            }
-            "filer" => {
-                let mut path: PathBuf = cwd.into();
-                path.push(&fname);
-                let enable_icon = msg
-                    .params
-                    .get("enable_icon")
-                    .and_then(|x| x.as_bool())
-                    .unwrap_or(false);
-                Provider::Filer { path, enable_icon }
-            }
-            "grep" | "grep2" => {
-                let mut preview_entry: GrepPreviewEntry = fname.try_into()?;
-                let mut with_cwd: PathBuf = cwd.into();
-                with_cwd.push(&preview_entry.fpath);
-                preview_entry.fpath = with_cwd;
-                Provider::Grep(preview_entry)
-            }
-            _ => {
-                return Err(anyhow!(
-                    "Couldn't into PreviewEnv from Message: {:?}, unknown provider_id: {}",
-                    msg,
-                    provider_id
-                ))
-            }
+        let ctx = PreviewContext {
+            cwd: cwd.into(),
+            curline,
+            enable_icon,
+            source_fpath: get_str("source_fpath").map(Into::into),
+            source_bufnr: msg.params.get("source_bufnr").and_then(|x| x.as_u64()),
+            runtimepath: get_str("runtimepath").map(Into::into),
+            source_index: msg
+                .params
+                .get("source_index")
+                .and_then(|x| x.as_u64())
+                .map(|x| x as usize),
         };
 
-        Ok(Self { size, provider })
+        let target = preview_target(&provider_id, &ctx)
+            .with_context(|| format!("Couldn't into PreviewEnv from Message: {:?}", msg))?;
+
+        Ok(Self {
+            size,
+            provider_id,
+            target,
+        })
     }
 }
 
@@ -0,0 +1,257 @@
+//! Tags of a single file, i.e., the outline of the buffer.
+
+use super::builtin::{self, Language};
+use super::incremental::FileStamp;
+use super::{Backend, TagInfo};
+use crate::ContentFiltering;
+use anyhow::{Context, Result};
+use fuzzy_filter::{subprocess::Exec, Source};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use structopt::StructOpt;
+
+/// Symbols of each file along with the stamp of the file when they are extracted.
+type SymbolsCache = HashMap<(PathBuf, Backend), (FileStamp, Arc<Vec<BufferTag>>)>;
+
+lazy_static! {
+    /// Symbols extracted in the daemon, only filtered for each query until the file is changed.
+    static ref SYMBOLS: Mutex<SymbolsCache> = Mutex::new(HashMap::new());
+}
+
+/// A symbol of the buffer.
+#[derive(Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct BufferTag {
+    pub name: String,
+    pub kind: String,
+    pub line: usize,
+    pub scope: Option<String>,
+    /// Kind of the scope, e.g., `implementation`.
+    pub scope_kind: Option<String>,
+    pub signature: Option<String>,
+    /// Nesting level of the symbol, used for the tree view, e.g., the method of an impl is 1.
+    pub depth: usize,
+    /// Line of the symbol definition.
+    pub text: String,
+}
+
+impl BufferTag {
+    /// Returns the display line `{name}:{line}\t[{kind}]\t{text}`, delimited by tab like
+    /// [`TagInfo::format`].
+    ///
+    /// The line is indented by the depth of symbol if `indent` is true.
+    pub fn format(&self, indent: bool) -> String {
+        let name_lnum = if indent {
+            format!("{}{}:{}", "  ".repeat(self.depth), self.name, self.line)
+        } else {
+            format!("{}:{}", self.name, self.line)
+        };
+        let kind = format!("[{}]", self.kind);
+        format!(
+            "{text:<width1$}\t{kind:<width2$}\t{pattern}",
+            text = name_lnum,
+            width1 = 30,
+            kind = kind,
+            width2 = 15,
+            pattern = self.text,
+        )
+    }
+}
+
+fn run_ctags(file: &Path) -> Result<Vec<TagInfo>> {
+    let output = Exec::cmd("ctags")
+        .args(&[
+            "--output-format=json",
+            "--fields=+nS",
+            "--sort=no",
+            "-f",
+            "-",
+        ])
+        .arg(file)
+        .capture()?;
+    Ok(output
+        .stdout_str()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<TagInfo>(line).ok())
+        .collect())
+}
+
+/// Converts the tags into the symbols in the order of line number, the depth of symbol is
+/// calculated from the scope of it.
+fn into_buffer_tags(mut tags: Vec<TagInfo>) -> Vec<BufferTag> {
+    tags.sort_by_key(|tag| tag.line);
+
+    // Map of the full name of symbol to its depth, the separator of scope depends on the language.
+    let mut depths: HashMap<String, usize> = HashMap::new();
+    // Same as `depths` but keyed by the kind as well, the symbol is nested in the container of
+    // the same kind as its scope if known, e.g., the method of `impl Foo` is nested in the impl
+    // instead of `struct Foo`.
+    let mut kind_depths: HashMap<(String, String), usize> = HashMap::new();
+    tags.into_iter()
+        .map(|tag| {
+            let depth = match &tag.scope {
+                Some(scope) => {
+                    let container = match &tag.scope_kind {
+                        Some(kind) => kind_depths
+                            .get(&(kind.clone(), scope.clone()))
+                            .or_else(|| depths.get(scope)),
+                        None => depths.get(scope),
+                    };
+                    container.map(|d| d + 1).unwrap_or(1)
+                }
+                None => 0,
+            };
+            let full_names = match &tag.scope {
+                Some(scope) => vec![
+                    format!("{}::{}", scope, tag.name),
+                    format!("{}.{}", scope, tag.name),
+                ],
+                None => vec![tag.name.clone()],
+            };
+            for full_name in full_names {
+                depths.insert(full_name.clone(), depth);
+                kind_depths.insert((tag.kind.clone(), full_name), depth);
+            }
+            BufferTag {
+                text: tag.pattern_text().into(),
+                name: tag.name,
+                kind: tag.kind,
+                line: tag.line,
+                scope: tag.scope,
+                scope_kind: tag.scope_kind,
+                signature: tag.signature,
+                depth,
+            }
+        })
+        .collect()
+}
+
+/// Returns the symbols of `file` using the given `backend`.
+pub fn buffer_tags(file: &Path, backend: Backend) -> Result<Vec<BufferTag>> {
+    let tags = match backend.resolve()? {
+        Backend::Builtin => {
+            let lang = Language::from_path(file).with_context(|| {
+                format!(
+                    "Unsupported language by builtin backend: {}",
+                    file.display()
+                )
+            })?;
+            let source = std::fs::read_to_string(file)?;
+            builtin::extract_symbols(&source, &file.to_string_lossy(), lang)
+        }
+        _ => run_ctags(file)?,
+    };
+    Ok(into_buffer_tags(tags))
+}
+
+/// Returns the symbols of `file` like [`buffer_tags`], the symbols are extracted again only if
+/// the mtime or size of `file` has changed since the last call.
+pub fn cached_buffer_tags(file: &Path, backend: Backend) -> Result<Arc<Vec<BufferTag>>> {
+    let stamp = FileStamp::new(&std::fs::metadata(file)?);
+    let key = (file.to_path_buf(), backend);
+    if let Some((cached_stamp, tags)) = SYMBOLS.lock().unwrap().get(&key) {
+        if *cached_stamp == stamp {
+            return Ok(tags.clone());
+        }
+    }
+    // The lock is not held while extracting the symbols, which might take a while.
+    let tags = Arc::new(buffer_tags(file, backend)?);
+    SYMBOLS.lock().unwrap().insert(key, (stamp, tags.clone()));
+    Ok(tags)
+}
+
+/// Generate the tags of a single file.
+#[derive(StructOpt, Debug, Clone)]
+pub struct BufferTags {
+    /// File path of current vim buffer.
+    #[structopt(index = 1, parse(from_os_str))]
+    file: PathBuf,
+
+    /// Initial query string, print all the symbols as a tree if it's empty.
+    #[structopt(short, long, default_value = "")]
+    query: String,
+
+    /// Tool used to extract the symbols, `auto` uses ctags if available.
+    #[structopt(
+        long,
+        default_value = "auto",
+        possible_values = &Backend::variants(),
+        case_insensitive = true
+    )]
+    backend: Backend,
+}
+
+impl BufferTags {
+    pub fn run(&self, number: Option<usize>, winwidth: Option<usize>) -> Result<()> {
+        let tags = buffer_tags(&self.file, self.backend)?;
+
+        if self.query.is_empty() {
+            let total = tags.len();
+            let lines = tags.iter().map(|tag| tag.format(true)).collect::<Vec<_>>();
+            println_json!(total, lines);
+            return Ok(());
+        }
+
+        crate::cmd::filter::dynamic::dyn_fuzzy_filter_and_rank(
+            &self.query,
+            Source::List(tags.iter().map(|tag| tag.format(false))),
+            None,
+            number,
+            winwidth,
+            None,
+            ContentFiltering::TagNameOnly,
+        )
+    }
+}
+
+#[test]
+fn test_into_buffer_tags() {
+    let source = r#"
+mod foo {
+    pub struct Bar;
+
+    impl Bar {
+        fn new() -> Self {
+            Self
+        }
+    }
+}
+"#;
+    let tags = into_buffer_tags(builtin::extract_symbols(source, "a.rs", Language::Rust));
+    let depths = tags
+        .iter()
+        .map(|t| (t.name.as_str(), t.depth))
+        .collect::<Vec<_>>();
+    assert_eq!(depths, vec![("foo", 0), ("Bar", 1), ("Bar", 1), ("new", 2)]);
+    assert_eq!(tags[3].scope.as_deref(), Some("foo::Bar"));
+    assert_eq!(tags[3].scope_kind.as_deref(), Some("implementation"));
+    assert_eq!(tags[3].kind, "method");
+    assert!(tags[3].format(true).starts_with("    new:6 "));
+}
+
+#[test]
+fn test_cached_buffer_tags() {
+    let file =
+        std::env::temp_dir().join(format!("clap_test_buffer_tags_{}.rs", std::process::id()));
+    std::fs::write(&file, "fn foo() {}\n").unwrap();
+
+    let tags = cached_buffer_tags(&file, Backend::Builtin).unwrap();
+    assert_eq!(tags[0].name, "foo");
+    assert!(Arc::ptr_eq(
+        &tags,
+        &cached_buffer_tags(&file, Backend::Builtin).unwrap()
+    ));
+
+    // The size is changed.
+    std::fs::write(&file, "fn foo() {}\nfn bar() {}\n").unwrap();
+    let names = cached_buffer_tags(&file, Backend::Builtin)
+        .unwrap()
+        .iter()
+        .map(|tag| tag.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["foo", "bar"]);
+
+    std::fs::remove_file(&file).unwrap();
+}
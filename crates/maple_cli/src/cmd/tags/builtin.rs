@@ -0,0 +1,349 @@
+//! Built-in symbol extractor, used when universal-ctags is unavailable.
+//!
+//! The symbols are extracted line by line using a few regexes per language, which is far less
+//! accurate than ctags but good enough for jumping around the project.
+
+use super::TagInfo;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::path::Path;
+
+/// Pairs of (kind, regex), the first capture group of the regex is the symbol name.
+type Rules = Vec<(&'static str, Regex)>;
+
+fn rules(raw: &[(&'static str, &str)]) -> Rules {
+    raw.iter()
+        .map(|(kind, re)| (*kind, Regex::new(re).unwrap()))
+        .collect()
+}
+
+lazy_static! {
+    static ref RUST_RULES: Rules = rules(&[
+        (
+            "implementation",
+            r"^\s*(?:unsafe\s+)?impl(?:<[^>]*>)?\s+(?:[\w:]+(?:<[^>]*>)?\s+for\s+)?([A-Za-z_][\w:]*)"
+        ),
+        (
+            "function",
+            r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:default|const|async|unsafe|extern(?:\s+"[^"]*")?)\s+)*fn\s+([A-Za-z_]\w*)"#
+        ),
+        ("struct", r"^\s*(?:pub(?:\([^)]*\))?\s+)?struct\s+([A-Za-z_]\w*)"),
+        ("enum", r"^\s*(?:pub(?:\([^)]*\))?\s+)?enum\s+([A-Za-z_]\w*)"),
+        ("union", r"^\s*(?:pub(?:\([^)]*\))?\s+)?union\s+([A-Za-z_]\w*)"),
+        (
+            "interface",
+            r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:unsafe\s+)?trait\s+([A-Za-z_]\w*)"
+        ),
+        ("typedef", r"^\s*(?:pub(?:\([^)]*\))?\s+)?type\s+([A-Za-z_]\w*)"),
+        (
+            "constant",
+            r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:const|static(?:\s+mut)?)\s+([A-Za-z_]\w*)\s*:"
+        ),
+        ("module", r"^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+([A-Za-z_]\w*)"),
+        ("macro", r"^\s*macro_rules!\s*([A-Za-z_]\w*)"),
+    ]);
+    static ref PYTHON_RULES: Rules = rules(&[
+        ("class", r"^\s*class\s+([A-Za-z_]\w*)"),
+        ("function", r"^\s*(?:async\s+)?def\s+([A-Za-z_]\w*)"),
+    ]);
+    static ref GO_RULES: Rules = rules(&[
+        ("func", r"^func\s+(?:\([^)]*\)\s*)?([A-Za-z_]\w*)"),
+        ("struct", r"^type\s+([A-Za-z_]\w*)\s+struct\b"),
+        ("interface", r"^type\s+([A-Za-z_]\w*)\s+interface\b"),
+        ("type", r"^type\s+([A-Za-z_]\w*)"),
+        ("constant", r"^const\s+([A-Za-z_]\w*)"),
+    ]);
+    static ref JAVASCRIPT_RULES: Rules = rules(&[
+        (
+            "function",
+            r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*([A-Za-z_$][\w$]*)"
+        ),
+        (
+            "class",
+            r"^\s*(?:export\s+)?(?:default\s+)?(?:abstract\s+)?class\s+([A-Za-z_$][\w$]*)"
+        ),
+        (
+            "function",
+            r"^\s*(?:export\s+)?(?:const|let|var)\s+([A-Za-z_$][\w$]*)\s*=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*=>|[A-Za-z_$][\w$]*\s*=>)"
+        ),
+    ]);
+    static ref TYPESCRIPT_RULES: Rules = {
+        let mut rules = rules(&[
+            ("interface", r"^\s*(?:export\s+)?interface\s+([A-Za-z_$][\w$]*)"),
+            ("alias", r"^\s*(?:export\s+)?type\s+([A-Za-z_$][\w$]*)\s*(?:<[^=]*>)?\s*="),
+            ("enum", r"^\s*(?:export\s+)?(?:const\s+)?enum\s+([A-Za-z_$][\w$]*)"),
+        ]);
+        rules.extend(JAVASCRIPT_RULES.iter().cloned());
+        rules
+    };
+    static ref C_RULES: Rules = rules(&[
+        ("macro", r"^\s*#\s*define\s+([A-Za-z_]\w*)"),
+        ("struct", r"^(?:typedef\s+)?struct\s+([A-Za-z_]\w*)\s*\{?\s*$"),
+        ("enum", r"^(?:typedef\s+)?enum\s+([A-Za-z_]\w*)\s*\{?\s*$"),
+        ("union", r"^(?:typedef\s+)?union\s+([A-Za-z_]\w*)\s*\{?\s*$"),
+        ("typedef", r"^typedef\s+[^;]*?\b([A-Za-z_]\w*)\s*;"),
+        // Function definitions start at the first column and do not end with `;`.
+        (
+            "function",
+            r"^[A-Za-z_][\w\s\*]*?\b([A-Za-z_]\w*)\s*\([^;]*$"
+        ),
+    ]);
+}
+
+/// Languages supported by the built-in extractor, the names are the same with ctags.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Language {
+    Rust,
+    Python,
+    Go,
+    JavaScript,
+    TypeScript,
+    C,
+}
+
+impl Language {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?;
+        match ext {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "go" => Some(Self::Go),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "ts" | "tsx" => Some(Self::TypeScript),
+            "c" | "h" => Some(Self::C),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rust => "Rust",
+            Self::Python => "Python",
+            Self::Go => "Go",
+            Self::JavaScript => "JavaScript",
+            Self::TypeScript => "TypeScript",
+            Self::C => "C",
+        }
+    }
+
+    /// Returns true if this language is enabled by the ctags style `--languages` option,
+    /// e.g., `Rust,Python`.
+    pub fn is_enabled(&self, languages: Option<&str>) -> bool {
+        match languages {
+            Some(languages) if !languages.is_empty() && !languages.eq_ignore_ascii_case("all") => {
+                languages
+                    .split(',')
+                    .any(|lang| lang.trim().eq_ignore_ascii_case(self.name()))
+            }
+            _ => true,
+        }
+    }
+
+    /// Separator of the nested scope, e.g., `Foo::new`.
+    fn scope_separator(&self) -> &'static str {
+        match self {
+            Self::Rust | Self::C => "::",
+            _ => ".",
+        }
+    }
+
+    fn rules(&self) -> &'static Rules {
+        match self {
+            Self::Rust => &RUST_RULES,
+            Self::Python => &PYTHON_RULES,
+            Self::Go => &GO_RULES,
+            Self::JavaScript => &JAVASCRIPT_RULES,
+            Self::TypeScript => &TYPESCRIPT_RULES,
+            Self::C => &C_RULES,
+        }
+    }
+}
+
+const C_KEYWORDS: [&str; 6] = ["if", "for", "while", "switch", "return", "sizeof"];
+
+/// Kinds of the symbols which may contain other symbols.
+const CONTAINER_KINDS: [&str; 7] = [
+    "implementation",
+    "struct",
+    "enum",
+    "union",
+    "interface",
+    "module",
+    "class",
+];
+
+/// Returns the parameter list following the symbol name, e.g., `(a: usize, b: usize)`.
+///
+/// Only the signature on a single line is recognized.
+fn extract_signature(rest: &str) -> Option<String> {
+    let start = rest.find('(')?;
+    let mut depth = 0;
+    for (idx, c) in rest[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(rest[start..start + idx + 1].into());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the kind ctags reports for the function defined in the container of `scope_kind`,
+/// e.g., the `fn` in the `impl` block of Rust is a `method`.
+fn method_kind(lang: Language, kind: &'static str, scope_kind: Option<&str>) -> &'static str {
+    match (lang, kind, scope_kind) {
+        (Language::Rust, "function", Some("implementation"))
+        | (Language::Rust, "function", Some("interface")) => "method",
+        (Language::Python, "function", Some("class")) => "member",
+        _ => kind,
+    }
+}
+
+/// Container symbol the following symbols may belong to.
+struct Container {
+    indent: usize,
+    scope: String,
+    kind: &'static str,
+}
+
+/// Extracts the symbols from `source`, `path` is only used to fill in [`TagInfo::path`].
+///
+/// The scope of symbol is inferred from the indentation, e.g., the indented `fn` following an
+/// `impl` is considered as a method of it.
+pub fn extract_symbols(source: &str, path: &str, lang: Language) -> Vec<TagInfo> {
+    let rules = lang.rules();
+    let mut tags = Vec::new();
+    let mut containers: Vec<Container> = Vec::new();
+    for (idx, line) in source.lines().enumerate() {
+        for &(kind, ref re) in rules.iter() {
+            if let Some(name_match) = re.captures(line).and_then(|cap| cap.get(1)) {
+                let name = name_match.as_str();
+                if lang == Language::C && C_KEYWORDS.contains(&name) {
+                    break;
+                }
+
+                let indent = line.len() - line.trim_start().len();
+                while containers
+                    .last()
+                    .map(|c| c.indent >= indent)
+                    .unwrap_or(false)
+                {
+                    containers.pop();
+                }
+                let parent = containers.last();
+                let scope = parent.map(|c| c.scope.clone());
+                let scope_kind = parent.map(|c| c.kind.to_string());
+                let kind = method_kind(lang, kind, parent.map(|c| c.kind));
+
+                if CONTAINER_KINDS.contains(&kind) {
+                    let full_name = match &scope {
+                        Some(scope) => format!("{}{}{}", scope, lang.scope_separator(), name),
+                        None => name.to_string(),
+                    };
+                    containers.push(Container {
+                        indent,
+                        scope: full_name,
+                        kind,
+                    });
+                }
+
+                let signature = if ["function", "func", "method", "member"].contains(&kind) {
+                    extract_signature(&line[name_match.end()..])
+                } else {
+                    None
+                };
+
+                tags.push(TagInfo {
+                    name: name.into(),
+                    path: path.into(),
+                    pattern: format!("/^{}$/", line),
+                    line: idx + 1,
+                    kind: kind.into(),
+                    scope,
+                    scope_kind,
+                    signature,
+                    language: Some(lang.name().into()),
+                });
+                break;
+            }
+        }
+    }
+    tags
+}
+
+/// Extracts the symbols of file `path` relative to `dir`.
+///
+/// Returns `None` if the language of the file is not supported or not enabled.
+pub fn extract_file_symbols(
+    dir: &Path,
+    path: &str,
+    languages: Option<&str>,
+) -> Option<Vec<TagInfo>> {
+    let lang = Language::from_path(path).filter(|lang| lang.is_enabled(languages))?;
+    // Binary or non-UTF8 files are simply ignored.
+    let source = std::fs::read_to_string(dir.join(path)).ok()?;
+    Some(extract_symbols(&source, path, lang))
+}
+
+#[test]
+fn test_extract_symbols() {
+    let source = r#"
+pub struct Foo {
+    bar: usize,
+}
+
+impl Foo {
+    pub(crate) async fn new() -> Self {
+        todo!()
+    }
+}
+
+macro_rules! foo {
+    () => {};
+}
+"#;
+    let tags = extract_symbols(source, "src/lib.rs", Language::Rust);
+    let symbols = tags
+        .iter()
+        .map(|t| (t.name.as_str(), t.line, t.kind.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        symbols,
+        vec![
+            ("Foo", 2, "struct"),
+            ("Foo", 6, "implementation"),
+            ("new", 7, "method"),
+            ("foo", 12, "macro")
+        ]
+    );
+    assert_eq!(
+        tags[0].format().trim_end(),
+        "Foo:2                         \t[Rust:struct@src/lib.rs]      \t\tpub struct Foo {"
+    );
+
+    assert_eq!(tags[2].scope.as_deref(), Some("Foo"));
+    assert_eq!(tags[2].scope_kind.as_deref(), Some("implementation"));
+    assert_eq!(tags[2].signature.as_deref(), Some("()"));
+
+    let source = "def foo():\n    pass\n\nclass Bar:\n    def baz(self):\n        pass\n";
+    let kinds = extract_symbols(source, "a.py", Language::Python)
+        .into_iter()
+        .map(|t| t.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec!["function", "class", "member"]);
+
+    let source = "static int add(int a, int b)\n{\n    if (a) {\n    return a + b;\n}\n";
+    let tags = extract_symbols(source, "a.c", Language::C);
+    assert_eq!(tags.len(), 1);
+    assert_eq!(tags[0].name, "add");
+    assert_eq!(tags[0].signature.as_deref(), Some("(int a, int b)"));
+
+    assert_eq!(Language::from_path("a/b.tsx"), Some(Language::TypeScript));
+    assert!(Language::Go.is_enabled(Some("rust,go")));
+    assert!(!Language::Go.is_enabled(Some("Rust")));
+}
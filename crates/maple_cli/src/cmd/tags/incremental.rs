@@ -0,0 +1,278 @@
+//! Regenerate the tags of the changed files only.
+//!
+//! The formatted tags are stored per file along with the mtime and size of the file, on the next
+//! run ctags is only invoked on the files that are new or changed since then, the tags of the
+//! deleted files are dropped.
+
+use super::{builtin, Backend, TagInfo, FORMAT_VERSION};
+use crate::utils::{calculate_hash, clap_cache_dir};
+use anyhow::Result;
+use fuzzy_filter::subprocess::Exec;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Stamp used to tell whether a file has been changed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub(super) struct FileStamp {
+    /// Modified time in milliseconds.
+    mtime: u128,
+    size: u64,
+}
+
+impl FileStamp {
+    pub(super) fn new(metadata: &fs::Metadata) -> Self {
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        Self {
+            mtime,
+            size: metadata.len(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct FileTags {
+    stamp: FileStamp,
+    tags: Vec<String>,
+}
+
+/// Formatted tags of each file in the project, keyed by the path relative to the project root.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TagsIndex {
+    files: BTreeMap<String, FileTags>,
+}
+
+/// Returns true if `name` matches the ctags exclude `pattern`, only the leading or trailing `*`
+/// wildcard is supported.
+fn is_excluded(name: &str, pattern: &str) -> bool {
+    if let Some(suffix) = pattern.strip_prefix('*') {
+        name.ends_with(suffix)
+    } else if let Some(prefix) = pattern.strip_suffix('*') {
+        name.starts_with(prefix)
+    } else {
+        name == pattern
+    }
+}
+
+/// Collects the stamps of all the files under `root` recursively, skipping the `excludes`.
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    excludes: &[&str],
+    files: &mut BTreeMap<String, FileStamp>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if excludes.iter().any(|pat| is_excluded(&name, pat)) {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_files(root, &path, excludes, files)?;
+        } else if let Ok(metadata) = fs::metadata(&path) {
+            // Symlinks to directory are skipped to avoid the potential loop.
+            if metadata.is_file() {
+                if let Ok(relative) = path.strip_prefix(root) {
+                    files.insert(
+                        relative.to_string_lossy().into_owned(),
+                        FileStamp::new(&metadata),
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs ctags on the given `files`, returns the formatted tags of each file.
+fn run_ctags(
+    args: &[&str],
+    dir: &Path,
+    files: &[&String],
+) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut tags = BTreeMap::new();
+    if files.is_empty() {
+        return Ok(tags);
+    }
+
+    // -R is useless as we pass the file list via stdin.
+    let opts = args[1..]
+        .iter()
+        .filter(|x| **x != "-R" && !x.is_empty())
+        .collect::<Vec<_>>();
+    let file_list = files
+        .iter()
+        .map(|x| x.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let output = Exec::cmd(args[0])
+        .args(&opts)
+        .args(&["-L", "-"])
+        .cwd(dir)
+        .stdin(file_list.as_str())
+        .capture()?;
+
+    for line in output.stdout_str().lines() {
+        if let Ok(tag) = serde_json::from_str::<TagInfo>(line) {
+            tags.entry(tag.path.clone())
+                .or_insert_with(Vec::new)
+                .push(tag.format());
+        }
+    }
+
+    Ok(tags)
+}
+
+/// Extracts the symbols of the given `files` using the built-in extractor.
+fn run_builtin(args: &[&str], dir: &Path, files: &[&String]) -> BTreeMap<String, Vec<String>> {
+    let languages = args.iter().find_map(|arg| arg.strip_prefix("--languages="));
+    files
+        .iter()
+        .filter_map(|path| {
+            builtin::extract_file_symbols(dir, path, languages)
+                .map(|tags| ((*path).clone(), tags.iter().map(TagInfo::format).collect()))
+        })
+        .collect()
+}
+
+impl TagsIndex {
+    fn path(args: &[&str], dir: &Path) -> PathBuf {
+        let mut path = clap_cache_dir();
+        path.push("tags_index");
+        path.push(format!(
+            "{}.json",
+            calculate_hash(&(FORMAT_VERSION, args, dir))
+        ));
+        path
+    }
+
+    /// Loads the index of `dir` from the disk, returns an empty one if it does not exist.
+    pub fn load(args: &[&str], dir: &Path) -> Self {
+        File::open(Self::path(args, dir))
+            .ok()
+            .and_then(|f| serde_json::from_reader(BufReader::new(f)).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, args: &[&str], dir: &Path) -> Result<()> {
+        let path = Self::path(args, dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        serde_json::to_writer(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    /// Brings the index up to date with the files under `dir`.
+    ///
+    /// Returns the number of files that the symbols are extracted from.
+    pub fn update(&mut self, args: &[&str], dir: &Path, backend: Backend) -> Result<usize> {
+        let excludes = args
+            .iter()
+            .filter_map(|arg| arg.strip_prefix("--exclude="))
+            .collect::<Vec<_>>();
+
+        let mut current = BTreeMap::new();
+        collect_files(dir, dir, &excludes, &mut current)?;
+
+        // Drop the tags of deleted files.
+        self.files.retain(|path, _| current.contains_key(path));
+
+        let changed = current
+            .iter()
+            .filter(|(path, stamp)| {
+                self.files
+                    .get(*path)
+                    .map(|file_tags| file_tags.stamp != **stamp)
+                    .unwrap_or(true)
+            })
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+
+        let mut new_tags = match backend {
+            Backend::Builtin => run_builtin(args, dir, &changed),
+            _ => run_ctags(args, dir, &changed)?,
+        };
+
+        for path in changed.iter() {
+            let tags = new_tags.remove(*path).unwrap_or_default();
+            self.files.insert(
+                (*path).clone(),
+                FileTags {
+                    stamp: current[*path],
+                    tags,
+                },
+            );
+        }
+
+        Ok(changed.len())
+    }
+
+    /// Returns all the formatted tags, ordered by the file path.
+    pub fn formatted_tags(&self) -> impl Iterator<Item = &String> {
+        self.files
+            .values()
+            .flat_map(|file_tags| file_tags.tags.iter())
+    }
+}
+
+#[test]
+fn test_is_excluded() {
+    assert!(is_excluded("package.json", "*.json"));
+    assert!(is_excluded("node_modules", "node_modules"));
+    assert!(is_excluded(".git", ".git"));
+    assert!(!is_excluded("src", "target"));
+}
+
+#[test]
+fn test_update_tags_index() {
+    let dir = std::env::temp_dir().join(format!("clap_test_tags_index_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("target")).unwrap();
+    fs::write(dir.join("a.rs"), "fn foo() {}\n").unwrap();
+    fs::write(dir.join("b.rs"), "struct Bar;\n").unwrap();
+    fs::write(dir.join("target").join("c.rs"), "fn ignored() {}\n").unwrap();
+
+    let args = Backend::Builtin.base_args();
+    let names = |index: &TagsIndex| {
+        index
+            .formatted_tags()
+            .filter_map(|line| TagInfo::extract_name(line).map(String::from))
+            .collect::<Vec<_>>()
+    };
+
+    let mut index = TagsIndex::default();
+    assert_eq!(index.update(&args, &dir, Backend::Builtin).unwrap(), 2);
+    assert_eq!(names(&index), vec!["foo", "Bar"]);
+
+    // Nothing changed.
+    assert_eq!(index.update(&args, &dir, Backend::Builtin).unwrap(), 0);
+
+    // Modified, deleted and new files.
+    fs::write(dir.join("a.rs"), "fn foo() {}\n\nfn foo2() {}\n").unwrap();
+    fs::remove_file(dir.join("b.rs")).unwrap();
+    fs::write(dir.join("d.py"), "def baz():\n    pass\n").unwrap();
+    assert_eq!(index.update(&args, &dir, Backend::Builtin).unwrap(), 2);
+    assert_eq!(names(&index), vec!["foo", "foo2", "baz"]);
+    assert_eq!(
+        index
+            .formatted_tags()
+            .nth(1)
+            .and_then(|line| TagInfo::extract_position(line)),
+        Some(("a.rs".into(), 3))
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
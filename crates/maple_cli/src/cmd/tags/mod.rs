@@ -0,0 +1,506 @@
+use crate::cmd::cache::{cache_exists, send_response_from_cache, CacheEntry, SendResponse};
+use crate::config::TagsConfig;
+use crate::ContentFiltering;
+use anyhow::{anyhow, Result};
+use fuzzy_filter::{subprocess, Source};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use structopt::clap::arg_enum;
+use structopt::StructOpt;
+
+const BASE_TAGS_ARGS: [&str; 9] = [
+    "ctags",
+    "-R",
+    "-x",
+    "--output-format=json",
+    "--fields=+nSl",
+    "--exclude=.git",
+    "--exclude=*.json",
+    "--exclude=node_modules",
+    "--exclude=target",
+];
+
+/// Version of the formatted tags, which is part of the cache key so that the tags cached in the
+/// old format are not reused, bump it when the line format or the extracted symbols change.
+const FORMAT_VERSION: &str = "format-v5";
+
+mod buffer;
+mod builtin;
+mod incremental;
+mod tags_file;
+
+pub use self::buffer::{buffer_tags, cached_buffer_tags, BufferTag, BufferTags};
+use self::incremental::TagsIndex;
+pub use self::tags_file::{read_tags_files, resolve_address, TagFiles, TagsFileEntry};
+
+arg_enum! {
+  /// Tool used to extract the symbols of project.
+  #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+  pub enum Backend {
+      Auto,
+      Ctags,
+      Builtin,
+  }
+}
+
+lazy_static! {
+    static ref CTAGS_HAS_JSON_FEATURE: bool = detect_json_feature();
+}
+
+/// Returns true if the ctags executable is universal-ctags compiled with the JSON output support.
+///
+/// Exuberant ctags does not even know `--list-features`.
+fn detect_json_feature() -> bool {
+    std::process::Command::new("ctags")
+        .arg("--list-features")
+        .stderr(std::process::Stdio::null())
+        .output()
+        .map(|output| {
+            output.status.success()
+                && String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .any(|line| line.split_whitespace().next() == Some("json"))
+        })
+        .unwrap_or(false)
+}
+
+impl Backend {
+    /// Resolves `Auto` to the concrete backend, falls back to the built-in extractor when ctags
+    /// is missing or too old.
+    pub fn resolve(self) -> Result<Self> {
+        match self {
+            Self::Auto if *CTAGS_HAS_JSON_FEATURE => Ok(Self::Ctags),
+            Self::Auto => {
+                log::debug!(
+                    "ctags with JSON output support not found, fall back to the builtin backend"
+                );
+                Ok(Self::Builtin)
+            }
+            Self::Ctags if !*CTAGS_HAS_JSON_FEATURE => Err(anyhow!(
+                "universal-ctags with JSON output support is required, try --backend=builtin"
+            )),
+            backend => Ok(backend),
+        }
+    }
+
+    /// Returns the base arguments of this backend, which are also used as the cache key.
+    fn base_args(self) -> Vec<&'static str> {
+        match self {
+            Self::Builtin => std::iter::once("builtin")
+                .chain(
+                    BASE_TAGS_ARGS
+                        .iter()
+                        .filter(|arg| arg.starts_with("--exclude="))
+                        .copied(),
+                )
+                .collect(),
+            _ => BASE_TAGS_ARGS.to_vec(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct TagInfo {
+    name: String,
+    path: String,
+    pattern: String,
+    line: usize,
+    kind: String,
+    #[serde(default)]
+    scope: Option<String>,
+    /// Kind of the scope, e.g., `implementation` for the methods in the `impl` block of Rust.
+    #[serde(rename = "scopeKind", default)]
+    scope_kind: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+}
+
+impl TagInfo {
+    /// Creates the tag of which the definition is at line `line` of `path`, `text` is the content
+    /// of that line.
+    pub(crate) fn new(name: String, path: String, line: usize, kind: String, text: &str) -> Self {
+        Self {
+            name,
+            path,
+            pattern: format!("/^{}$/", text),
+            line,
+            kind,
+            scope: None,
+            scope_kind: None,
+            signature: None,
+            language: None,
+        }
+    }
+
+    /// Returns the scope prefixed by its kind if known, e.g., `impl Exec` or `struct Exec`.
+    fn scope_with_kind(&self) -> Option<String> {
+        let scope = self.scope.as_ref()?;
+        Some(match self.scope_kind.as_deref() {
+            Some("implementation") => format!("impl {}", scope),
+            Some(kind) => format!("{} {}", kind, scope),
+            None => scope.clone(),
+        })
+    }
+
+    /// Returns the line of the tag definition, i.e., the pattern without `/^` and `$/`.
+    pub fn pattern_text(&self) -> &str {
+        let pattern = self.pattern.strip_prefix("/^").unwrap_or(&self.pattern);
+        pattern
+            .strip_suffix("$/")
+            .or_else(|| pattern.strip_suffix('/'))
+            .unwrap_or(pattern)
+            .trim()
+    }
+
+    /// Returns the display line `{name}:{line}\t[{language}:{kind}@{path}]\t{scope_kind} {scope} {signature}\t{pattern}`.
+    ///
+    /// The fields are delimited by tab which can not appear in the tag name, path or scope, the
+    /// text of pattern may contain tab but it's always the last field. The language, scope and
+    /// signature are omitted if unknown, the kind of scope `implementation` is shown as `impl`.
+    pub fn format(&self) -> String {
+        let name_lnum = format!("{}:{}", self.name, self.line);
+        let kind = match &self.language {
+            Some(language) => format!("[{}:{}@{}]", language, self.kind, self.path),
+            None => format!("[{}@{}]", self.kind, self.path),
+        };
+        let context = self
+            .scope_with_kind()
+            .into_iter()
+            .chain(self.signature.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "{text:<width1$}\t{kind:<width2$}\t{context}\t{pattern}",
+            text = name_lnum,
+            width1 = 30,
+            kind = kind,
+            width2 = 30,
+            context = context,
+            pattern = self.pattern_text(),
+        )
+    }
+
+    /// Returns the tuple of (name, line, kind, path) from the line produced by
+    /// [`TagInfo::format`].
+    fn parse_formatted(formatted: &str) -> Option<(&str, usize, &str, &str)> {
+        let mut fields = formatted.splitn(3, '\t');
+        let (name, line) = split_last(fields.next()?.trim_end(), ':')?;
+        let line = line.parse().ok()?;
+        let kind_path = fields.next()?.trim_end();
+        let kind_path = kind_path.strip_prefix('[')?.strip_suffix(']')?;
+        let (language_kind, path) = split_first(kind_path, '@')?;
+        let kind = split_last(language_kind, ':')
+            .map(|(_language, kind)| kind)
+            .unwrap_or(language_kind);
+        Some((name, line, kind, path))
+    }
+
+    /// Returns the tuple of (path, line) from the line produced by [`TagInfo::format`].
+    pub fn extract_position(formatted: &str) -> Option<(PathBuf, usize)> {
+        Self::parse_formatted(formatted).map(|(_name, line, _kind, path)| (path.into(), line))
+    }
+
+    /// Returns the name from the line produced by [`TagInfo::format`].
+    pub fn extract_name(formatted: &str) -> Option<&str> {
+        Self::parse_formatted(formatted).map(|(name, _line, _kind, _path)| name)
+    }
+
+    /// Returns the kind from the line produced by [`TagInfo::format`].
+    pub fn extract_kind(formatted: &str) -> Option<&str> {
+        Self::parse_formatted(formatted).map(|(_name, _line, kind, _path)| kind)
+    }
+}
+
+#[inline]
+fn split_first(s: &str, sep: char) -> Option<(&str, &str)> {
+    s.find(sep).map(|idx| (&s[..idx], &s[idx + 1..]))
+}
+
+#[inline]
+fn split_last(s: &str, sep: char) -> Option<(&str, &str)> {
+    s.rfind(sep).map(|idx| (&s[..idx], &s[idx + 1..]))
+}
+
+/// Generate ctags recursively given the directory.
+#[derive(StructOpt, Debug, Clone)]
+pub struct Tags {
+    /// Initial query string
+    #[structopt(index = 1, short, long)]
+    query: String,
+
+    /// The directory to generate recursive ctags.
+    #[structopt(index = 2, short, long, parse(from_os_str))]
+    dir: PathBuf,
+
+    /// Specify the language.
+    #[structopt(long = "languages")]
+    languages: Option<String>,
+
+    /// Read input from a cached grep tempfile, only absolute file path is supported.
+    #[structopt(long = "input", parse(from_os_str))]
+    input: Option<PathBuf>,
+
+    /// Runs as the forerunner job, create the new cache entry.
+    #[structopt(short, long)]
+    forerunner: bool,
+
+    /// Tool used to extract the symbols, `auto` uses ctags if available.
+    #[structopt(
+        long,
+        default_value = "auto",
+        possible_values = &Backend::variants(),
+        case_insensitive = true
+    )]
+    backend: Backend,
+}
+
+/// Returns the command of `args` running in `dir`, the empty args are skipped.
+///
+/// The args are passed to ctags directly instead of via the shell, the excludes and options from
+/// the project config may contain the spaces or any other special chars of the shell.
+fn ctags_exec(args: &[&str], dir: &Path) -> subprocess::Exec {
+    subprocess::Exec::cmd(args[0])
+        .args(
+            &args[1..]
+                .iter()
+                .filter(|arg| !arg.is_empty())
+                .collect::<Vec<_>>(),
+        )
+        .cwd(dir)
+}
+
+fn formatted_tags_stream(args: &[&str], dir: &Path) -> Result<impl Iterator<Item = String>> {
+    let stdout_stream = ctags_exec(args, dir).stream_stdout()?;
+    Ok(BufReader::new(stdout_stream).lines().filter_map(|line| {
+        line.ok().and_then(|tag| {
+            if let Ok(tag) = serde_json::from_str::<TagInfo>(&tag) {
+                Some(tag.format())
+            } else {
+                None
+            }
+        })
+    }))
+}
+
+/// Returns all the formatted tags of `dir`, the symbols are only extracted from the files changed
+/// since last time.
+fn collect_formatted_tags(args: &[&str], dir: &Path, backend: Backend) -> Result<Vec<String>> {
+    let mut index = TagsIndex::load(args, dir);
+    index.update(args, dir, backend)?;
+    index.save(args, dir)?;
+    Ok(index.formatted_tags().cloned().collect())
+}
+
+/// Returns the args of generating the tags, `extra_args` from the project config are appended.
+fn with_extra_args<'a>(args: &[&'a str], extra_args: &'a [String]) -> Vec<&'a str> {
+    args.iter()
+        .copied()
+        .chain(extra_args.iter().map(String::as_str))
+        .collect()
+}
+
+/// Keeps only the formatted tags of the kinds specified in the project config.
+pub(crate) fn filter_kinds<'a>(
+    formatted_tags: impl Iterator<Item = String> + 'a,
+    config: &'a TagsConfig,
+) -> impl Iterator<Item = String> + 'a {
+    formatted_tags.filter(move |line| {
+        TagInfo::extract_kind(line)
+            .map(|kind| config.keep_kind(kind))
+            .unwrap_or(true)
+    })
+}
+
+/// Creates the cache of formatted tags, ctags is only run on the files changed since last time.
+///
+/// The cache entry is keyed by `cache_args`, the project config is covered by the hash of dir.
+fn create_tags_cache(
+    cache_args: &[&str],
+    tags_args: &[&str],
+    dir: &Path,
+    backend: Backend,
+    config: &TagsConfig,
+) -> Result<(PathBuf, usize)> {
+    let formatted_tags = filter_kinds(
+        collect_formatted_tags(tags_args, dir, backend)?.into_iter(),
+        config,
+    )
+    .collect::<Vec<_>>();
+    let total = formatted_tags.len();
+    let lines = formatted_tags.into_iter().join("\n");
+    let cache = CacheEntry::create(cache_args, Some(dir.to_path_buf()), total, lines)?;
+    Ok((cache, total))
+}
+
+/// Returns the positions of the definitions of symbol `name` in the project `dir`.
+///
+/// The same tags index with `proj_tags` is used, only the changed files are re-parsed.
+pub fn find_definitions(
+    name: &str,
+    dir: &Path,
+    backend: Backend,
+    config: &TagsConfig,
+) -> Result<Vec<(PathBuf, usize)>> {
+    let backend = backend.resolve()?;
+    let extra_args = config.extra_args(backend == Backend::Ctags);
+    let mut args = backend.base_args();
+    // Same with the args of Tags without --languages.
+    args.push("");
+    let args = with_extra_args(&args, &extra_args);
+    Ok(collect_formatted_tags(&args, dir, backend)?
+        .iter()
+        .filter(|line| TagInfo::extract_name(line) == Some(name))
+        .filter_map(|line| TagInfo::extract_position(line))
+        .collect())
+}
+
+impl Tags {
+    /// Returns the project root, where the project config is looked up.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn run(&self, no_cache: bool, config: &TagsConfig) -> Result<()> {
+        let backend = self.backend.resolve()?;
+        let mut cmd_args = backend.base_args();
+        let lang = if let Some(ref languages) = self.languages {
+            format!("--languages={}", languages)
+        } else {
+            String::from("")
+        };
+        cmd_args.push(&lang);
+
+        let extra_args = config.extra_args(backend == Backend::Ctags);
+        let tags_args = with_extra_args(&cmd_args, &extra_args);
+
+        if self.forerunner {
+            let mut cache_args = cmd_args.clone();
+            cache_args.push(FORMAT_VERSION);
+            let (cache, total) = if no_cache {
+                create_tags_cache(&cache_args, &tags_args, &self.dir, backend, config)?
+            } else if let Ok(cached_info) = cache_exists(&cache_args, &self.dir) {
+                cached_info
+            } else {
+                create_tags_cache(&cache_args, &tags_args, &self.dir, backend, config)?
+            };
+            send_response_from_cache(&cache, total, SendResponse::Json, None);
+            return Ok(());
+        } else {
+            let formatted_tags: Box<dyn Iterator<Item = String>> = if backend == Backend::Builtin {
+                Box::new(collect_formatted_tags(&tags_args, &self.dir, backend)?.into_iter())
+            } else {
+                Box::new(formatted_tags_stream(&tags_args, &self.dir)?)
+            };
+            crate::cmd::filter::dynamic::dyn_fuzzy_filter_and_rank(
+                &self.query,
+                Source::List(filter_kinds(formatted_tags, config)),
+                None,
+                Some(30),
+                None,
+                None,
+                ContentFiltering::TagNameOnly,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ctags_exec_without_shell() {
+    let dir = std::env::temp_dir().join(format!("clap_test_ctags_exec_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let extra_args = vec!["--exclude=a b; touch injected".to_string()];
+    let args = with_extra_args(&["echo", "-R", ""], &extra_args);
+    let output = ctags_exec(&args, &dir).capture().unwrap();
+    assert_eq!(output.stdout_str(), "-R --exclude=a b; touch injected\n");
+    assert!(!dir.join("injected").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_parse_ctags_line() {
+    let data = r#"{"_type": "tag", "name": "Exec", "path": "crates/maple_cli/src/cmd/exec.rs", "pattern": "/^pub struct Exec {$/", "line": 10, "kind": "struct"}"#;
+    let tag: TagInfo = serde_json::from_str(&data).unwrap();
+    assert_eq!(tag.name, "Exec");
+
+    let data = r#"{"_type": "tag", "name": "run", "path": "crates/maple_cli/src/cmd/exec.rs", "pattern": "/^    pub fn run(&self) -> Result<()> {$/", "language": "Rust", "line": 42, "kind": "method", "signature": "(&self)", "scope": "Exec", "scopeKind": "implementation"}"#;
+    let tag: TagInfo = serde_json::from_str(data).unwrap();
+    assert_eq!(tag.scope.as_deref(), Some("Exec"));
+    assert_eq!(tag.signature.as_deref(), Some("(&self)"));
+    assert_eq!(tag.language.as_deref(), Some("Rust"));
+
+    let formatted = tag.format();
+    assert_eq!(
+        formatted.split('\t').map(str::trim_end).collect::<Vec<_>>(),
+        vec![
+            "run:42",
+            "[Rust:method@crates/maple_cli/src/cmd/exec.rs]",
+            "impl Exec (&self)",
+            "pub fn run(&self) -> Result<()> {"
+        ]
+    );
+    assert_eq!(TagInfo::extract_kind(&formatted), Some("method"));
+    assert_eq!(
+        TagInfo::extract_position(&formatted),
+        Some(("crates/maple_cli/src/cmd/exec.rs".into(), 42))
+    );
+}
+
+#[test]
+fn test_extract_tag_position() {
+    let data = r#"{"_type": "tag", "name": "Exec", "path": "crates/maple_cli/src/cmd/exec.rs", "pattern": "/^pub struct Exec {$/", "line": 10, "kind": "struct"}"#;
+    let tag: TagInfo = serde_json::from_str(data).unwrap();
+    assert_eq!(
+        TagInfo::extract_position(&tag.format()),
+        Some(("crates/maple_cli/src/cmd/exec.rs".into(), 10))
+    );
+
+    assert_eq!(TagInfo::extract_kind(&tag.format()), Some("struct"));
+
+    let line = "std::ops::Index:3\t[implementation@src/a b.rs]";
+    assert_eq!(
+        TagInfo::extract_position(line),
+        Some(("src/a b.rs".into(), 3))
+    );
+
+    // The name and path containing the brackets.
+    let tag = TagInfo::new(
+        "operator []".into(),
+        "src/x] y/vec.hpp".into(),
+        42,
+        "function".into(),
+        "T& operator [](size_t i) { return data_[i]; }",
+    );
+    let formatted = tag.format();
+    assert_eq!(TagInfo::extract_name(&formatted), Some("operator []"));
+    assert_eq!(TagInfo::extract_kind(&formatted), Some("function"));
+    assert_eq!(
+        TagInfo::extract_position(&formatted),
+        Some(("src/x] y/vec.hpp".into(), 42))
+    );
+
+    let tag = TagInfo::new(
+        "Intro [draft] @home".into(),
+        "docs/a@b [1].md".into(),
+        3,
+        "chapter".into(),
+        "# Intro [draft] @home",
+    );
+    assert_eq!(
+        TagInfo::extract_position(&tag.format()),
+        Some(("docs/a@b [1].md".into(), 3))
+    );
+    assert_eq!(
+        TagInfo::extract_name(&tag.format()),
+        Some("Intro [draft] @home")
+    );
+}
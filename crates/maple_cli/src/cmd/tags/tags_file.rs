@@ -0,0 +1,373 @@
+//! Reader of the existing tags files in the Vi/Exuberant format, e.g., the ones maintained by
+//! gutentags, the help tags of Vim are in the same format.
+//!
+//! ```text
+//! {tagname}<Tab>{tagfile}<Tab>{tagaddress}[;"<Tab>{kind}<Tab>{field}:{value}...]
+//! ```
+
+use super::{split_first, TagInfo};
+use crate::utils::read_lines;
+use crate::ContentFiltering;
+use anyhow::{anyhow, Result};
+use fuzzy_filter::Source;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Extension fields of which the name is the kind of scope, e.g., `class:Foo`.
+const SCOPE_FIELDS: [&str; 9] = [
+    "class",
+    "struct",
+    "union",
+    "enum",
+    "interface",
+    "namespace",
+    "module",
+    "implementation",
+    "function",
+];
+
+/// An entry of the tags file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TagsFileEntry {
+    pub name: String,
+    /// Path of the file containing the tag, relative to the directory of tags file.
+    pub path: String,
+    /// Ex command locating the tag, either a line number or a search pattern, e.g.,
+    /// `/^fn main() {$/`.
+    pub address: String,
+    /// Kind of the tag, the full name or a single letter.
+    pub kind: Option<String>,
+    /// Extension fields other than kind, e.g., `("line", "42")`.
+    pub fields: Vec<(String, String)>,
+}
+
+impl TagsFileEntry {
+    /// Parses a line of the tags file, returns `None` for the `!_TAG_` headers.
+    pub fn parse(line: &str) -> Option<Self> {
+        if line.starts_with("!_TAG_") {
+            return None;
+        }
+
+        let mut items = line.splitn(3, '\t');
+        let name = items.next()?;
+        let path = items.next()?;
+        let rest = items.next()?;
+
+        // The address can contain tabs, the extension fields follow `;"`.
+        let (address, fields) = match rest.find(";\"\t") {
+            Some(idx) => (&rest[..idx], Some(&rest[idx + 3..])),
+            None => (rest.trim_end_matches(";\""), None),
+        };
+
+        let mut kind = None;
+        let mut extra_fields = Vec::new();
+        for field in fields.into_iter().flat_map(|x| x.split('\t')) {
+            let mut name_value = field.splitn(2, ':');
+            match (name_value.next(), name_value.next()) {
+                (Some("kind"), Some(value)) => kind = Some(value.into()),
+                (Some(name), Some(value)) => extra_fields.push((name.into(), value.into())),
+                (Some(name), None) if !name.is_empty() => kind = Some(name.into()),
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            name: name.into(),
+            path: path.into(),
+            address: address.into(),
+            kind,
+            fields: extra_fields,
+        })
+    }
+
+    /// Returns the value of extension field `name`.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the tuple of (scope_kind, scope), either from the `scope:{kind}:{scope}` field of
+    /// universal-ctags or the `{kind}:{scope}` field, e.g., `class:Foo`.
+    fn scope(&self) -> Option<(&str, &str)> {
+        if let Some(value) = self.field("scope") {
+            return split_first(value, ':');
+        }
+        self.fields
+            .iter()
+            .find(|(n, _)| SCOPE_FIELDS.contains(&n.as_str()))
+            .map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// Returns the line number of the tag if it's known without searching the file.
+    pub fn line(&self) -> Option<usize> {
+        self.field("line").unwrap_or(&self.address).parse().ok()
+    }
+
+    /// Converts into the tag of `proj_tags` format at line `lnum`, the path is joined with
+    /// `tags_dir`.
+    fn into_tag_info(self, tags_dir: &Path, cwd: &Path, lnum: usize) -> TagInfo {
+        let path = tags_dir.join(&self.path);
+        let path = path.strip_prefix(cwd).unwrap_or(&path);
+        let pattern = match SearchPattern::parse(&self.address) {
+            Some(search) => format!("/^{}$/", search.text),
+            None => String::new(),
+        };
+        let signature = self.field("signature").map(Into::into);
+        let language = self.field("language").map(Into::into);
+        let (scope_kind, scope) = match self.scope() {
+            Some((kind, scope)) => (Some(kind.into()), Some(scope.into())),
+            None => (None, None),
+        };
+        TagInfo {
+            line: lnum,
+            path: path.display().to_string(),
+            kind: self.kind.clone().unwrap_or_default(),
+            name: self.name,
+            pattern,
+            scope,
+            scope_kind,
+            signature,
+            language,
+        }
+    }
+}
+
+/// Search pattern of the tag address, the escaped chars are restored.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct SearchPattern {
+    text: String,
+    /// The pattern starts with `^`.
+    line_start: bool,
+    /// The pattern ends with `$`.
+    line_end: bool,
+}
+
+impl SearchPattern {
+    /// Returns `None` if `address` is not a search pattern, i.e., `/{pattern}/` or
+    /// `?{pattern}?`.
+    fn parse(address: &str) -> Option<Self> {
+        let delimiter = address.chars().next().filter(|c| *c == '/' || *c == '?')?;
+        let mut pattern = &address[1..];
+        if pattern.ends_with(delimiter) && !pattern.ends_with(&format!("\\{}", delimiter)) {
+            pattern = &pattern[..pattern.len() - 1];
+        }
+
+        let line_start = pattern.starts_with('^');
+        if line_start {
+            pattern = &pattern[1..];
+        }
+        let line_end = pattern.ends_with('$') && !pattern.ends_with("\\$");
+        if line_end {
+            pattern = &pattern[..pattern.len() - 1];
+        }
+
+        let mut text = String::with_capacity(pattern.len());
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                text.extend(chars.next());
+            } else {
+                text.push(c);
+            }
+        }
+
+        Some(Self {
+            text,
+            line_start,
+            line_end,
+        })
+    }
+
+    fn matches(&self, line: &str) -> bool {
+        match (self.line_start, self.line_end) {
+            (true, true) => line == self.text,
+            (true, false) => line.starts_with(&self.text),
+            (false, true) => line.ends_with(&self.text),
+            (false, false) => line.contains(&self.text),
+        }
+    }
+
+    /// Returns the 1-based line number of the first line matching the pattern.
+    fn find<S: AsRef<str>>(&self, lines: &[S]) -> Option<usize> {
+        lines
+            .iter()
+            .position(|line| self.matches(line.as_ref()))
+            .map(|idx| idx + 1)
+    }
+}
+
+/// Returns the 1-based line number in `path` the tag `address` points to.
+pub fn resolve_address(address: &str, path: &Path) -> Result<usize> {
+    if let Ok(lnum) = address.parse() {
+        return Ok(lnum);
+    }
+    let search = SearchPattern::parse(address)
+        .ok_or_else(|| anyhow!("Unsupported tag address: {}", address))?;
+    let lines = read_lines(path)?.collect::<std::io::Result<Vec<_>>>()?;
+    search
+        .find(&lines)
+        .ok_or_else(|| anyhow!("{} not found in {}", address, path.display()))
+}
+
+/// Returns the tags of all the `tags_files` in the `proj_tags` format.
+///
+/// The line number of the tag located by a search pattern is resolved by searching the file,
+/// which is read only once for all its tags. The tags that can not be located are skipped, e.g.,
+/// the file is changed since the tags file was generated.
+pub fn read_tags_files(tags_files: &[PathBuf], cwd: &Path) -> Vec<String> {
+    // Lines of the files searched, `None` if the file is unreadable.
+    let mut files: HashMap<PathBuf, Option<Vec<String>>> = HashMap::new();
+    let mut formatted_tags = Vec::new();
+
+    for tags_file in tags_files {
+        let tags_dir = tags_file.parent().unwrap_or_else(|| Path::new(""));
+        let lines = match read_lines(tags_file) {
+            Ok(lines) => lines,
+            Err(_) => continue,
+        };
+        for entry in lines.filter_map(|line| TagsFileEntry::parse(&line.ok()?)) {
+            let lnum = match entry.line() {
+                Some(lnum) => Some(lnum),
+                None => SearchPattern::parse(&entry.address).and_then(|search| {
+                    let path = tags_dir.join(&entry.path);
+                    let lines = files.entry(path.clone()).or_insert_with(|| {
+                        read_lines(&path)
+                            .and_then(|lines| lines.collect::<std::io::Result<Vec<_>>>())
+                            .ok()
+                    });
+                    search.find(lines.as_ref()?)
+                }),
+            };
+            if let Some(lnum) = lnum {
+                formatted_tags.push(entry.into_tag_info(tags_dir, cwd, lnum).format());
+            }
+        }
+    }
+
+    formatted_tags
+}
+
+/// Fuzzy filter the tags of existing tags files, e.g., the files of Vim `&tags`.
+#[derive(StructOpt, Debug, Clone)]
+pub struct TagFiles {
+    /// Initial query string
+    #[structopt(index = 1, short, long)]
+    query: String,
+
+    /// Tags files to read.
+    #[structopt(index = 2, parse(from_os_str))]
+    files: Vec<PathBuf>,
+}
+
+impl TagFiles {
+    pub fn run(&self, number: Option<usize>, winwidth: Option<usize>) -> Result<()> {
+        let cwd = std::env::current_dir()?;
+        let formatted_tags = read_tags_files(&self.files, &cwd);
+        crate::cmd::filter::dynamic::dyn_fuzzy_filter_and_rank(
+            &self.query,
+            Source::List(formatted_tags.into_iter()),
+            None,
+            number,
+            winwidth,
+            None,
+            ContentFiltering::TagNameOnly,
+        )
+    }
+}
+
+#[test]
+fn test_parse_tags_file_line() {
+    assert!(TagsFileEntry::parse("!_TAG_FILE_FORMAT\t2\t/extended format/").is_none());
+
+    let entry =
+        TagsFileEntry::parse("main\tsrc/main.rs\t/^fn main() {$/;\"\tf\tline:42\tsignature:()")
+            .unwrap();
+    assert_eq!(entry.name, "main");
+    assert_eq!(entry.address, "/^fn main() {$/");
+    assert_eq!(entry.kind.as_deref(), Some("f"));
+    assert_eq!(entry.field("signature"), Some("()"));
+    assert_eq!(entry.line(), Some(42));
+
+    let entry = TagsFileEntry::parse("Foo\tfoo.c\t12;\"\tkind:struct").unwrap();
+    assert_eq!(entry.kind.as_deref(), Some("struct"));
+    assert_eq!(entry.line(), Some(12));
+
+    // Helptags without extension fields.
+    let entry = TagsFileEntry::parse("clap\tclap.txt\t/*clap*").unwrap();
+    assert_eq!(entry.address, "/*clap*");
+    assert_eq!(entry.kind, None);
+
+    let tag = TagsFileEntry::parse("f\ta.rs\t/^fn f(a: \\/x) {$/;\"\tf")
+        .unwrap()
+        .into_tag_info(Path::new("/proj"), Path::new("/proj"), 3);
+    assert_eq!(
+        TagInfo::extract_position(&tag.format()),
+        Some(("a.rs".into(), 3))
+    );
+    assert_eq!(tag.pattern_text(), "fn f(a: /x) {");
+
+    let tag = TagsFileEntry::parse("new\ta.rs\t3;\"\tP\timplementation:foo::Bar")
+        .unwrap()
+        .into_tag_info(Path::new("/proj"), Path::new("/proj"), 3);
+    assert_eq!(tag.scope.as_deref(), Some("foo::Bar"));
+    assert_eq!(tag.scope_kind.as_deref(), Some("implementation"));
+
+    let entry = TagsFileEntry::parse("run\ta.rs\t3;\"\tm\tscope:class:Exec").unwrap();
+    assert_eq!(entry.scope(), Some(("class", "Exec")));
+}
+
+#[test]
+fn test_read_tags_files() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("clap_test_tags_file_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src").join("lib.rs"),
+        "use std::io;\n\nfn foo() {\n}\n\nfn bar() {\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("tags"),
+        [
+            "bar\tsrc/lib.rs\t/^fn bar() {$/;\"\tf",
+            "foo\tsrc/lib.rs\t/^fn foo() {$/;\"\tf",
+            "gone\tsrc/lib.rs\t/^fn gone() {$/;\"\tf",
+            "io\tsrc/lib.rs\t1;\"\tn",
+            "missing\tsrc/missing.rs\t/^fn missing() {$/;\"\tf",
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+
+    let positions = read_tags_files(&[dir.join("tags")], &dir)
+        .iter()
+        .map(|tag| TagInfo::extract_position(tag).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        positions,
+        vec![
+            ("src/lib.rs".into(), 6),
+            ("src/lib.rs".into(), 3),
+            ("src/lib.rs".into(), 1)
+        ]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_search_pattern() {
+    let search = SearchPattern::parse("/^fn main() {$/").unwrap();
+    assert!(search.matches("fn main() {"));
+    assert!(!search.matches("    fn main() {"));
+
+    let search = SearchPattern::parse("?*clap*?").unwrap();
+    assert!(search.matches("Contents\t*clap*"));
+
+    assert!(SearchPattern::parse("42").is_none());
+}
@@ -0,0 +1,279 @@
+//! Config of maple, the global config `~/.config/maple/config.toml` is overridden by the project
+//! config `.clap.toml` or `.maple.toml` at the project root, both of which are optional.
+//!
+//! ```toml
+//! number = 100
+//! winwidth = 80
+//! icon_painter = "File"
+//! algo = "Fzy"
+//! output_threshold = 100000
+//!
+//! [tags]
+//! exclude = ["venv", ".tox", "dist"]
+//! kinds = ["function", "method", "struct"]
+//! ctags_options = ["--langmap=python:+.pyx"]
+//!
+//! [lsp.rust]
+//! command = ["rust-analyzer"]
+//! extensions = ["rs"]
+//! ```
+//!
+//! The CLI flags always take precedence over the config values.
+
+use anyhow::{Context, Result};
+use fuzzy_filter::Algo;
+use icon::IconPainter;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File names of the project config, the first existing one is used.
+pub const PROJECT_CONFIG_FILES: [&str; 2] = [".clap.toml", ".maple.toml"];
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Default value of `--number`.
+    pub number: Option<usize>,
+    /// Default value of `--winwidth`.
+    pub winwidth: Option<usize>,
+    /// Default value of `--icon-painter`.
+    #[serde(with = "arg_enum_option")]
+    pub icon_painter: Option<IconPainter>,
+    /// Default filter algorithm of `filter` subcommand and the RPC methods.
+    #[serde(with = "arg_enum_option")]
+    pub algo: Option<Algo>,
+    /// Default value of `--output-threshold` of `exec` and `ripgrep-forerunner` subcommand.
+    pub output_threshold: Option<usize>,
+    pub tags: TagsConfig,
+    /// Language servers keyed by the name, e.g., `[lsp.rust]`.
+    pub lsp: BTreeMap<String, LspServerConfig>,
+}
+
+/// Config of the project tags.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq)]
+#[serde(default)]
+pub struct TagsConfig {
+    /// Files or directories to exclude in addition to the default ones, e.g., `venv`, `*.min.js`.
+    pub exclude: Vec<String>,
+    /// Kinds of the tags to keep, e.g., `function`, all the kinds are kept if it's empty.
+    pub kinds: Vec<String>,
+    /// Extra ctags options, e.g., `--langmap=python:+.pyx`.
+    pub ctags_options: Vec<String>,
+}
+
+impl TagsConfig {
+    /// Returns the extra args of generating the tags, the ctags options are only included when
+    /// `with_ctags_options` is true.
+    pub fn extra_args(&self, with_ctags_options: bool) -> Vec<String> {
+        let excludes = self.exclude.iter().map(|x| format!("--exclude={}", x));
+        if with_ctags_options {
+            excludes.chain(self.ctags_options.iter().cloned()).collect()
+        } else {
+            excludes.collect()
+        }
+    }
+
+    /// Returns true if the tag of `kind` should be kept.
+    pub fn keep_kind(&self, kind: &str) -> bool {
+        self.kinds.is_empty() || self.kinds.iter().any(|k| k == kind)
+    }
+
+    /// The excludes and ctags options are accumulated, the kinds of `other` replace the current
+    /// ones if any.
+    fn merge(mut self, other: Self) -> Self {
+        self.exclude.extend(other.exclude);
+        self.ctags_options.extend(other.ctags_options);
+        if !other.kinds.is_empty() {
+            self.kinds = other.kinds;
+        }
+        self
+    }
+}
+
+/// Config of a language server started over stdio.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq)]
+#[serde(default)]
+pub struct LspServerConfig {
+    /// Command and arguments to start the server, e.g., `["rust-analyzer"]`.
+    pub command: Vec<String>,
+    /// Extensions of the files handled by the server, e.g., `["rs"]`.
+    pub extensions: Vec<String>,
+    /// Seconds to wait for each response, 30 by default.
+    pub timeout: Option<u64>,
+}
+
+/// (De)serializes the `arg_enum` value by its name, e.g., `"Fzy"`, which is case-insensitive.
+mod arg_enum_option {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(v) => serializer.serialize_some(&v.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| s.parse().map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+/// Returns the path of the global config, i.e., `$XDG_CONFIG_HOME/maple/config.toml`, falls back
+/// to `~/.config/maple/config.toml`.
+pub fn global_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("maple").join("config.toml"))
+}
+
+/// Returns the path of the project config file in `dir`, if any.
+pub fn find_project_config<P: AsRef<Path>>(dir: P) -> Option<PathBuf> {
+    PROJECT_CONFIG_FILES
+        .iter()
+        .map(|fname| dir.as_ref().join(fname))
+        .find(|path| path.is_file())
+}
+
+/// Returns the existing config files applied to project `dir`, from the lowest priority.
+pub fn config_files<P: AsRef<Path>>(dir: P) -> Vec<PathBuf> {
+    global_config_path()
+        .filter(|path| path.is_file())
+        .into_iter()
+        .chain(find_project_config(dir))
+        .collect()
+}
+
+impl Config {
+    fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        toml::from_str(&contents).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    /// Returns a config of which the values of `other` take precedence.
+    fn merge(self, other: Self) -> Self {
+        Self {
+            number: other.number.or(self.number),
+            winwidth: other.winwidth.or(self.winwidth),
+            icon_painter: other.icon_painter.or(self.icon_painter),
+            algo: other.algo.or(self.algo),
+            output_threshold: other.output_threshold.or(self.output_threshold),
+            tags: self.tags.merge(other.tags),
+            lsp: {
+                let mut lsp = self.lsp;
+                lsp.extend(other.lsp);
+                lsp
+            },
+        }
+    }
+
+    /// Loads the effective config of project `dir`, returns the default config if there is none.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        config_files(dir)
+            .iter()
+            .try_fold(Self::default(), |config, path| {
+                Ok(config.merge(Self::from_file(path)?))
+            })
+    }
+
+    /// Same with [`Config::load`], but the invalid config is logged and the default config is
+    /// returned instead, `maple config` can be used to see the error.
+    pub fn load_or_default<P: AsRef<Path>>(dir: P) -> Self {
+        Self::load(dir).unwrap_or_else(|e| {
+            log::warn!("{:?}, fall back to the default config", e);
+            Self::default()
+        })
+    }
+}
+
+/// Returns the hash of the config files applied to `dir`, `None` if there is no config.
+pub fn config_hash<P: AsRef<Path>>(dir: P) -> Option<u64> {
+    let contents = config_files(dir)
+        .into_iter()
+        .filter_map(|path| std::fs::read(path).ok())
+        .collect::<Vec<_>>();
+    if contents.is_empty() {
+        None
+    } else {
+        Some(crate::utils::calculate_hash(&contents))
+    }
+}
+
+#[test]
+fn test_parse_project_config() {
+    let config: Config = toml::from_str(
+        r#"
+[tags]
+exclude = ["venv", ".tox"]
+kinds = ["function"]
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.tags.extra_args(true),
+        vec!["--exclude=venv", "--exclude=.tox"]
+    );
+    assert!(config.tags.keep_kind("function"));
+    assert!(!config.tags.keep_kind("struct"));
+    assert_eq!(
+        toml::from_str::<Config>("").unwrap().tags,
+        TagsConfig::default()
+    );
+}
+
+#[test]
+fn test_merge_config() {
+    let global: Config = toml::from_str(
+        r#"
+number = 100
+icon_painter = "file"
+algo = "skim"
+
+[tags]
+exclude = ["venv"]
+kinds = ["function"]
+"#,
+    )
+    .unwrap();
+    let project: Config = toml::from_str(
+        r#"
+number = 50
+algo = "Fzy"
+
+[tags]
+exclude = ["dist"]
+"#,
+    )
+    .unwrap();
+    let config = global.merge(project);
+    assert_eq!(config.number, Some(50));
+    assert_eq!(
+        config.icon_painter.as_ref().map(ToString::to_string),
+        Some("File".into())
+    );
+    assert_eq!(
+        config.algo.as_ref().map(ToString::to_string),
+        Some("Fzy".into())
+    );
+    assert_eq!(config.tags.exclude, vec!["venv", "dist"]);
+    assert_eq!(config.tags.kinds, vec!["function"]);
+
+    let shown = toml::to_string(&config).unwrap();
+    assert!(shown.contains("algo = \"Fzy\""));
+    assert!(toml::from_str::<Config>("algo = \"foo\"").is_err());
+}
@@ -22,6 +22,7 @@ macro_rules! print_json_with_length {
 }
 
 mod app;
+mod config;
 mod light_command;
 mod logger;
 mod utils;
@@ -66,17 +66,28 @@ pub(crate) fn clap_cache_dir() -> PathBuf {
     dir
 }
 
+/// Returns the hash of `cmd_dir` used in the cache path.
+///
+/// The config files are taken into account so that the cache is invalidated once the config is
+/// changed.
+pub(crate) fn hash_cmd_dir(cmd_dir: &PathBuf) -> u64 {
+    match crate::config::config_hash(cmd_dir) {
+        Some(config_hash) => calculate_hash(&(cmd_dir, config_hash)),
+        None => calculate_hash(&cmd_dir),
+    }
+}
+
 /// Returns the cache path for clap.
 ///
 /// The reason for using hash(cmd_dir) instead of cmd_dir directory is to avoid the possible issue
 /// of using a path as the directory name.
 ///
-/// Formula: temp_dir + clap_cache + arg1_arg2_arg3 + hash(cmd_dir)
+/// Formula: temp_dir + clap_cache + arg1_arg2_arg3 + hash(cmd_dir, config)
 pub fn get_cache_dir(args: &[&str], cmd_dir: &PathBuf) -> PathBuf {
     let mut dir = clap_cache_dir();
     dir.push(args.join("_"));
     // TODO: use a readable cache cmd_dir name?
-    dir.push(format!("{}", calculate_hash(&cmd_dir)));
+    dir.push(format!("{}", hash_cmd_dir(cmd_dir)));
     dir
 }
 
@@ -121,3 +132,26 @@ pub fn read_preview_lines<P: AsRef<Path>>(
         hl_line,
     ))
 }
+
+/// Returns the size in a human readable format, e.g., `1.5K`, `3.2M`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}{}", bytes, UNITS[0])
+    } else {
+        format!("{:.1}{}", size, UNITS[unit])
+    }
+}
+
+#[test]
+fn test_human_size() {
+    assert_eq!(human_size(42), "42B");
+    assert_eq!(human_size(1536), "1.5K");
+    assert_eq!(human_size(3 * 1024 * 1024), "3.0M");
+}
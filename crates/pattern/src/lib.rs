@@ -12,12 +12,34 @@ lazy_static! {
 
   // match the tag_name:lnum of tag line.
   static ref TAG_RE: Regex = Regex::new(r"^(.*:\d+)").unwrap();
+
+  // match the lnum of tag line.
+  static ref TAG_LNUM: Regex = Regex::new(r"^.*?:(\d+)(\s|$)").unwrap();
+
+  // match the leading lnum of blines line.
+  static ref BLINES_LNUM: Regex = Regex::new(r"^\s*(\d+) ").unwrap();
+
+  // match the fpath, lnum and col of quickfix line, e.g., src/lib.rs|10 col 5| text.
+  static ref QUICKFIX_POS: Regex = Regex::new(r"^(.*?)\|(\d+) col (\d+)\|").unwrap();
+
+  // match the bufnr, lnum and name of buffers line, e.g., [3]  1.2K line 42 src/lib.rs % [+].
+  static ref BUFFERS_POS: Regex =
+      Regex::new(r"^\[(\d+)\].*?line (\d+)\s+(.+?)(\s+[%#])?(\s+\[\+\])?(\s+\[RO\])?$").unwrap();
+
+  // match the abbreviated commit hash of commits line.
+  static ref COMMIT_REV: Regex = Regex::new(r"\b([0-9a-f]{7,40})\b").unwrap();
+}
+
+/// Returns the leading field of the tag line delimited by tab, the whole line if no tab.
+#[inline]
+fn tag_name_field(line: &str) -> &str {
+    line.split('\t').next().unwrap_or(line)
 }
 
 /// Extract tag name from the line in tags provider.
 #[inline]
 pub fn tag_name_only(line: &str) -> Option<&str> {
-    TAG_RE.find(line).map(|x| x.as_str())
+    TAG_RE.find(tag_name_field(line)).map(|x| x.as_str())
 }
 
 /// Returns the line content only and offset in the raw line.
@@ -57,6 +79,48 @@ pub fn extract_fpath_from_grep_line(line: &str) -> Option<&str> {
         .and_then(|cap| cap.get(1).map(|x| x.as_str()))
 }
 
+/// Returns the line number of the tag line, e.g., `Exec:10\t[struct]\tpub struct Exec {`.
+pub fn extract_tag_lnum(line: &str) -> Option<usize> {
+    TAG_LNUM
+        .captures(tag_name_field(line))
+        .and_then(|cap| cap.get(1))
+        .and_then(|x| x.as_str().parse().ok())
+}
+
+/// Returns the line number of the line in blines provider.
+pub fn extract_blines_lnum(line: &str) -> Option<usize> {
+    BLINES_LNUM
+        .captures(line)
+        .and_then(|cap| cap.get(1))
+        .and_then(|x| x.as_str().parse().ok())
+}
+
+/// Returns a tuple of (fpath, lnum, col) of the line in quickfix/loclist provider.
+pub fn extract_quickfix_position(line: &str) -> Option<(PathBuf, usize, usize)> {
+    let cap = QUICKFIX_POS.captures(line)?;
+    let fpath = cap.get(1)?.as_str().into();
+    let lnum = cap.get(2)?.as_str().parse().ok()?;
+    let col = cap.get(3)?.as_str().parse().ok()?;
+    Some((fpath, lnum, col))
+}
+
+/// Returns a tuple of (bufnr, lnum, bufname) of the line in buffers provider.
+pub fn extract_buffer_position(line: &str) -> Option<(usize, usize, &str)> {
+    let cap = BUFFERS_POS.captures(line)?;
+    let bufnr = cap.get(1)?.as_str().parse().ok()?;
+    let lnum = cap.get(2)?.as_str().parse().ok()?;
+    let bufname = cap.get(3)?.as_str();
+    Some((bufnr, lnum, bufname))
+}
+
+/// Returns the commit hash of the line in commits provider.
+pub fn extract_commit_rev(line: &str) -> Option<&str> {
+    COMMIT_REV
+        .captures(line)
+        .and_then(|cap| cap.get(1))
+        .map(|x| x.as_str())
+}
+
 /// Returns the file name of files entry.
 #[inline]
 pub fn file_name_only(line: &str) -> Option<(&str, usize)> {
@@ -76,5 +140,29 @@ mod tests {
         let line = "<Backspace>:60       [map]           inoremap <silent> <buffer> <Backspace> <C-R>=clap#handler#bs_action()<CR>  ftplugin/clap_input.vim";
         let mat = TAG_RE.find(line);
         assert_eq!(mat.unwrap().as_str(), "<Backspace>:60");
+
+        let line =
+            "operator []:42\t[function@a.hpp]\tT& operator [](size_t i) { return x ? a:1 : b; }";
+        assert_eq!(tag_name_only(line), Some("operator []:42"));
+        assert_eq!(extract_tag_lnum(line), Some(42));
+    }
+
+    #[test]
+    fn test_extract_positions() {
+        let line = "Exec:10                       \t[struct@crates/maple_cli/src/cmd/exec.rs]\tpub struct Exec {";
+        assert_eq!(extract_tag_lnum(line), Some(10));
+        assert_eq!(extract_blines_lnum("  42 let x = 1;"), Some(42));
+        assert_eq!(
+            extract_quickfix_position("src/lib.rs|10 col 5| unused variable"),
+            Some(("src/lib.rs".into(), 10, 5))
+        );
+        assert_eq!(
+            extract_buffer_position("[3]  1.2K   line 42    src/lib.rs % [+]"),
+            Some((3, 42, "src/lib.rs"))
+        );
+        assert_eq!(
+            extract_commit_rev("* 2020-05-25 8a3c1f2 (HEAD -> master) Fix typo (Jane)"),
+            Some("8a3c1f2")
+        );
     }
 }
@@ -1,3 +1,4 @@
+use serde::Serialize;
 use std::collections::HashMap;
 
 pub const DOTS: &str = "..";
@@ -16,8 +17,9 @@ pub type VimLineNumber = usize;
 ///
 pub type LinesTruncatedMap = HashMap<VimLineNumber, String>;
 
-/// Tuple of (matched line text, filtering score, indices of matched elements)
-pub type FuzzyMatchedLineInfo = (String, i64, Vec<usize>);
+/// Tuple of (matched line text, filtering score, indices of matched elements, index of the line
+/// in the source)
+pub type FuzzyMatchedLineInfo = (String, i64, Vec<usize>, usize);
 
 // https://stackoverflow.com/questions/51982999/slice-a-string-containing-unicode-chars
 #[inline]
@@ -100,12 +102,167 @@ pub fn truncate_long_matched_lines<T>(
     (lines, truncated_map)
 }
 
+/// Version of the output schema of the filtered items, specified by `version` in the requests.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OutputVersion {
+    /// The parallel arrays `lines` and `indices` with `truncated_map` keyed by the Vim line number.
+    V1,
+    /// A list of [`MatchedItem`] in `items`.
+    V2,
+}
+
+impl OutputVersion {
+    pub fn from_u64(version: u64) -> Option<Self> {
+        match version {
+            1 => Some(Self::V1),
+            2 => Some(Self::V2),
+            _ => None,
+        }
+    }
+}
+
+/// A filtered item of the output schema version 2.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MatchedItem {
+    /// Original text of the item.
+    pub text: String,
+    /// Text displayed in the window, which is truncated and prefixed with the icon if any.
+    pub display: String,
+    pub score: i64,
+    /// Indices of matched elements in `display`.
+    pub indices: Vec<usize>,
+    /// Byte offset of `text` from which `display` is truncated, `None` if it's not truncated.
+    pub truncated_from: Option<usize>,
+    pub icon: Option<char>,
+    /// Index of the item in the source, e.g., the 0-based line number of the input stream.
+    pub source_index: usize,
+}
+
+impl MatchedItem {
+    /// Prepends `icon` to the displayed text.
+    pub fn with_icon(mut self, icon: char) -> Self {
+        let prefix = format!("{} ", icon);
+        self.display = format!("{}{}", prefix, self.display);
+        self.indices = self.indices.iter().map(|x| x + prefix.len()).collect();
+        self.icon = Some(icon);
+        self
+    }
+}
+
+/// Same with [`truncate_long_matched_lines`], but returns the [`MatchedItem`]s.
+pub fn truncate_into_matched_items(
+    lines: impl IntoIterator<Item = FuzzyMatchedLineInfo>,
+    winwidth: usize,
+    skipped: Option<usize>,
+) -> Vec<MatchedItem> {
+    let lines = lines
+        .into_iter()
+        .map(|(text, score, indices, source_index)| (text, (score, source_index), indices));
+    let (truncated_lines, mut truncated_map) =
+        truncate_long_matched_lines(lines, winwidth, skipped);
+    truncated_lines
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (display, (score, source_index), indices))| {
+            match truncated_map.remove(&(idx + 1)) {
+                Some(text) => {
+                    // The truncated display is `{skipped}..{suffix of text}`.
+                    let skipped_len = display
+                        .chars()
+                        .take(skipped.unwrap_or(0))
+                        .map(char::len_utf8)
+                        .sum::<usize>();
+                    let suffix_len = display.len() - skipped_len - DOTS.len();
+                    MatchedItem {
+                        truncated_from: Some(text.len() - suffix_len),
+                        text,
+                        display,
+                        score,
+                        indices,
+                        icon: None,
+                        source_index,
+                    }
+                }
+                None => MatchedItem {
+                    text: display.clone(),
+                    display,
+                    score,
+                    indices,
+                    truncated_from: None,
+                    icon: None,
+                    source_index,
+                },
+            }
+        })
+        .collect()
+}
+
+/// Converts the items back to the output schema version 1, i.e., `(lines, indices,
+/// truncated_map)`.
+pub fn split_matched_items(
+    items: Vec<MatchedItem>,
+) -> (Vec<String>, Vec<Vec<usize>>, LinesTruncatedMap) {
+    let mut lines = Vec::with_capacity(items.len());
+    let mut indices = Vec::with_capacity(items.len());
+    let mut truncated_map = HashMap::new();
+    for (idx, item) in items.into_iter().enumerate() {
+        if item.truncated_from.is_some() {
+            truncated_map.insert(idx + 1, item.text);
+        }
+        lines.push(item.display);
+        indices.push(item.indices);
+    }
+    (lines, indices, truncated_map)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use fuzzy_filter::{Algo, Source};
     use rayon::prelude::*;
 
+    #[test]
+    fn test_truncate_into_matched_items() {
+        let long_line = format!("{}/file.rs", "nested/".repeat(20));
+        let lines = vec![
+            ("src/file.rs".to_string(), 10, vec![4, 5, 6], 3),
+            (long_line.clone(), 5, vec![long_line.len() - 7], 1),
+        ];
+        let items = truncate_into_matched_items(lines.clone(), 50, None);
+
+        assert_eq!(items[0].text, items[0].display);
+        assert_eq!(items[0].truncated_from, None);
+        assert_eq!(items[0].source_index, 3);
+
+        let item = &items[1];
+        assert_eq!(item.text, long_line);
+        let offset = item.truncated_from.unwrap();
+        assert_eq!(&item.display[DOTS.len()..], &long_line[offset..]);
+        // The indices are still pointing to the same chars.
+        assert_eq!(
+            &item.display[item.indices[0]..=item.indices[0]],
+            &long_line[long_line.len() - 7..=long_line.len() - 7]
+        );
+
+        let item = items[0].clone().with_icon('x');
+        assert_eq!(item.display, "x src/file.rs");
+        assert_eq!(&item.display[item.indices[0]..=item.indices[0]], "f");
+
+        let lines = lines
+            .into_iter()
+            .map(|(text, score, indices, _)| (text, score, indices));
+        let (truncated_lines, truncated_map) = truncate_long_matched_lines(lines, 50, None);
+        let (v1_lines, v1_indices, v1_map) = split_matched_items(items);
+        assert_eq!(v1_map, truncated_map);
+        assert_eq!(
+            truncated_lines
+                .into_iter()
+                .map(|(line, _, indices)| (line, indices))
+                .collect::<Vec<_>>(),
+            v1_lines.into_iter().zip(v1_indices).collect::<Vec<_>>()
+        );
+    }
+
     fn wrap_matches(line: &str, indices: &[usize]) -> String {
         let mut ret = String::new();
         let mut peekable = indices.iter().peekable();
@@ -141,7 +298,10 @@ mod tests {
         winwidth: usize,
     ) {
         let mut ranked = source.fuzzy_filter(Algo::Fzy, query).unwrap();
-        ranked.par_sort_unstable_by(|(_, v1, _), (_, v2, _)| v2.partial_cmp(&v1).unwrap());
+        ranked.par_sort_unstable_by(|(_, v1, _, _), (_, v2, _, _)| v2.partial_cmp(&v1).unwrap());
+        let ranked = ranked
+            .into_iter()
+            .map(|(text, score, indices, _)| (text, score, indices));
 
         println!("");
         println!("query: {:?}", query);
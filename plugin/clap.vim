@@ -30,6 +30,13 @@ function! s:OnBufDelete(bufnr) abort
   if has_key(g:__clap_buffers, a:bufnr)
     call remove(g:__clap_buffers, a:bufnr)
   endif
+  " The buffer is only kept in the daemon once the blines or lines provider is used.
+  if exists('*clap#provider#blines#on_buf_delete')
+    call clap#provider#blines#on_buf_delete(a:bufnr)
+  endif
+  if exists('*clap#provider#lines#on_buf_delete')
+    call clap#provider#lines#on_buf_delete(a:bufnr)
+  endif
   let tabpagenr = tabpagenr()
   if has_key(g:__clap_tab_buffers, tabpagenr)
     let idx = index(g:__clap_tab_buffers[tabpagenr], a:bufnr)
@@ -4,6 +4,12 @@ syntax match ClapLinesBufname / \f*\.\f* / nextgroup=ClapLinesNumber
 
 syntax match ClapLinesNumber / \d\+ /
 
+" `{bufname}:{lnum}:{line}` filtered by maple.
+syntax match ClapLinesPath /^\%(\[No Name \d\+\]\|[^:[]\+\):\ze\d\+:/ nextgroup=ClapLinesLnum
+syntax match ClapLinesLnum /\d\+:/ contained
+
 hi default link ClapLinesBufnr   Function
 hi default link ClapLinesBufname Type
 hi default link ClapLinesNumber  Number
+hi default link ClapLinesPath    Type
+hi default link ClapLinesLnum    Number
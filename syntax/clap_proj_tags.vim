@@ -1,14 +1,16 @@
-syntax match ClapProjTagLnum /:\zs\d\+/ contained
-syntax match ClapProjTagName /^\(.*\):\d\+.*\ze\[.*@.*\]/ contains=ClapProjTagLnum
+syntax match ClapProjTagLnum /:\zs\d\+\ze\s*\t/ contained
+syntax match ClapProjTagName /^[^\t]*\t/ contains=ClapProjTagLnum
 syntax match ClapProjTagKindPathSeperator /@/ contained
-syntax match ClapProjTagBrackets /\[\|\]/ contained
-syntax match ClapProjTagKind   /\[\zs.*\ze@\f*\]/ contained
-syntax match ClapProjTagPath /\[.*@\f*\]/ contains=ClapProjTagKind,ClapProjTagKindPathSeperator
-syntax match ClapProjTagPattern /^.*$/ contains=ClapTagName,ClapProjTagKind,ClapProjTagPath,ClapProjTagLnum
+syntax match ClapProjTagBrackets /\t\zs\[\|\]\ze\s*\t/ contained
+syntax match ClapProjTagKind   /\t\[\zs[^@\t]*\ze@/ contained
+syntax match ClapProjTagPath /\t\[[^\t]*\]\s*\t/ contains=ClapProjTagKind,ClapProjTagKindPathSeperator,ClapProjTagBrackets
+syntax match ClapProjTagScope /\(\]\s*\t\)\@<=[^\t]\+\ze\t/ contained
+syntax match ClapProjTagPattern /^.*$/ contains=ClapProjTagName,ClapProjTagKind,ClapProjTagPath,ClapProjTagLnum,ClapProjTagScope
 
 hi default link ClapProjTagName Type
 hi default link ClapProjTagKind Function
 hi default link ClapProjTagPath Directory
+hi default link ClapProjTagScope Special
 hi default link ClapProjTagPattern Identifier
 hi default link ClapProjTagKindPathSeperator String
 hi default link ClapProjTagLnum Number